@@ -31,6 +31,9 @@ else
     if [ "$NO_TRANSPARENT_BACKGROUND" = "true" ]; then
         CMD="$CMD --no-transparent-background"
     fi
+    if [ ! -z "$JOBS" ]; then
+        CMD="$CMD --jobs $JOBS"
+    fi
     eval $CMD
 
     if [ ! -z "$HOST_UID" ] && [ ! -z "$HOST_GID" ]; then
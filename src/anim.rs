@@ -0,0 +1,135 @@
+use anyhow::{bail, Context, Result};
+use image::RgbaImage;
+use image_webp::{ColorType, WebPEncoder};
+use png::{BlendOp, DisposeOp};
+
+/// Encodes frames of equal size as an animated PNG, each shown for
+/// `delay_ms`. `looping` repeats it forever, otherwise it plays once.
+pub fn encode_apng(frames: &[RgbaImage], delay_ms: u16, looping: bool) -> Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        bail!("Animation has no frames");
+    };
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, first.width(), first.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, if looping { 0 } else { 1 })?;
+    encoder.set_frame_delay(delay_ms, 1000)?;
+    encoder.set_dispose_op(DisposeOp::Background)?;
+    encoder.set_blend_op(BlendOp::Source)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer
+            .write_image_data(frame.as_raw())
+            .context("Failed to write APNG frame")?;
+    }
+    writer.finish()?;
+    Ok(data)
+}
+
+/// Encodes frames of equal size as a lossless animated WebP. The frames
+/// are encoded as still images and their `VP8L` data wrapped in `ANMF`
+/// chunks.
+pub fn encode_webp(frames: &[RgbaImage], delay_ms: u16, looping: bool) -> Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        bail!("Animation has no frames");
+    };
+    let (width, height) = first.dimensions();
+
+    let mut chunks = Vec::new();
+    let mut vp8x = vec![0x12, 0, 0, 0]; // animation and alpha
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+    push_chunk(&mut chunks, b"VP8X", &vp8x);
+    let mut anim = vec![0; 4]; // transparent background
+    anim.extend_from_slice(&(if looping { 0u16 } else { 1 }).to_le_bytes());
+    push_chunk(&mut chunks, b"ANIM", &anim);
+
+    for frame in frames {
+        let mut still = Vec::new();
+        WebPEncoder::new(&mut still)
+            .encode(frame.as_raw(), width, height, ColorType::Rgba8)
+            .context("Failed to encode WebP frame")?;
+        // RIFF header and the VP8L chunk header come first
+        let len = u32::from_le_bytes(still[16..20].try_into()?) as usize;
+        let bitstream = still.get(20..20 + len).context("Truncated WebP frame")?;
+
+        let mut anmf = Vec::with_capacity(24 + bitstream.len());
+        anmf.extend_from_slice(&u24(0)); // x / 2
+        anmf.extend_from_slice(&u24(0)); // y / 2
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(delay_ms as u32));
+        anmf.push(0x03); // no blending, dispose to background
+        push_chunk(&mut anmf, b"VP8L", bitstream);
+        push_chunk(&mut chunks, b"ANMF", &anmf);
+    }
+
+    let mut data = b"RIFF".to_vec();
+    data.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+    data.extend_from_slice(b"WEBP");
+    data.extend_from_slice(&chunks);
+    Ok(data)
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+fn push_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use image_webp::{LoopCount, WebPDecoder};
+    use std::io::Cursor;
+
+    fn frames() -> Vec<RgbaImage> {
+        vec![
+            RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 255])),
+            RgbaImage::from_pixel(3, 2, Rgba([0, 0, 255, 128])),
+        ]
+    }
+
+    #[test]
+    fn encodes_animated_png() {
+        let data = encode_apng(&frames(), 66, false).unwrap();
+        let mut reader = png::Decoder::new(Cursor::new(data)).read_info().unwrap();
+        let control = *reader.info().animation_control().unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (2, 1));
+        let mut buf = vec![0; 3 * 2 * 4];
+        reader.next_frame(&mut buf).unwrap();
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(buf[..4], [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn encodes_animated_webp() {
+        let data = encode_webp(&frames(), 66, true).unwrap();
+        let mut decoder = WebPDecoder::new(Cursor::new(data)).unwrap();
+        assert!(decoder.is_animated());
+        assert_eq!(decoder.dimensions(), (3, 2));
+        assert_eq!(decoder.num_frames(), 2);
+        assert!(matches!(decoder.loop_count(), LoopCount::Forever));
+        let mut buf = vec![0; 3 * 2 * 4];
+        assert_eq!(decoder.read_frame(&mut buf).unwrap(), 66);
+        assert_eq!(buf[..4], [255, 0, 0, 255]);
+        decoder.read_frame(&mut buf).unwrap();
+        assert_eq!(buf[..4], [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn rejects_animations_without_frames() {
+        assert!(encode_apng(&[], 66, true).is_err());
+        assert!(encode_webp(&[], 66, true).is_err());
+    }
+}
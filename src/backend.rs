@@ -0,0 +1,478 @@
+//! How the movies' assets get exported. A [`GameExtractor`] only says what
+//! a game needs; the [`ExtractorBackend`] decides how each movie is run:
+//! `dir_extractor.exe` under Wine or straight on Windows, cgex's own
+//! Director parser, or a replay of a snapshot an earlier run left.
+
+use crate::game_extractor::{self, GameExtractor};
+use crate::native::{self, ExportOptions, MovieAssets};
+use crate::report::MovieReport;
+use crate::watchdog;
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub trait ExtractorBackend: Send + Sync {
+    fn name(&self) -> &str;
+    /// Checks that what the backend runs is there, before the disc is
+    /// copied.
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Adds what the backend needs to the temp directory, after the disc is
+    /// copied to it and before the game prepares it.
+    fn prepare(&self, _temp_dir: &Path) -> Result<()> {
+        Ok(())
+    }
+    /// Exports the assets of `movie`, a movie at the top of `temp_dir`, to
+    /// `movie--cast__member` files there. Backends that decode the movie
+    /// themselves return its bitmaps and manifest entries instead.
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        game: &dyn GameExtractor,
+        options: ExportOptions,
+    ) -> Result<MovieAssets>;
+    /// Whether [`extract_movie`](Self::extract_movie) lists every member
+    /// it exports, for `manifest.json`. The members of other backends are
+    /// described from the movies after they are extracted.
+    fn describes_members(&self) -> bool {
+        false
+    }
+    /// Starts a worker that extracts movies alongside others for `--jobs`,
+    /// each in its own copy of the temp directory. `dir` is the worker's
+    /// to keep things in, and is removed after it. Backends that can only
+    /// run one at a time return `None` and get the movies one by one.
+    fn worker(&self, _dir: &Path) -> Result<Option<Box<dyn ExtractorBackend>>> {
+        Ok(None)
+    }
+}
+
+/// The backend for the platform cgex was built for: Windows on Windows,
+/// Wine everywhere else.
+pub fn default_backend(extractor_tools: impl Into<PathBuf>) -> Box<dyn ExtractorBackend> {
+    if cfg!(target_os = "windows") {
+        Box::new(WindowsBackend::new(extractor_tools))
+    } else {
+        Box::new(WineBackend::new(extractor_tools))
+    }
+}
+
+/// How long a movie may take under Wine by default.
+pub const DEFAULT_MOVIE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Runs `dir_extractor.exe` under Wine.
+pub struct WineBackend {
+    extractor_tools: PathBuf,
+    movie_timeout: Duration,
+    /// The display the extractor runs on when Xvfb is installed, started
+    /// with the first movie. Otherwise it runs on the user's.
+    display: OnceLock<Option<Xvfb>>,
+}
+
+impl WineBackend {
+    /// `extractor_tools` holds `dir_extractor.exe` and its `Xtras`.
+    pub fn new(extractor_tools: impl Into<PathBuf>) -> Self {
+        WineBackend {
+            extractor_tools: extractor_tools.into(),
+            movie_timeout: DEFAULT_MOVIE_TIMEOUT,
+            display: OnceLock::new(),
+        }
+    }
+
+    /// Kills the extractor and everything it started when a movie takes
+    /// longer than `timeout`, and goes on with the next one.
+    pub fn movie_timeout(mut self, timeout: Duration) -> Self {
+        self.movie_timeout = timeout;
+        self
+    }
+}
+
+impl ExtractorBackend for WineBackend {
+    fn name(&self) -> &str {
+        "wine"
+    }
+
+    fn check(&self) -> Result<()> {
+        let output = Command::new("wine")
+            .arg("--version")
+            .output()
+            .context("Failed to execute 'wine --version'. Is Wine installed and in your PATH?")?;
+
+        if !output.status.success() {
+            bail!("Wine is not properly installed or configured. Please install Wine and ensure it's in your PATH.");
+        }
+        watchdog::check_tools();
+
+        Ok(())
+    }
+
+    fn prepare(&self, temp_dir: &Path) -> Result<()> {
+        copy_extractor_tools(&self.extractor_tools, temp_dir)
+    }
+
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        _game: &dyn GameExtractor,
+        _options: ExportOptions,
+    ) -> Result<MovieAssets> {
+        let display = self.display.get_or_init(|| {
+            if !watchdog::is_installed("Xvfb") {
+                return None;
+            }
+            Xvfb::start()
+                .inspect_err(|e| println!("Warning: {:#}, running on the current display", e))
+                .ok()
+        });
+        let display = display.as_ref().map(|display| display.name.as_str());
+        let report = run_wine(temp_dir, movie, self.movie_timeout, None, display)?;
+        Ok(MovieAssets {
+            reports: vec![report],
+            ..Default::default()
+        })
+    }
+
+    /// Each worker runs Wine in a clone of the prefix, `WINEPREFIX` or
+    /// `~/.wine`, and on an Xvfb display of its own, so the extractors
+    /// don't share a wineserver or each other's dialogs.
+    fn worker(&self, dir: &Path) -> Result<Option<Box<dyn ExtractorBackend>>> {
+        let prefix = dir.join("wineprefix");
+        let source = env::var_os("WINEPREFIX")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".wine")));
+        // Without one, Wine sets up a fresh prefix on the first run
+        if let Some(source) = source.filter(|source| source.is_dir()) {
+            // cp keeps the drive symlinks, z: would otherwise pull in /
+            let status = Command::new("cp")
+                .arg("-a")
+                .arg(&source)
+                .arg(&prefix)
+                .status()
+                .context("Failed to run cp to clone the Wine prefix")?;
+            if !status.success() {
+                bail!("Failed to clone Wine prefix {:?}", source);
+            }
+        }
+        let display = Xvfb::start().context("--jobs needs an Xvfb display for each worker")?;
+        Ok(Some(Box::new(WineWorker {
+            prefix,
+            display,
+            movie_timeout: self.movie_timeout,
+        })))
+    }
+}
+
+/// A [`WineBackend`] worker, see [`WineBackend::worker`].
+struct WineWorker {
+    prefix: PathBuf,
+    display: Xvfb,
+    movie_timeout: Duration,
+}
+
+impl ExtractorBackend for WineWorker {
+    fn name(&self) -> &str {
+        "wine"
+    }
+
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        _game: &dyn GameExtractor,
+        _options: ExportOptions,
+    ) -> Result<MovieAssets> {
+        let report = run_wine(
+            temp_dir,
+            movie,
+            self.movie_timeout,
+            Some(&self.prefix),
+            Some(&self.display.name),
+        )?;
+        Ok(MovieAssets {
+            reports: vec![report],
+            ..Default::default()
+        })
+    }
+}
+
+impl Drop for WineWorker {
+    fn drop(&mut self) {
+        // The prefix's wineserver outlives the last process for a while
+        let _ = Command::new("wineserver")
+            .arg("-k")
+            .env("WINEPREFIX", &self.prefix)
+            .status();
+    }
+}
+
+/// A virtual X server, stopped when dropped.
+struct Xvfb {
+    process: Child,
+    /// The display, e.g. `:1`.
+    name: String,
+}
+
+impl Xvfb {
+    fn start() -> Result<Xvfb> {
+        // Xvfb picks a free display and prints its number once it is ready
+        let mut process = Command::new("Xvfb")
+            .args([
+                "-displayfd",
+                "1",
+                "-screen",
+                "0",
+                "1024x768x24",
+                "-nolisten",
+                "tcp",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start Xvfb. Is it installed?")?;
+        let mut line = String::new();
+        BufReader::new(process.stdout.take().unwrap()).read_line(&mut line)?;
+        let number = line.trim();
+        if number.is_empty() {
+            let _ = process.kill();
+            let _ = process.wait();
+            bail!("Xvfb exited without opening a display");
+        }
+        Ok(Xvfb {
+            name: format!(":{}", number),
+            process,
+        })
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Runs `dir_extractor.exe` directly, on Windows.
+pub struct WindowsBackend {
+    extractor_tools: PathBuf,
+}
+
+impl WindowsBackend {
+    /// `extractor_tools` holds `dir_extractor.exe` and its `Xtras`.
+    pub fn new(extractor_tools: impl Into<PathBuf>) -> Self {
+        WindowsBackend {
+            extractor_tools: extractor_tools.into(),
+        }
+    }
+}
+
+impl ExtractorBackend for WindowsBackend {
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn prepare(&self, temp_dir: &Path) -> Result<()> {
+        copy_extractor_tools(&self.extractor_tools, temp_dir)
+    }
+
+    /// Director Player Error dialogs wait for a click here; the extractor
+    /// carries on once they are closed.
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        _game: &dyn GameExtractor,
+        _options: ExportOptions,
+    ) -> Result<MovieAssets> {
+        let output = Command::new(temp_dir.join("dir_extractor.exe"))
+            .arg(movie_name(movie)?)
+            .current_dir(temp_dir)
+            .output()
+            .context("Failed to run extractor on Windows")?;
+        Ok(MovieAssets {
+            reports: vec![MovieReport::new(temp_dir, movie, &output, &[], false)],
+            ..Default::default()
+        })
+    }
+}
+
+/// Parses the movies with cgex's own Director reader; see [`native`].
+pub struct NativeBackend;
+
+impl ExtractorBackend for NativeBackend {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        _game: &dyn GameExtractor,
+        options: ExportOptions,
+    ) -> Result<MovieAssets> {
+        native::extract_movie(temp_dir, movie, options)
+    }
+
+    fn describes_members(&self) -> bool {
+        true
+    }
+}
+
+/// Takes the exported files from a snapshot of the temp directory that an
+/// earlier Wine or Windows run saved, to redo the rest of the pipeline
+/// without running the extractor again.
+pub struct ReplayBackend {
+    snapshot: PathBuf,
+}
+
+impl ReplayBackend {
+    pub fn new(snapshot: impl Into<PathBuf>) -> Self {
+        ReplayBackend {
+            snapshot: snapshot.into(),
+        }
+    }
+}
+
+impl ExtractorBackend for ReplayBackend {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn check(&self) -> Result<()> {
+        if !self.snapshot.is_dir() {
+            bail!("Snapshot directory {:?} does not exist", self.snapshot);
+        }
+        Ok(())
+    }
+
+    /// Copies the files exported from `movie` out of the snapshot.
+    fn extract_movie(
+        &self,
+        temp_dir: &Path,
+        movie: &Path,
+        _game: &dyn GameExtractor,
+        _options: ExportOptions,
+    ) -> Result<MovieAssets> {
+        // Exported files are named after the movie without its extension
+        let name = movie_name(movie)?;
+        let prefix = format!("{}--", name.split('.').next().unwrap_or_default());
+        let mut exported = false;
+        for entry in fs::read_dir(&self.snapshot)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file()
+                && entry.file_name().to_string_lossy().starts_with(&prefix)
+            {
+                fs::copy(entry.path(), temp_dir.join(entry.file_name()))
+                    .with_context(|| format!("Failed to copy {:?} from snapshot", entry.path()))?;
+                exported = true;
+            }
+        }
+        if !exported {
+            println!("Warning: Snapshot has no files exported from {:?}", name);
+        }
+        Ok(MovieAssets::default())
+    }
+}
+
+/// Runs `dir_extractor.exe` on a movie under Wine, in the given prefix and
+/// on the given private display instead of the user's if there are. Director
+/// Player Error dialogs are dismissed and reported as warnings, and a
+/// movie taking longer than `timeout` is given up on with a warning too.
+/// Returns what the run left behind, for the extraction report.
+fn run_wine(
+    temp_dir: &Path,
+    movie: &Path,
+    timeout: Duration,
+    prefix: Option<&Path>,
+    display: Option<&str>,
+) -> Result<MovieReport> {
+    let name = movie_name(movie)?;
+    let mut command = Command::new("wine");
+    command.arg("dir_extractor.exe").arg(name);
+    command.current_dir(temp_dir);
+    if let Some(prefix) = prefix {
+        command.env("WINEPREFIX", prefix);
+    }
+    if let Some(display) = display {
+        command.env("DISPLAY", display);
+    }
+    let watched = watchdog::run(command, name, display, timeout)?;
+    for error in &watched.player_errors {
+        println!("Warning: {}", error);
+    }
+    if watched.timed_out {
+        println!(
+            "Warning: Killed the extractor after {} seconds on {:?}",
+            timeout.as_secs(),
+            name
+        );
+    }
+    Ok(MovieReport::new(
+        temp_dir,
+        movie,
+        &watched.output,
+        &watched.player_errors,
+        watched.timed_out,
+    ))
+}
+
+fn movie_name(movie: &Path) -> Result<&str> {
+    movie
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Invalid movie file name")
+}
+
+fn copy_extractor_tools(extractor_tools: &Path, temp_dir: &Path) -> Result<()> {
+    fs::copy(
+        extractor_tools.join("dir_extractor.exe"),
+        temp_dir.join("dir_extractor.exe"),
+    )
+    .context("Failed to copy dir_extractor.exe")?;
+
+    game_extractor::copy_directory(&extractor_tools.join("Xtras"), &temp_dir.join("Xtras"))
+        .context("Failed to copy Xtras folder")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile;
+
+    #[test]
+    fn replays_a_movies_files_from_a_snapshot() {
+        let dir = env::temp_dir().join(format!("cgex_test_replay_{}", std::process::id()));
+        let (snapshot, temp_dir) = (dir.join("snapshot"), dir.join("temp"));
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::create_dir_all(&temp_dir).unwrap();
+        for name in ["02--1__Car.bmp", "020--1__Boat.bmp", "03--1__Map.bmp"] {
+            fs::write(snapshot.join(name), name).unwrap();
+        }
+        let backend = ReplayBackend::new(&snapshot);
+        backend.check().unwrap();
+        let game = &profile::builtin_profiles()[0];
+        backend
+            .extract_movie(
+                &temp_dir,
+                &temp_dir.join("02.DXR"),
+                game,
+                ExportOptions::default(),
+            )
+            .unwrap();
+        let copied: Vec<_> = fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(copied, ["02--1__Car.bmp"]);
+
+        assert!(ReplayBackend::new(dir.join("missing")).check().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! Afterburner (Shockwave) compressed movies, as written by "Save and
+//! Compact" for `.dxr`/`.dcr` movies and `.cct` casts.
+//!
+//! Instead of an `mmap`, these files carry an `ABMP` resource map and an
+//! `FGEI` section. Most resources are zlib-compressed, and the small ones
+//! are packed together into the initial load segment (ILS, resource 2).
+
+use super::{Chunk, Endian, FourCC, Reader};
+use anyhow::{bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+
+const FVER: FourCC = FourCC::new(b"Fver");
+const FCDR: FourCC = FourCC::new(b"Fcdr");
+const ABMP: FourCC = FourCC::new(b"ABMP");
+const FGEI: FourCC = FourCC::new(b"FGEI");
+
+/// Resource id of the initial load segment.
+const ILS_ID: u32 = 2;
+/// First part of the Moa GUID for "Standard Macromedia zlib compression".
+const ZLIB_GUID_DATA1: u32 = 0xAC99_E904;
+
+struct Resource {
+    id: u32,
+    offset: i32,
+    comp_size: usize,
+    uncomp_size: usize,
+    compression: usize,
+    fourcc: FourCC,
+}
+
+/// Reads every resource of an Afterburner file into the same chunk map an
+/// uncompressed movie's `mmap` produces. `reader` must be positioned just
+/// after the `FGDM`/`FGDC` codec tag.
+pub fn read_chunks(reader: &mut Reader, endian: Endian) -> Result<HashMap<u32, Chunk>> {
+    if reader.read_fourcc()? != FVER {
+        bail!("Expected Fver chunk in Afterburner header");
+    }
+    let fver_len = reader.read_varint()? as usize;
+    reader.skip(fver_len)?;
+
+    if reader.read_fourcc()? != FCDR {
+        bail!("Expected Fcdr chunk in Afterburner header");
+    }
+    let fcdr_len = reader.read_varint()? as usize;
+    let fcdr = inflate(reader.read_bytes(fcdr_len)?).context("Failed to inflate Fcdr")?;
+    let zlib_types = read_compression_types(&fcdr, endian)?;
+
+    if reader.read_fourcc()? != ABMP {
+        bail!("Expected ABMP chunk in Afterburner header");
+    }
+    let abmp_len = reader.read_varint()? as usize;
+    let abmp_end = reader.pos() + abmp_len;
+    reader.read_varint()?; // compression type of the map itself
+    reader.read_varint()?; // uncompressed length
+    let abmp_data = reader.read_bytes(abmp_end.saturating_sub(reader.pos()))?;
+    let abmp = inflate(abmp_data).context("Failed to inflate ABMP")?;
+    let resources = read_resource_map(&abmp, endian)?;
+
+    if reader.read_fourcc()? != FGEI {
+        bail!("Expected FGEI chunk after ABMP");
+    }
+    reader.read_varint()?;
+    let body_offset = reader.pos();
+
+    let mut chunks = HashMap::new();
+    if let Some(ils) = resources.iter().find(|res| res.id == ILS_ID) {
+        reader.seek(body_offset)?;
+        let ils_data = inflate(reader.read_bytes(ils.comp_size)?)
+            .context("Failed to inflate initial load segment")?;
+        let mut ils_reader = Reader::new(&ils_data, endian);
+        while !ils_reader.eof() {
+            let id = ils_reader.read_varint()?;
+            let Some(res) = resources.iter().find(|res| res.id == id) else {
+                bail!("Initial load segment contains unknown resource {}", id);
+            };
+            let data = ils_reader.read_bytes(res.comp_size)?.to_vec();
+            chunks.insert(
+                id,
+                Chunk {
+                    fourcc: res.fourcc,
+                    data,
+                },
+            );
+        }
+    }
+
+    for res in &resources {
+        if res.id == ILS_ID || res.offset < 0 || chunks.contains_key(&res.id) {
+            continue;
+        }
+        reader.seek(body_offset + res.offset as usize)?;
+        let raw = reader
+            .read_bytes(res.comp_size)
+            .with_context(|| format!("Resource {} ({}) is truncated", res.id, res.fourcc))?;
+        // Whether a resource is compressed is down to its compression type
+        // alone; a compressed one can be as long as its uncompressed data
+        let data = if zlib_types.get(res.compression).copied().unwrap_or(false) && !raw.is_empty() {
+            let data = match inflate(raw) {
+                Ok(data) => data,
+                Err(err) => {
+                    println!(
+                        "Warning: Skipping resource {} ({}), it failed to inflate: {:#}",
+                        res.id, res.fourcc, err
+                    );
+                    continue;
+                }
+            };
+            if data.len() != res.uncomp_size {
+                println!(
+                    "Warning: Resource {} ({}) inflated to {} bytes instead of {}",
+                    res.id,
+                    res.fourcc,
+                    data.len(),
+                    res.uncomp_size
+                );
+            }
+            data
+        } else {
+            raw.to_vec()
+        };
+        chunks.insert(
+            res.id,
+            Chunk {
+                fourcc: res.fourcc,
+                data,
+            },
+        );
+    }
+    Ok(chunks)
+}
+
+/// Returns, per compression type index, whether the type is zlib. The
+/// other types (none, sound, font map) are stored as is.
+fn read_compression_types(fcdr: &[u8], endian: Endian) -> Result<Vec<bool>> {
+    let mut reader = Reader::new(fcdr, endian);
+    let count = reader.read_u16()? as usize;
+    let mut guids = Vec::with_capacity(count);
+    for _ in 0..count {
+        guids.push(reader.read_u32()?);
+        reader.skip(12)?;
+    }
+    let mut is_zlib = Vec::with_capacity(count);
+    for data1 in guids {
+        let mut description = Vec::new();
+        loop {
+            match reader.read_u8()? {
+                0 => break,
+                b => description.push(b),
+            }
+        }
+        let description = String::from_utf8_lossy(&description).to_lowercase();
+        is_zlib.push(data1 == ZLIB_GUID_DATA1 || description.contains("zlib"));
+    }
+    Ok(is_zlib)
+}
+
+fn read_resource_map(abmp: &[u8], endian: Endian) -> Result<Vec<Resource>> {
+    let mut reader = Reader::new(abmp, endian);
+    reader.read_varint()?;
+    reader.read_varint()?;
+    let count = reader.read_varint()? as usize;
+    // Entries are varints, so the count can't be checked against the size
+    let mut resources = Vec::new();
+    for _ in 0..count {
+        resources.push(Resource {
+            id: reader.read_varint()?,
+            offset: reader.read_varint()? as i32,
+            comp_size: reader.read_varint()? as usize,
+            uncomp_size: reader.read_varint()? as usize,
+            compression: reader.read_varint()? as usize,
+            fourcc: reader.read_fourcc()?,
+        });
+    }
+    Ok(resources)
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn varint(mut value: u32) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7f) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, (value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    /// A resource's id, tag, compression type, stored data and uncompressed
+    /// length.
+    type Stored<'a> = (u32, &'a [u8; 4], u32, Vec<u8>, usize);
+
+    /// A tag as `endian` files store it: little-endian ones reversed.
+    fn tag(tag: &[u8; 4], endian: Endian) -> [u8; 4] {
+        let mut bytes = *tag;
+        if endian == Endian::Little {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    /// Builds the part of an Afterburner file after its codec tag. Resource
+    /// 2 is the initial load segment.
+    fn afterburner(endian: Endian, resources: &[Stored]) -> Vec<u8> {
+        let number = |value: u32| match endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes(),
+        };
+        let mut fcdr = match endian {
+            Endian::Big => 2u16.to_be_bytes(),
+            Endian::Little => 2u16.to_le_bytes(),
+        }
+        .to_vec();
+        fcdr.extend(number(ZLIB_GUID_DATA1));
+        fcdr.extend([0; 12]);
+        fcdr.extend(number(0x8a46_79a1));
+        fcdr.extend([0; 12]);
+        fcdr.extend(b"Standard Macromedia zlib compression\0None\0");
+        let fcdr = deflate(&fcdr);
+
+        let mut abmp = [varint(0), varint(0), varint(resources.len() as u32)].concat();
+        let mut offset = 0;
+        for (id, tag_name, compression, data, uncomp_size) in resources {
+            for value in [
+                *id,
+                offset,
+                data.len() as u32,
+                *uncomp_size as u32,
+                *compression,
+            ] {
+                abmp.extend(varint(value));
+            }
+            abmp.extend(tag(tag_name, endian));
+            offset += data.len() as u32;
+        }
+        let abmp = deflate(&abmp);
+        let mut abmp_chunk = [varint(0), varint(0)].concat();
+        abmp_chunk.extend(abmp);
+
+        let mut file = tag(b"Fver", endian).to_vec();
+        file.extend(varint(1));
+        file.push(0);
+        file.extend(tag(b"Fcdr", endian));
+        file.extend(varint(fcdr.len() as u32));
+        file.extend(fcdr);
+        file.extend(tag(b"ABMP", endian));
+        file.extend(varint(abmp_chunk.len() as u32));
+        file.extend(abmp_chunk);
+        file.extend(tag(b"FGEI", endian));
+        file.extend(varint(0));
+        for (_, _, _, data, _) in resources {
+            file.extend(data);
+        }
+        file
+    }
+
+    #[test]
+    fn reads_resources_by_compression_type() {
+        // Windows movies (XFIR) are little-endian apart from their varints
+        for endian in [Endian::Big, Endian::Little] {
+            let key_table = b"key table".to_vec();
+            let mut ils = varint(3);
+            ils.extend(&key_table);
+            let cast_member = vec![7; 300];
+            // Compression type 1 is not zlib, so it is stored as is
+            let text = b"Hej".to_vec();
+            let data = afterburner(
+                endian,
+                &[
+                    (2, b"ILS ", 0, deflate(&ils), ils.len()),
+                    (3, b"KEY*", 0, key_table.clone(), key_table.len()),
+                    (4, b"CASt", 0, deflate(&cast_member), cast_member.len()),
+                    (5, b"STXT", 1, text.clone(), text.len()),
+                ],
+            );
+            let chunks = read_chunks(&mut Reader::new(&data, endian), endian).unwrap();
+            assert_eq!(chunks[&3].fourcc, FourCC::new(b"KEY*"));
+            assert_eq!(chunks[&3].data, key_table);
+            assert_eq!(chunks[&4].fourcc, FourCC::new(b"CASt"));
+            assert_eq!(chunks[&4].data, cast_member);
+            assert_eq!(chunks[&5].data, text);
+        }
+    }
+
+    #[test]
+    fn keeps_resources_of_the_wrong_size() {
+        let cast_member = vec![7; 300];
+        let data = afterburner(
+            Endian::Big,
+            &[
+                (4, b"CASt", 0, deflate(&cast_member), 200),
+                (5, b"CASt", 0, b"not zlib".to_vec(), 100),
+            ],
+        );
+        let chunks = read_chunks(&mut Reader::new(&data, Endian::Big), Endian::Big).unwrap();
+        assert_eq!(chunks[&4].data, cast_member);
+        assert!(!chunks.contains_key(&5));
+    }
+}
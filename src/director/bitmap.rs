@@ -0,0 +1,303 @@
+//! Bitmap cast members: the properties stored in `CASt` and the pixel data
+//! in the member's `BITD` chunk.
+
+use super::palette::Palette;
+use super::{Endian, Reader};
+use anyhow::{bail, Result};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteRef {
+    /// One of Director's built-in palettes, see
+    /// [`palette::builtin`](super::palette::builtin).
+    Builtin(i16),
+    /// A palette cast member. A cast library of 0 means the bitmap's own cast.
+    Member { cast_lib: i16, member: i16 },
+}
+
+pub struct BitmapInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Bytes per row of the unpacked pixel data.
+    pub pitch: usize,
+    pub bit_depth: u8,
+    pub palette: PaletteRef,
+    /// Registration point relative to the top left corner.
+    pub reg_x: i16,
+    pub reg_y: i16,
+}
+
+impl BitmapInfo {
+    /// Parses a bitmap's `CASt` data. `default_palette` is the built-in
+    /// palette of bitmaps that don't name one, see [`Movie::system_palette`].
+    ///
+    /// [`Movie::system_palette`]: super::Movie::system_palette
+    pub fn parse(data: &[u8], version: u16, default_palette: i16) -> Result<BitmapInfo> {
+        let mut reader = Reader::new(data, Endian::Big);
+        let raw_pitch = reader.read_u16()?;
+        let top = reader.read_u16()? as i16;
+        let left = reader.read_u16()? as i16;
+        let bottom = reader.read_u16()? as i16;
+        let right = reader.read_u16()? as i16;
+        let (reg_x, reg_y) = if data.len() >= 22 {
+            reader.seek(18)?;
+            let reg_y = reader.read_u16()? as i16;
+            (reader.read_u16()? as i16, reg_y)
+        } else {
+            (left, top)
+        };
+
+        let mut bit_depth = 1;
+        let mut palette = PaletteRef::Builtin(default_palette);
+        // The high bit marks colour bitmaps, which carry depth and palette
+        if raw_pitch & 0x8000 != 0 && data.len() >= 26 {
+            reader.seek(23)?;
+            bit_depth = reader.read_u8()?;
+            let cast_lib = if version >= 500 {
+                reader.read_u16()? as i16
+            } else {
+                0
+            };
+            let id = reader.read_u16()? as i16;
+            palette = if id <= 0 {
+                // Built-in palettes are stored off by one
+                match id.checked_sub(1) {
+                    Some(id) => PaletteRef::Builtin(id),
+                    None => bail!("Invalid bitmap palette id: {}", id),
+                }
+            } else {
+                PaletteRef::Member {
+                    cast_lib,
+                    member: id,
+                }
+            };
+        }
+
+        let width = i32::from(right) - i32::from(left);
+        let height = i32::from(bottom) - i32::from(top);
+        if width < 0 || height < 0 {
+            bail!(
+                "Invalid bitmap rect: {}, {}, {}, {}",
+                top,
+                left,
+                bottom,
+                right
+            );
+        }
+
+        Ok(BitmapInfo {
+            width: width as u32,
+            height: height as u32,
+            pitch: (raw_pitch & 0x3fff) as usize,
+            bit_depth,
+            palette,
+            reg_x: reg_x.wrapping_sub(left),
+            reg_y: reg_y.wrapping_sub(top),
+        })
+    }
+}
+
+/// Decodes a `BITD` chunk into an RGBA image. Alpha is always opaque, like
+/// in the BMP files written by SharpExport.
+pub fn decode(info: &BitmapInfo, data: &[u8], palette: &Palette) -> Result<RgbaImage> {
+    let (width, height) = (info.width as usize, info.height as usize);
+    let pitch = match info.bit_depth {
+        // 32-bit rows are stored as four planes of `width` bytes
+        32 => width * 4,
+        _ => info.pitch,
+    };
+    if pitch * 8 < width * info.bit_depth as usize {
+        bail!(
+            "Bitmap pitch {} is too small for {} pixels at {} bits",
+            pitch,
+            width,
+            info.bit_depth
+        );
+    }
+    let expected = pitch * height;
+    // PackBits makes at most 128 bytes out of 2, so shorter data can't be
+    // this bitmap and its size comes from a damaged rect
+    if expected > data.len().saturating_mul(64) {
+        bail!(
+            "Bitmap data of {} bytes is too short for {}x{} pixels",
+            data.len(),
+            width,
+            height
+        );
+    }
+    // Director only stores a bitmap uncompressed when packing doesn't help.
+    // Packed 16 and 32-bit rows are split into one plane per byte.
+    let compressed = data.len() != expected;
+    let pixels = if compressed {
+        unpack_bits(data, expected)
+    } else {
+        data.to_vec()
+    };
+
+    let mut image = RgbaImage::new(info.width, info.height);
+    for y in 0..height {
+        let row = &pixels[y * pitch..(y + 1) * pitch];
+        for x in 0..width {
+            let [r, g, b] = match info.bit_depth {
+                1 => {
+                    if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                        [0, 0, 0]
+                    } else {
+                        [0xff, 0xff, 0xff]
+                    }
+                }
+                2 | 4 | 8 => {
+                    let bits = info.bit_depth as usize;
+                    let per_byte = 8 / bits;
+                    let shift = 8 - bits * (x % per_byte + 1);
+                    let index = (row[x / per_byte] >> shift) & ((1u16 << bits) - 1) as u8;
+                    palette.get(index as usize).copied().unwrap_or([0, 0, 0])
+                }
+                16 => {
+                    let value = if compressed {
+                        u16::from_be_bytes([row[x], row[width + x]])
+                    } else {
+                        u16::from_be_bytes([row[x * 2], row[x * 2 + 1]])
+                    };
+                    let expand = |v: u16| ((v & 0x1f) << 3 | (v & 0x1f) >> 2) as u8;
+                    [expand(value >> 10), expand(value >> 5), expand(value)]
+                }
+                32 => {
+                    if compressed {
+                        [row[width + x], row[2 * width + x], row[3 * width + x]]
+                    } else {
+                        [row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]]
+                    }
+                }
+                depth => bail!("Unsupported bitmap depth: {}", depth),
+            };
+            image.put_pixel(x as u32, y as u32, Rgba([r, g, b, 0xff]));
+        }
+    }
+    Ok(image)
+}
+
+/// PackBits run-length decoding. Output is padded with zeroes or truncated
+/// to `expected` bytes, since some encoders overrun the last row.
+fn unpack_bits(data: &[u8], expected: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(expected);
+    let mut i = 0;
+    while i < data.len() && out.len() < expected {
+        let control = data[i] as usize;
+        i += 1;
+        if control < 0x80 {
+            let end = (i + control + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        } else if let Some(&value) = data.get(i) {
+            out.extend(std::iter::repeat_n(value, 257 - control));
+            i += 1;
+        }
+    }
+    out.resize(expected, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::director::palette;
+
+    fn info(width: u32, height: u32, pitch: usize, bit_depth: u8) -> BitmapInfo {
+        BitmapInfo {
+            width,
+            height,
+            pitch,
+            bit_depth,
+            palette: PaletteRef::Builtin(palette::SYSTEM_MAC),
+            reg_x: 0,
+            reg_y: 0,
+        }
+    }
+
+    #[test]
+    fn unpacks_literals_and_runs() {
+        // Two literal bytes, then 0xaa repeated four times
+        assert_eq!(
+            unpack_bits(&[0x01, 1, 2, 0xfd, 0xaa], 6),
+            [1, 2, 0xaa, 0xaa, 0xaa, 0xaa]
+        );
+        // Short data is padded, overruns are cut off
+        assert_eq!(unpack_bits(&[0xfe, 5], 5), [5, 5, 5, 0, 0]);
+        assert_eq!(unpack_bits(&[0xfe, 5, 0x00, 9], 2), [5, 5]);
+    }
+
+    #[test]
+    fn decodes_packed_and_plain_rows() {
+        let palette = vec![[0, 0, 0], [10, 20, 30], [40, 50, 60]];
+        let info = info(3, 2, 4, 8);
+        let plain = [1, 2, 1, 0, 2, 2, 2, 0];
+        let packed = [0x02, 1, 2, 1, 0x00, 0, 0xfe, 2, 0x00, 0];
+        for data in [&plain[..], &packed[..]] {
+            let image = decode(&info, data, &palette).unwrap();
+            assert_eq!(image.get_pixel(0, 0).0, [10, 20, 30, 0xff]);
+            assert_eq!(image.get_pixel(1, 0).0, [40, 50, 60, 0xff]);
+            assert_eq!(image.get_pixel(2, 1).0, [40, 50, 60, 0xff]);
+        }
+    }
+
+    #[test]
+    fn decodes_one_bit_rows() {
+        let image = decode(&info(3, 1, 2, 1), &[0b1010_0000, 0], &Vec::new()).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0xff]);
+        assert_eq!(image.get_pixel(1, 0).0, [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(image.get_pixel(2, 0).0, [0, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn reads_colour_bitmap_properties() {
+        let mut data = vec![0; 28];
+        data[0..2].copy_from_slice(&0x8004u16.to_be_bytes());
+        // Rect top, left, bottom, right
+        for (offset, value) in [(2, 10), (4, 20), (6, 40), (8, 60)] {
+            data[offset..offset + 2].copy_from_slice(&(value as u16).to_be_bytes());
+        }
+        // Registration point y, x
+        data[18..20].copy_from_slice(&25u16.to_be_bytes());
+        data[20..22].copy_from_slice(&30u16.to_be_bytes());
+        data[23] = 8;
+        data[24..26].copy_from_slice(&2u16.to_be_bytes());
+        data[26..28].copy_from_slice(&5u16.to_be_bytes());
+        let info = BitmapInfo::parse(&data, 500, palette::SYSTEM_WIN).unwrap();
+        assert_eq!((info.width, info.height, info.pitch), (40, 30, 4));
+        assert_eq!((info.reg_x, info.reg_y), (10, 15));
+        assert_eq!(info.bit_depth, 8);
+        assert!(matches!(
+            info.palette,
+            PaletteRef::Member {
+                cast_lib: 2,
+                member: 5
+            }
+        ));
+    }
+
+    #[test]
+    fn refuses_damaged_rects_and_data() {
+        let mut data = vec![0; 28];
+        data[0..2].copy_from_slice(&0x8004u16.to_be_bytes());
+        // Right 0x7fff, left 0x8000: a width that overflows i16
+        data[4..6].copy_from_slice(&0x8000u16.to_be_bytes());
+        data[8..10].copy_from_slice(&0x7fffu16.to_be_bytes());
+        let wide = BitmapInfo::parse(&data, 500, palette::SYSTEM_WIN).unwrap();
+        assert_eq!(wide.width, 0xffff);
+        // A right edge left of the left one
+        data[4..6].copy_from_slice(&10u16.to_be_bytes());
+        data[8..10].copy_from_slice(&5u16.to_be_bytes());
+        assert!(BitmapInfo::parse(&data, 500, palette::SYSTEM_WIN).is_err());
+        // A built-in palette id that overflows when taking one off
+        data[8..10].copy_from_slice(&20u16.to_be_bytes());
+        data[23] = 8;
+        data[26..28].copy_from_slice(&(i16::MIN as u16).to_be_bytes());
+        assert!(BitmapInfo::parse(&data, 500, palette::SYSTEM_WIN).is_err());
+
+        // A huge bitmap from a few bytes of data
+        assert!(decode(&info(0xffff, 0xffff, 0x3fff, 8), &[0xfe, 1], &Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Film loop members: a small score of their own, kept in a `SCVW` chunk
+//! owned by the member. The `CASt` data holds the rect the loop is drawn
+//! in, in the same stage coordinates its sprites are positioned with.
+
+use super::score::{Frame, MemberRef};
+use super::{Endian, Reader};
+use anyhow::Result;
+use image::imageops::{resize, FilterType};
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Director plays a film loop at the tempo of the movie around it, so the
+/// loop itself has none. This is Director's default tempo.
+pub const FRAME_RATE: u32 = 15;
+
+/// Inks that leave the white around a bitmap transparent.
+const INK_MATTE: u8 = 8;
+const INK_BACKGROUND_TRANSPARENT: u8 = 36;
+
+#[derive(Serialize)]
+pub struct FilmLoop {
+    /// Left, top, right and bottom of the loop on the stage.
+    pub rect: [i16; 4],
+    pub looping: bool,
+    pub frame_rate: u32,
+    pub frames: Vec<Frame>,
+}
+
+impl FilmLoop {
+    pub fn parse(specific_data: &[u8], frames: Vec<Frame>) -> Result<FilmLoop> {
+        let mut reader = Reader::new(specific_data, Endian::Big);
+        let top = reader.read_u16()? as i16;
+        let left = reader.read_u16()? as i16;
+        let bottom = reader.read_u16()? as i16;
+        let right = reader.read_u16()? as i16;
+        let flags = if reader.len() >= 12 {
+            reader.read_u32()?
+        } else {
+            0
+        };
+        Ok(FilmLoop {
+            rect: [left, top, right, bottom],
+            looping: flags & 0x40 == 0,
+            frame_rate: FRAME_RATE,
+            frames,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.rect[2] - self.rect[0]).max(0) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.rect[3] - self.rect[1]).max(0) as u32
+    }
+}
+
+/// A decoded bitmap with its registration point, ready to be placed.
+pub struct SpriteImage {
+    pub image: RgbaImage,
+    pub reg_x: i16,
+    pub reg_y: i16,
+}
+
+/// Draws one frame of a film loop. Sprites are drawn in channel order with
+/// their registration point at their location, stretched to the sprite's
+/// size. Sprites without an image in `images` are left out.
+pub fn render_frame(
+    film_loop: &FilmLoop,
+    frame: &Frame,
+    images: &HashMap<MemberRef, SpriteImage>,
+) -> RgbaImage {
+    let mut canvas = RgbaImage::new(film_loop.width(), film_loop.height());
+    for sprite in &frame.sprites {
+        let Some(sprite_image) = images.get(&sprite.member) else {
+            continue;
+        };
+        let mut image = &sprite_image.image;
+        let mut scale = (1.0, 1.0);
+        let stretched;
+        let (width, height) = (sprite.width as u32, sprite.height as u32);
+        if sprite.width > 0 && sprite.height > 0 && (width, height) != image.dimensions() {
+            scale = (
+                width as f32 / image.width() as f32,
+                height as f32 / image.height() as f32,
+            );
+            stretched = resize(image, width, height, FilterType::Nearest);
+            image = &stretched;
+        }
+        let x = sprite.loc_h as i32
+            - (sprite_image.reg_x as f32 * scale.0) as i32
+            - film_loop.rect[0] as i32;
+        let y = sprite.loc_v as i32
+            - (sprite_image.reg_y as f32 * scale.1) as i32
+            - film_loop.rect[1] as i32;
+        let keep_white = !matches!(sprite.ink, INK_MATTE | INK_BACKGROUND_TRANSPARENT);
+        for (px, py, pixel) in image.enumerate_pixels() {
+            let (cx, cy) = (x + px as i32, y + py as i32);
+            if cx < 0 || cy < 0 || cx >= canvas.width() as i32 || cy >= canvas.height() as i32 {
+                continue;
+            }
+            if keep_white || pixel.0[..3] != [255, 255, 255] {
+                canvas.put_pixel(cx as u32, cy as u32, *pixel);
+            }
+        }
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::director::score::Sprite;
+    use image::Rgba;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    fn sprite(member: i16, ink: u8, loc: (i16, i16), size: (i16, i16)) -> Sprite {
+        Sprite {
+            channel: 1,
+            member: MemberRef {
+                cast_lib: 1,
+                member,
+            },
+            name: String::new(),
+            cast: String::new(),
+            sprite_type: 1,
+            ink,
+            blend: 255,
+            loc_h: loc.0,
+            loc_v: loc.1,
+            width: size.0,
+            height: size.1,
+            fore_color: 255,
+            back_color: 0,
+            script: None,
+        }
+    }
+
+    fn frame(sprites: Vec<Sprite>) -> Frame {
+        Frame {
+            frame: 1,
+            script: None,
+            tempo: 0,
+            transition: None,
+            palette: None,
+            sound1: None,
+            sound2: None,
+            sprites,
+        }
+    }
+
+    /// A 2x2 image, red with a white bottom right pixel, registered at
+    /// its centre.
+    fn images() -> HashMap<MemberRef, SpriteImage> {
+        let mut image = RgbaImage::from_pixel(2, 2, RED);
+        image.put_pixel(1, 1, WHITE);
+        let mut images = HashMap::new();
+        for member in [1, 2] {
+            images.insert(
+                MemberRef {
+                    cast_lib: 1,
+                    member,
+                },
+                SpriteImage {
+                    image: image.clone(),
+                    reg_x: 1,
+                    reg_y: 1,
+                },
+            );
+        }
+        images
+    }
+
+    #[test]
+    fn parses_the_rect_and_looping() {
+        let mut data = Vec::new();
+        for value in [10i16, 20, 40, 60] {
+            data.extend(value.to_be_bytes());
+        }
+        let film_loop = FilmLoop::parse(&data, Vec::new()).unwrap();
+        assert_eq!(film_loop.rect, [20, 10, 60, 40]);
+        assert_eq!((film_loop.width(), film_loop.height()), (40, 30));
+        assert!(film_loop.looping);
+
+        data.extend(0x40u32.to_be_bytes());
+        assert!(!FilmLoop::parse(&data, Vec::new()).unwrap().looping);
+    }
+
+    #[test]
+    fn places_sprites_by_their_registration_point() {
+        let film_loop = FilmLoop {
+            rect: [100, 100, 108, 104],
+            looping: true,
+            frame_rate: FRAME_RATE,
+            frames: Vec::new(),
+        };
+        let frame = frame(vec![
+            // Copy ink keeps the white pixel
+            sprite(1, 0, (102, 102), (2, 2)),
+            // Matte ink drops it, and the sprite is stretched to 4x2
+            sprite(2, INK_MATTE, (106, 102), (4, 2)),
+            // Members without an image are left out
+            sprite(3, 0, (101, 101), (2, 2)),
+        ]);
+        let canvas = render_frame(&film_loop, &frame, &images());
+        assert_eq!(canvas.dimensions(), (8, 4));
+        assert_eq!(*canvas.get_pixel(1, 1), RED);
+        assert_eq!(*canvas.get_pixel(2, 2), WHITE);
+        assert_eq!(canvas.get_pixel(0, 0).0[3], 0);
+        // Registered at (2, 1) once stretched
+        assert_eq!(*canvas.get_pixel(4, 1), RED);
+        assert_eq!(*canvas.get_pixel(7, 1), RED);
+        assert_eq!(canvas.get_pixel(6, 2).0[3], 0);
+        assert_eq!(*canvas.get_pixel(5, 2), RED);
+    }
+}
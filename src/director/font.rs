@@ -0,0 +1,321 @@
+//! Fonts: the movie's font map and the fonts embedded in font members.
+//!
+//! Director 7 and later embed outline fonts as PFR (Bitstream's Portable
+//! Font Resource), which is what the Font Asset Xtra converts fonts to on
+//! import; TrueType data can show up too. Older Mac movies carry bitmap
+//! fonts as `FONT`/`NFNT` resources, which are converted to BDF here since
+//! nothing reads those any more.
+
+use super::{decode_string, Endian, Reader};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const PFR_MAGIC: &[u8] = b"PFR0";
+/// Versions an `sfnt` font can start with: TrueType, CFF-based OpenType
+/// and Apple's `true`.
+const SFNT_VERSIONS: [&[u8]; 3] = [b"\x00\x01\x00\x00", b"OTTO", b"true"];
+
+/// Reads the `Fmap` chunk, mapping the font ids used by text style runs to
+/// font names.
+pub fn read_font_map(data: &[u8], endian: Endian) -> Result<HashMap<u16, String>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    let map_len = reader.read_u32()? as usize;
+    reader.skip(4)?; // names length
+    let names_start = reader.pos() + map_len;
+    reader.skip(8)?;
+    let count = reader.read_u32()?;
+    reader.skip(16)?;
+    let mut fonts = HashMap::new();
+    for _ in 0..count {
+        let name_offset = reader.read_u32()? as usize;
+        reader.skip(2)?; // platform
+        let id = reader.read_u16()?;
+        let mut names = Reader::new(data, Endian::Big);
+        names.seek(names_start + name_offset)?;
+        let len = names.read_u32()? as usize;
+        fonts.insert(id, decode_string(names.read_bytes(len)?, endian));
+    }
+    Ok(fonts)
+}
+
+/// Finds an outline font in a font member's data. Returns the font and
+/// the file extension it should be written with.
+pub fn find_outline_font(data: &[u8]) -> Option<(&[u8], &'static str)> {
+    if let Some(start) = find(data, PFR_MAGIC) {
+        return Some((&data[start..], "pfr"));
+    }
+    (0..data.len().saturating_sub(12)).find_map(|start| {
+        let version = &data[start..start + 4];
+        if !SFNT_VERSIONS.contains(&version) {
+            return None;
+        }
+        let len = sfnt_len(&data[start..])?;
+        let extension = if version == b"OTTO" { "otf" } else { "ttf" };
+        Some((&data[start..start + len], extension))
+    })
+}
+
+/// Length of an `sfnt` font from its table directory, or `None` if the
+/// directory doesn't look like one.
+fn sfnt_len(data: &[u8]) -> Option<usize> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.skip(4).ok()?;
+    let tables = reader.read_u16().ok()?;
+    if tables == 0 || tables > 64 {
+        return None;
+    }
+    reader.skip(6).ok()?;
+    let mut end = 12 + tables as usize * 16;
+    for _ in 0..tables {
+        let tag = reader.read_bytes(4).ok()?;
+        if !tag.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            return None;
+        }
+        reader.skip(4).ok()?; // checksum
+        let offset = reader.read_u32().ok()? as usize;
+        let len = reader.read_u32().ok()? as usize;
+        end = end.max(offset + len);
+    }
+    (end <= data.len()).then_some(end)
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A font found in a font member.
+pub enum EmbeddedFont<'a> {
+    Outline {
+        data: &'a [u8],
+        extension: &'static str,
+    },
+    /// A Mac `FONT`/`NFNT` resource.
+    Bitmap(&'a [u8]),
+}
+
+/// Converts a Mac `FONT`/`NFNT` bitmap font to BDF. The resource doesn't
+/// know its own name or point size, those come from the family it belongs
+/// to, so they are passed in. Without a size, the line height is used.
+pub fn nfnt_to_bdf(data: &[u8], name: &str, size: Option<u16>) -> Result<String> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.skip(2)?; // font type
+    let first_char = reader.read_u16()? as usize;
+    let last_char = reader.read_u16()? as usize;
+    let max_width = reader.read_u16()? as i16;
+    let kern_max = i32::from(reader.read_u16()? as i16);
+    let descent_high = reader.read_u16()? as i16;
+    let rect_width = reader.read_u16()? as i16;
+    let rect_height = reader.read_u16()? as usize;
+    let width_table_low = reader.read_u16()? as usize;
+    let ascent = i32::from(reader.read_u16()? as i16);
+    let descent = i32::from(reader.read_u16()? as i16);
+    reader.skip(2)?; // leading
+    let row_words = reader.read_u16()? as usize;
+    if last_char < first_char || last_char > 255 {
+        bail!("Bitmap font has an invalid character range");
+    }
+    let size = size.unwrap_or((ascent + descent).clamp(1, u16::MAX.into()) as u16);
+
+    let row_bytes = row_words * 2;
+    let image = reader.read_bytes(row_bytes * rect_height)?;
+    let glyphs = last_char - first_char + 1;
+    let locations = (0..glyphs + 2)
+        .map(|_| Ok(reader.read_u16()? as usize))
+        .collect::<Result<Vec<_>>>()?;
+    // The offset to the width table is in words from its own field, with
+    // the high word borrowed from the descent field when that's positive
+    let high = if descent_high > 0 {
+        descent_high as usize
+    } else {
+        0
+    };
+    reader.seek(16 + ((high << 16) | width_table_low) * 2)?;
+    let widths = (0..glyphs + 2)
+        .map(|_| reader.read_u16())
+        .collect::<Result<Vec<_>>>()?;
+
+    let pixel = |x: usize, y: usize| {
+        x < row_bytes * 8 && image[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+    };
+    let mut chars = String::new();
+    let mut count = 0;
+    for index in 0..glyphs {
+        let offset_width = widths[index];
+        if offset_width == 0xffff {
+            continue;
+        }
+        let code = (first_char + index) as u8;
+        let encoding = decode_string(&[code], Endian::Big)
+            .chars()
+            .next()
+            .map_or(code as u32, u32::from);
+        let (start, end) = (locations[index], locations[index + 1]);
+        let glyph_width = end.saturating_sub(start);
+        let advance = offset_width & 0xff;
+        let x_offset = kern_max + i32::from(offset_width >> 8);
+        writeln!(chars, "STARTCHAR U+{:04X}", encoding)?;
+        writeln!(chars, "ENCODING {}", encoding)?;
+        writeln!(
+            chars,
+            "SWIDTH {} 0",
+            advance as u32 * 1000 / size.max(1) as u32
+        )?;
+        writeln!(chars, "DWIDTH {} 0", advance)?;
+        // Blank glyphs like the space have no image at all
+        let height = if glyph_width == 0 { 0 } else { rect_height };
+        writeln!(
+            chars,
+            "BBX {} {} {} {}",
+            glyph_width, height, x_offset, -descent
+        )?;
+        writeln!(chars, "BITMAP")?;
+        for y in 0..height {
+            let mut row = vec![0u8; glyph_width.div_ceil(8)];
+            for x in 0..glyph_width {
+                if pixel(start + x, y) {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            let hex: String = row.iter().map(|b| format!("{:02X}", b)).collect();
+            writeln!(chars, "{}", hex)?;
+        }
+        writeln!(chars, "ENDCHAR")?;
+        count += 1;
+    }
+
+    let mut bdf = String::new();
+    writeln!(bdf, "STARTFONT 2.1")?;
+    writeln!(bdf, "FONT {}-{}", name.replace(' ', "_"), size)?;
+    writeln!(bdf, "SIZE {} 72 72", size)?;
+    writeln!(
+        bdf,
+        "FONTBOUNDINGBOX {} {} {} {}",
+        rect_width.max(max_width),
+        rect_height,
+        kern_max,
+        -descent
+    )?;
+    writeln!(bdf, "STARTPROPERTIES 5")?;
+    writeln!(bdf, "FAMILY_NAME \"{}\"", name.replace('"', "'"))?;
+    writeln!(bdf, "FONT_ASCENT {}", ascent)?;
+    writeln!(bdf, "FONT_DESCENT {}", descent)?;
+    writeln!(bdf, "CHARSET_REGISTRY \"ISO10646\"")?;
+    writeln!(bdf, "CHARSET_ENCODING \"1\"")?;
+    writeln!(bdf, "ENDPROPERTIES")?;
+    writeln!(bdf, "CHARS {}", count)?;
+    bdf.push_str(&chars);
+    writeln!(bdf, "ENDFONT")?;
+    Ok(bdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_font_map() {
+        let mut data = 44u32.to_be_bytes().to_vec();
+        data.extend([0; 12]);
+        data.extend(2u32.to_be_bytes());
+        data.extend([0; 16]);
+        for (name_offset, id) in [(0u32, 3u16), (9, 7)] {
+            data.extend(name_offset.to_be_bytes());
+            data.extend(2u16.to_be_bytes());
+            data.extend(id.to_be_bytes());
+        }
+        data.extend(5u32.to_be_bytes());
+        data.extend(b"Arial");
+        data.extend(5u32.to_be_bytes());
+        data.extend(b"M\xfclle");
+        let fonts = read_font_map(&data, Endian::Little).unwrap();
+        assert_eq!(fonts.len(), 2);
+        assert_eq!(fonts[&3], "Arial");
+        assert_eq!(fonts[&7], "Mülle");
+    }
+
+    #[test]
+    fn finds_outline_fonts() {
+        let mut sfnt = b"\x00\x01\x00\x00".to_vec();
+        sfnt.extend(1u16.to_be_bytes());
+        sfnt.extend([0; 6]);
+        sfnt.extend(b"head");
+        sfnt.extend([0; 4]);
+        sfnt.extend(28u32.to_be_bytes());
+        sfnt.extend(4u32.to_be_bytes());
+        sfnt.extend([1, 2, 3, 4]);
+        let data = [&[9, 9, 9][..], &sfnt, &[0; 16]].concat();
+        assert_eq!(find_outline_font(&data), Some((&sfnt[..], "ttf")));
+
+        let data = b"xxPFR0 rest of the font";
+        assert_eq!(find_outline_font(data), Some((&data[2..], "pfr")));
+        assert_eq!(find_outline_font(&[0; 64]), None);
+    }
+
+    /// An `NFNT` with "A" (3 pixels wide, advancing 4) and "B" (2 pixels
+    /// wide, kerned 1 to the right, advancing 3), two rows high.
+    fn nfnt() -> Vec<u8> {
+        let mut data = Vec::new();
+        // Type, first and last char, max width, kern, descent high word,
+        // rect width and height, width table offset, ascent, descent,
+        // leading and row width in words
+        for value in [0x9000u16, 65, 66, 4, 0, 0, 5, 2, 11, 1, 1, 0, 1] {
+            data.extend(value.to_be_bytes());
+        }
+        data.extend([0b1011_1000, 0, 0b0100_1000, 0]);
+        for location in [0u16, 3, 5, 5] {
+            data.extend(location.to_be_bytes());
+        }
+        for offset_width in [0x0004u16, 0x0103, 0xffff, 0xffff] {
+            data.extend(offset_width.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn converts_bitmap_fonts_to_bdf() {
+        let bdf = nfnt_to_bdf(&nfnt(), "Mulle Font", Some(12)).unwrap();
+        assert!(bdf.starts_with("STARTFONT 2.1\nFONT Mulle_Font-12\nSIZE 12 72 72\n"));
+        assert!(bdf.contains("FONTBOUNDINGBOX 5 2 0 -1\n"));
+        assert!(bdf.contains("FAMILY_NAME \"Mulle Font\"\nFONT_ASCENT 1\nFONT_DESCENT 1\n"));
+        assert!(bdf.contains("CHARS 2\n"));
+        assert!(bdf.contains(concat!(
+            "STARTCHAR U+0041\nENCODING 65\nSWIDTH 333 0\nDWIDTH 4 0\n",
+            "BBX 3 2 0 -1\nBITMAP\nA0\n40\nENDCHAR\n"
+        )));
+        assert!(bdf.contains(concat!(
+            "STARTCHAR U+0042\nENCODING 66\nSWIDTH 250 0\nDWIDTH 3 0\n",
+            "BBX 2 2 1 -1\nBITMAP\nC0\n40\nENDCHAR\n"
+        )));
+        assert!(bdf.ends_with("ENDFONT\n"));
+
+        // Without a size, the line height stands in for it
+        let bdf = nfnt_to_bdf(&nfnt(), "Mulle", None).unwrap();
+        assert!(bdf.contains("SIZE 2 72 72\n"));
+    }
+
+    #[test]
+    fn rejects_bad_character_ranges() {
+        let mut data = nfnt();
+        data[2..4].copy_from_slice(&70u16.to_be_bytes());
+        assert!(nfnt_to_bdf(&data, "Mulle", None).is_err());
+        assert!(nfnt_to_bdf(&nfnt()[..30], "Mulle", None).is_err());
+    }
+
+    #[test]
+    fn converts_damaged_headers_without_overflowing() {
+        let mut data = nfnt();
+        // Kerning, ascent and descent at the ends of the i16 range
+        data[8..10].copy_from_slice(&0x7fffu16.to_be_bytes());
+        data[18..20].copy_from_slice(&0x7fffu16.to_be_bytes());
+        data[20..22].copy_from_slice(&0x7fffu16.to_be_bytes());
+        let bdf = nfnt_to_bdf(&data, "Mulle", None).unwrap();
+        assert!(bdf.contains("SIZE 65534 72 72\n"));
+        assert!(bdf.contains("FONTBOUNDINGBOX 5 2 32767 -32767\n"));
+        data[20..22].copy_from_slice(&0x8000u16.to_be_bytes());
+        let bdf = nfnt_to_bdf(&data, "Mulle", None).unwrap();
+        assert!(bdf.contains("FONT_DESCENT -32768\n"));
+        assert!(bdf.contains("BBX 2 2 32768 32768\n"));
+    }
+}
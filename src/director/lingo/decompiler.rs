@@ -0,0 +1,1130 @@
+//! Turns Lingo bytecode back into source.
+//!
+//! The bytecode is a plain stack machine, so expressions are rebuilt by
+//! replaying it on a stack of partial expressions. Statements are emitted
+//! whenever an instruction consumes the stack for its side effect. Control
+//! flow only ever comes from `if` and `repeat`: backward `EndRepeat` jumps
+//! mark loops, and forward `JmpIfZ` jumps mark `if` blocks, with a `Jmp` at
+//! the end of the block meaning there is an `else`.
+//!
+//! The result reads like the original script but is not guaranteed to be
+//! identical: `case` statements come out as `if` chains, and instructions
+//! the decompiler does not understand are written as comments.
+
+use super::opcodes::{self, Op};
+use super::{Handler, Literal, Script};
+use std::collections::HashMap;
+
+const INDENT: &str = "  ";
+/// Precedence of anything that never needs parentheses.
+const ATOM: u8 = 10;
+const UNARY: u8 = 7;
+
+/// Decompiles every handler of a script, preceded by its property and
+/// global declarations.
+pub fn decompile(script: &Script) -> String {
+    let mut out = String::new();
+    if !script.properties.is_empty() {
+        out.push_str(&format!("property {}\n", script.properties.join(", ")));
+    }
+    if !script.globals.is_empty() {
+        out.push_str(&format!("global {}\n", script.globals.join(", ")));
+    }
+    for handler in &script.handlers {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&decompile_handler(script, handler));
+    }
+    out
+}
+
+fn decompile_handler(script: &Script, handler: &Handler) -> String {
+    let mut out = format!("on {}", handler.name);
+    if !handler.arguments.is_empty() {
+        out.push(' ');
+        out.push_str(&handler.arguments.join(", "));
+    }
+    out.push('\n');
+    if !handler.globals.is_empty() {
+        out.push_str(&format!(
+            "{}global {}\n",
+            INDENT,
+            handler.globals.join(", ")
+        ));
+    }
+
+    let mut decompiler = HandlerDecompiler::new(script, handler);
+    let statements = decompiler.block(0, decompiler.code.len());
+    for statement in &statements {
+        statement.render(1, &mut out);
+    }
+    out.push_str("end\n");
+    out
+}
+
+#[derive(Clone, Copy)]
+struct Instruction {
+    pos: usize,
+    op: Op,
+    operand: i32,
+}
+
+impl Instruction {
+    /// Where a jump forward by the operand lands. Damaged bytecode can
+    /// jump anywhere, so this wraps rather than overflows.
+    fn jump_target(&self) -> usize {
+        self.pos.wrapping_add(self.operand as usize)
+    }
+}
+
+fn read_instructions(bytecode: &[u8]) -> Vec<Instruction> {
+    let mut code = Vec::new();
+    let mut pos = 0;
+    while pos < bytecode.len() {
+        let byte = bytecode[pos];
+        let op = Op::from_byte(byte);
+        let signed = matches!(op, Op::PushInt8 | Op::PushInt16 | Op::PushInt32);
+        let (operand, len) = match byte {
+            0xc0.. => match bytecode.get(pos + 1..pos + 5) {
+                Some(b) => (i32::from_be_bytes([b[0], b[1], b[2], b[3]]), 5),
+                None => break,
+            },
+            0x80.. => match bytecode.get(pos + 1..pos + 3) {
+                Some(b) if signed => (i16::from_be_bytes([b[0], b[1]]) as i32, 3),
+                Some(b) => (u16::from_be_bytes([b[0], b[1]]) as i32, 3),
+                None => break,
+            },
+            0x40.. => match bytecode.get(pos + 1) {
+                Some(&b) if signed => (b as i8 as i32, 2),
+                Some(&b) => (b as i32, 2),
+                None => break,
+            },
+            _ => (0, 1),
+        };
+        code.push(Instruction { pos, op, operand });
+        pos += len;
+    }
+    code
+}
+
+#[derive(Clone)]
+enum Expr {
+    Text(String, u8),
+    Int(i32),
+    Var(String),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    ArgList(Vec<Expr>, bool),
+}
+
+impl Expr {
+    fn text(text: impl Into<String>) -> Expr {
+        Expr::Text(text.into(), ATOM)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Text(_, precedence) => *precedence,
+            Expr::Binary(op, _, _) => op.binary().map_or(ATOM, |(_, p)| p),
+            Expr::Int(value) if *value < 0 => UNARY,
+            _ => ATOM,
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Expr::Text(text, _) => text.clone(),
+            Expr::Int(value) => value.to_string(),
+            Expr::Var(name) => name.clone(),
+            Expr::Binary(op, lhs, rhs) => {
+                let (symbol, precedence) = op.binary().unwrap_or(("?", ATOM));
+                format!(
+                    "{} {} {}",
+                    lhs.wrap(precedence),
+                    symbol,
+                    rhs.wrap(precedence + 1)
+                )
+            }
+            Expr::ArgList(items, _) => join(items),
+        }
+    }
+
+    /// Renders with parentheses if this binds looser than `precedence`.
+    fn wrap(&self, precedence: u8) -> String {
+        if self.precedence() < precedence {
+            format!("({})", self.render())
+        } else {
+            self.render()
+        }
+    }
+
+    fn items(self) -> Vec<Expr> {
+        match self {
+            Expr::ArgList(items, _) => items,
+            other => vec![other],
+        }
+    }
+}
+
+fn join(items: &[Expr]) -> String {
+    items
+        .iter()
+        .map(Expr::render)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+enum Statement {
+    Line(String),
+    Assign(String, Expr),
+    If(Expr, Vec<Statement>, Vec<Statement>),
+    RepeatWhile(Expr, Vec<Statement>),
+    RepeatWith(String, Expr, Expr, bool, Vec<Statement>),
+    Tell(Expr, Vec<Statement>),
+}
+
+impl Statement {
+    fn render(&self, depth: usize, out: &mut String) {
+        let indent = INDENT.repeat(depth);
+        match self {
+            Statement::Line(line) => out.push_str(&format!("{}{}\n", indent, line)),
+            Statement::Assign(target, value) => {
+                out.push_str(&format!("{}{} = {}\n", indent, target, value.render()))
+            }
+            Statement::If(condition, then, otherwise) => {
+                out.push_str(&format!("{}if {} then\n", indent, condition.render()));
+                render_block(then, depth + 1, out);
+                let mut otherwise = otherwise;
+                // Fold `else` blocks holding a single `if` into `else if`
+                while let [Statement::If(condition, then, next)] = otherwise.as_slice() {
+                    out.push_str(&format!("{}else if {} then\n", indent, condition.render()));
+                    render_block(then, depth + 1, out);
+                    otherwise = next;
+                }
+                if !otherwise.is_empty() {
+                    out.push_str(&format!("{}else\n", indent));
+                    render_block(otherwise, depth + 1, out);
+                }
+                out.push_str(&format!("{}end if\n", indent));
+            }
+            Statement::RepeatWhile(condition, body) => {
+                out.push_str(&format!("{}repeat while {}\n", indent, condition.render()));
+                render_block(body, depth + 1, out);
+                out.push_str(&format!("{}end repeat\n", indent));
+            }
+            Statement::RepeatWith(variable, start, end, down, body) => {
+                out.push_str(&format!(
+                    "{}repeat with {} = {} {} {}\n",
+                    indent,
+                    variable,
+                    start.render(),
+                    if *down { "down to" } else { "to" },
+                    end.render()
+                ));
+                render_block(body, depth + 1, out);
+                out.push_str(&format!("{}end repeat\n", indent));
+            }
+            Statement::Tell(target, body) => {
+                out.push_str(&format!("{}tell {}\n", indent, target.render()));
+                render_block(body, depth + 1, out);
+                out.push_str(&format!("{}end tell\n", indent));
+            }
+        }
+    }
+}
+
+fn render_block(statements: &[Statement], depth: usize, out: &mut String) {
+    for statement in statements {
+        statement.render(depth, out);
+    }
+}
+
+/// Positions of the loop being decompiled, for `exit repeat` and
+/// `next repeat`.
+struct LoopFrame {
+    start: usize,
+    end: usize,
+}
+
+struct HandlerDecompiler<'a> {
+    script: &'a Script,
+    handler: &'a Handler,
+    code: Vec<Instruction>,
+    /// Instruction index by bytecode position, including the end position.
+    index: HashMap<usize, usize>,
+    /// `EndRepeat` index by the index of the instruction its loop starts at.
+    loops: HashMap<usize, usize>,
+    loop_stack: Vec<LoopFrame>,
+    stack: Vec<Expr>,
+}
+
+impl<'a> HandlerDecompiler<'a> {
+    fn new(script: &'a Script, handler: &'a Handler) -> Self {
+        let code = read_instructions(&handler.bytecode);
+        let mut index: HashMap<usize, usize> = code
+            .iter()
+            .enumerate()
+            .map(|(i, instruction)| (instruction.pos, i))
+            .collect();
+        index.insert(handler.bytecode.len(), code.len());
+
+        let mut loops: HashMap<usize, usize> = HashMap::new();
+        for (i, instruction) in code.iter().enumerate() {
+            if instruction.op != Op::EndRepeat {
+                continue;
+            }
+            let target = instruction.pos.wrapping_sub(instruction.operand as usize);
+            // A loop can only start before its end
+            if let Some(&start) = index.get(&target).filter(|&&start| start <= i) {
+                // Keep the outermost loop if two share a start
+                let end = loops.entry(start).or_insert(i);
+                *end = (*end).max(i);
+            }
+        }
+
+        HandlerDecompiler {
+            script,
+            handler,
+            code,
+            index,
+            loops,
+            loop_stack: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn pop(&mut self) -> Expr {
+        self.stack
+            .pop()
+            .unwrap_or_else(|| Expr::text("ERROR_EMPTY_STACK"))
+    }
+
+    fn push(&mut self, expr: Expr) {
+        self.stack.push(expr);
+    }
+
+    fn target_index(&self, pos: usize, end: usize) -> usize {
+        self.index.get(&pos).copied().unwrap_or(end).min(end)
+    }
+
+    fn position(&self, index: usize) -> usize {
+        self.code
+            .get(index)
+            .map_or(self.handler.bytecode.len(), |instruction| instruction.pos)
+    }
+
+    fn local(&self, operand: i32) -> String {
+        let index = operand / self.script.variable_multiplier;
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.handler.locals.get(i))
+            .cloned()
+            .unwrap_or_else(|| format!("local_{}", index))
+    }
+
+    fn argument(&self, operand: i32) -> String {
+        let index = operand / self.script.variable_multiplier;
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.handler.arguments.get(i))
+            .cloned()
+            .unwrap_or_else(|| format!("param_{}", index))
+    }
+
+    /// Decompiles the instructions in `start..end` into statements.
+    fn block(&mut self, start: usize, end: usize) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut i = start;
+        while i < end {
+            if let Some(&repeat_end) = self.loops.get(&i).filter(|&&e| e < end) {
+                let statement = self.repeat(i, repeat_end, &mut statements);
+                statements.push(statement);
+                i = repeat_end + 1;
+                continue;
+            }
+
+            let instruction = self.code[i];
+            match instruction.op {
+                Op::JmpIfZ => {
+                    let condition = self.pop();
+                    // Only a jump forward within the block makes one;
+                    // damaged bytecode jumping anywhere else would loop
+                    let target = match self.index.get(&instruction.jump_target()) {
+                        Some(&target) if target > i && target <= end => target,
+                        _ => {
+                            statements.push(Statement::Line(condition.render()));
+                            i += 1;
+                            continue;
+                        }
+                    };
+                    let mut then_end = target;
+                    let mut else_end = target;
+                    if let Some(jump) = target.checked_sub(1).and_then(|j| self.code.get(j)) {
+                        let jump_target = jump.jump_target();
+                        if jump.op == Op::Jmp
+                            && target - 1 > i
+                            && jump_target > self.position(target)
+                            && !self.is_loop_jump(jump_target)
+                        {
+                            then_end = target - 1;
+                            else_end = self.target_index(jump_target, end);
+                        }
+                    }
+                    let then = self.block(i + 1, then_end);
+                    let otherwise = self.block(target, else_end);
+                    statements.push(Statement::If(condition, then, otherwise));
+                    i = else_end;
+                    continue;
+                }
+                Op::StartTell => {
+                    let target = self.pop();
+                    let tell_end = self.matching_end_tell(i, end);
+                    let body = self.block(i + 1, tell_end);
+                    statements.push(Statement::Tell(target, body));
+                    i = tell_end + 1;
+                    continue;
+                }
+                _ => {
+                    let last = i + 1 == self.code.len();
+                    if let Some(statement) = self.instruction(instruction, last) {
+                        statements.push(statement);
+                    }
+                }
+            }
+            i += 1;
+        }
+        statements
+    }
+
+    fn matching_end_tell(&self, start: usize, end: usize) -> usize {
+        let mut depth = 0;
+        for i in start + 1..end {
+            match self.code[i].op {
+                Op::StartTell => depth += 1,
+                Op::EndTell if depth == 0 => return i,
+                Op::EndTell => depth -= 1,
+                _ => {}
+            }
+        }
+        end
+    }
+
+    fn is_loop_jump(&self, target: usize) -> bool {
+        self.loop_stack
+            .iter()
+            .any(|frame| frame.start == target || frame.end == target)
+    }
+
+    /// Decompiles the loop from `start` to the `EndRepeat` at `repeat_end`.
+    /// `preceding` holds the statements before the loop, so the counter
+    /// initialisation of a `repeat with` can be folded into it.
+    fn repeat(
+        &mut self,
+        start: usize,
+        repeat_end: usize,
+        preceding: &mut Vec<Statement>,
+    ) -> Statement {
+        let after = self.position(repeat_end + 1);
+        let condition_jump = (start..repeat_end).find(|&j| {
+            let instruction = self.code[j];
+            instruction.op == Op::JmpIfZ && instruction.jump_target() == after
+        });
+        let (condition, body_start) = match condition_jump {
+            Some(jump) => {
+                self.block(start, jump);
+                (self.pop(), jump + 1)
+            }
+            None => (Expr::text("TRUE"), start),
+        };
+
+        self.loop_stack.push(LoopFrame {
+            start: self.position(start),
+            end: after,
+        });
+        let mut body = self.block(body_start, repeat_end);
+        self.loop_stack.pop();
+
+        // repeat with i = a to b: `i = a` before the loop, `i <= b` as the
+        // condition and `i = i + 1` as the last statement
+        if let Expr::Binary(op @ (Op::LtEq | Op::GtEq), variable, limit) = &condition {
+            if let Expr::Var(name) = variable.as_ref() {
+                let step = if *op == Op::LtEq { "+ 1" } else { "- 1" };
+                let increments = matches!(body.last(), Some(Statement::Assign(target, value))
+                    if target == name && value.render() == format!("{} {}", name, step));
+                let initialised = matches!(preceding.last(), Some(Statement::Assign(target, _)) if target == name);
+                if increments && initialised {
+                    body.pop();
+                    if let Some(Statement::Assign(_, initial)) = preceding.pop() {
+                        return Statement::RepeatWith(
+                            name.clone(),
+                            initial,
+                            limit.as_ref().clone(),
+                            *op == Op::GtEq,
+                            body,
+                        );
+                    }
+                }
+            }
+        }
+        Statement::RepeatWhile(condition, body)
+    }
+
+    /// Replays one straight-line instruction, returning a statement if it
+    /// completes one.
+    fn instruction(&mut self, instruction: Instruction, last: bool) -> Option<Statement> {
+        let operand = instruction.operand;
+        let op = instruction.op;
+        if op.binary().is_some() {
+            let rhs = self.pop();
+            let lhs = self.pop();
+            self.push(Expr::Binary(op, Box::new(lhs), Box::new(rhs)));
+            return None;
+        }
+
+        match op {
+            Op::Ret | Op::RetFactory => {
+                if !last {
+                    return Some(Statement::Line("exit".to_string()));
+                }
+            }
+            Op::PushZero => self.push(Expr::Int(0)),
+            Op::Inv => {
+                let value = self.pop();
+                self.push(Expr::Text(format!("-{}", value.wrap(UNARY)), UNARY));
+            }
+            Op::Not => {
+                let value = self.pop();
+                self.push(Expr::Text(format!("not {}", value.wrap(UNARY)), UNARY));
+            }
+            Op::GetChunk => {
+                let string = self.pop();
+                let chunk = self.chunk(string);
+                self.push(chunk);
+            }
+            Op::HiliteChunk => {
+                let field = self.field();
+                let chunk = self.chunk(field);
+                return Some(Statement::Line(format!("hilite {}", chunk.render())));
+            }
+            Op::OntoSpr | Op::IntoSpr => {
+                let second = self.pop();
+                let first = self.pop();
+                let relation = if op == Op::OntoSpr {
+                    "intersects"
+                } else {
+                    "within"
+                };
+                self.push(Expr::Text(
+                    format!(
+                        "sprite {} {} {}",
+                        first.wrap(ATOM),
+                        relation,
+                        second.wrap(ATOM)
+                    ),
+                    3,
+                ));
+            }
+            Op::GetField => {
+                let field = self.field();
+                self.push(field);
+            }
+            Op::EndTell => {}
+            Op::PushList => {
+                let items = self.pop().items();
+                self.push(Expr::text(format!("[{}]", join(&items))));
+            }
+            Op::PushPropList => {
+                let items = self.pop().items();
+                let text = if items.is_empty() {
+                    "[:]".to_string()
+                } else {
+                    let pairs: Vec<String> = items
+                        .chunks(2)
+                        .map(|pair| match pair {
+                            [key, value] => format!("{}: {}", key.render(), value.render()),
+                            [key] => key.render(),
+                            _ => String::new(),
+                        })
+                        .collect();
+                    format!("[{}]", pairs.join(", "))
+                };
+                self.push(Expr::text(text));
+            }
+            Op::Swap => {
+                let top = self.pop();
+                let below = self.pop();
+                self.push(top);
+                self.push(below);
+            }
+            Op::PushInt8 | Op::PushInt16 | Op::PushInt32 => self.push(Expr::Int(operand)),
+            Op::PushFloat32 => self.push(Expr::text(format_float(
+                f32::from_bits(operand as u32) as f64
+            ))),
+            Op::PushArgList | Op::PushArgListNoRet => {
+                let count = operand.max(0) as usize;
+                let start = self.stack.len().saturating_sub(count);
+                let items = self.stack.split_off(start);
+                self.push(Expr::ArgList(items, op == Op::PushArgList));
+            }
+            Op::PushCons => {
+                let literal = usize::try_from(operand / self.script.variable_multiplier)
+                    .ok()
+                    .and_then(|i| self.script.literals.get(i));
+                let expr = match literal {
+                    Some(Literal::String(text)) => format_string(text),
+                    Some(Literal::Int(value)) => Expr::Int(*value),
+                    Some(Literal::Float(value)) => Expr::text(format_float(*value)),
+                    None => Expr::text(format!("literal_{}", operand)),
+                };
+                self.push(expr);
+            }
+            Op::PushSymb => self.push(Expr::text(format!("#{}", self.script.name(operand)))),
+            Op::PushVarRef | Op::GetGlobal | Op::GetGlobal2 | Op::GetProp => {
+                self.push(Expr::Var(self.script.name(operand)))
+            }
+            Op::GetTopLevelProp => self.push(Expr::text(format!("_{}", self.script.name(operand)))),
+            Op::GetParam => self.push(Expr::Var(self.argument(operand))),
+            Op::GetLocal => self.push(Expr::Var(self.local(operand))),
+            Op::SetGlobal | Op::SetGlobal2 | Op::SetProp => {
+                let value = self.pop();
+                return Some(Statement::Assign(self.script.name(operand), value));
+            }
+            Op::SetParam => {
+                let value = self.pop();
+                return Some(Statement::Assign(self.argument(operand), value));
+            }
+            Op::SetLocal => {
+                let value = self.pop();
+                return Some(Statement::Assign(self.local(operand), value));
+            }
+            Op::Jmp => {
+                let target = instruction.jump_target();
+                if let Some(frame) = self.loop_stack.last() {
+                    if target == frame.end {
+                        return Some(Statement::Line("exit repeat".to_string()));
+                    }
+                    if target == frame.start {
+                        return Some(Statement::Line("next repeat".to_string()));
+                    }
+                }
+                return Some(Statement::Line(format!("-- jump to {}", target)));
+            }
+            Op::EndRepeat => {}
+            Op::LocalCall => {
+                let name = usize::try_from(operand)
+                    .ok()
+                    .and_then(|i| self.script.handlers.get(i))
+                    .map_or_else(|| format!("handler_{}", operand), |h| h.name.clone());
+                return self.call(name);
+            }
+            Op::ExtCall | Op::TellCall => {
+                let name = self.script.name(operand);
+                return self.call(name);
+            }
+            Op::ObjCall => {
+                let name = self.script.name(operand);
+                let args = self.pop();
+                let returns = matches!(args, Expr::ArgList(_, true));
+                let mut items = args.items();
+                let object = if items.is_empty() {
+                    Expr::text("ERROR_NO_OBJECT")
+                } else {
+                    items.remove(0)
+                };
+                let target = object.wrap(ATOM);
+                let expr = match (name.as_str(), items.as_slice()) {
+                    ("getAt", [index]) => format!("{}[{}]", target, index.render()),
+                    ("setAt", [index, value]) => {
+                        return Some(Statement::Line(format!(
+                            "{}[{}] = {}",
+                            target,
+                            index.render(),
+                            value.render()
+                        )))
+                    }
+                    _ => format!("{}.{}({})", target, name, join(&items)),
+                };
+                if returns {
+                    self.push(Expr::text(expr));
+                } else {
+                    return Some(Statement::Line(expr));
+                }
+            }
+            Op::ObjCallV4 => {
+                let object = self.variable(operand);
+                return self.call(object.render());
+            }
+            Op::Put => {
+                let variable = self.variable(operand);
+                let value = self.pop();
+                return Some(Statement::Line(format!(
+                    "put {} {} {}",
+                    value.render(),
+                    opcodes::put_type(operand),
+                    variable.render()
+                )));
+            }
+            Op::PutChunk => {
+                let variable = self.variable(operand);
+                let chunk = self.chunk(variable);
+                let value = self.pop();
+                return Some(Statement::Line(format!(
+                    "put {} {} {}",
+                    value.render(),
+                    opcodes::put_type(operand),
+                    chunk.render()
+                )));
+            }
+            Op::DeleteChunk => {
+                let variable = self.variable(operand);
+                let chunk = self.chunk(variable);
+                return Some(Statement::Line(format!("delete {}", chunk.render())));
+            }
+            Op::Get => {
+                let property = self.the_property(operand);
+                self.push(property);
+            }
+            Op::Set => {
+                let property = self.the_property(operand);
+                let value = self.pop();
+                return Some(Statement::Line(format!(
+                    "set {} to {}",
+                    property.render(),
+                    value.render()
+                )));
+            }
+            Op::GetMovieProp => self.push(Expr::text(format!("the {}", self.script.name(operand)))),
+            Op::SetMovieProp => {
+                let value = self.pop();
+                return Some(Statement::Assign(
+                    format!("the {}", self.script.name(operand)),
+                    value,
+                ));
+            }
+            Op::GetObjProp | Op::GetChainedProp => {
+                let object = self.pop();
+                self.push(Expr::text(format!(
+                    "{}.{}",
+                    object.wrap(ATOM),
+                    self.script.name(operand)
+                )));
+            }
+            Op::SetObjProp => {
+                let value = self.pop();
+                let object = self.pop();
+                return Some(Statement::Assign(
+                    format!("{}.{}", object.wrap(ATOM), self.script.name(operand)),
+                    value,
+                ));
+            }
+            Op::Peek => {
+                let index = self.stack.len().checked_sub(1 + operand.max(0) as usize);
+                let expr = index
+                    .and_then(|i| self.stack.get(i))
+                    .cloned()
+                    .unwrap_or_else(|| Expr::text("ERROR_EMPTY_STACK"));
+                self.push(expr);
+            }
+            Op::Pop => {
+                let count = operand.max(0) as usize;
+                let len = self.stack.len();
+                self.stack.truncate(len.saturating_sub(count));
+            }
+            Op::TheBuiltin => {
+                let args = self.pop().items();
+                let name = self.script.name(operand);
+                let text = if args.is_empty() {
+                    format!("the {}", name)
+                } else {
+                    format!("the {} of {}", name, join(&args))
+                };
+                self.push(Expr::text(text));
+            }
+            Op::PushChunkVarRef => {
+                let variable = self.variable(operand);
+                self.push(variable);
+            }
+            Op::NewObj => {
+                let args = self.pop().items();
+                self.push(Expr::text(format!(
+                    "new {}({})",
+                    self.script.name(operand),
+                    join(&args)
+                )));
+            }
+            _ => {
+                return Some(Statement::Line(format!(
+                    "-- unknown instruction {:?} {}",
+                    op, operand
+                )))
+            }
+        }
+        None
+    }
+
+    /// Calls a handler with the argument list on the stack. Calls whose
+    /// result is used become expressions, the rest statements.
+    fn call(&mut self, name: String) -> Option<Statement> {
+        let args = self.pop();
+        let returns = matches!(args, Expr::ArgList(_, true));
+        let items = args.items();
+        if returns {
+            self.push(Expr::text(format!("{}({})", name, join(&items))));
+            return None;
+        }
+        Some(Statement::Line(match (name.as_str(), items.as_slice()) {
+            ("return", []) => "return".to_string(),
+            ("return", [value]) => format!("return {}", value.render()),
+            _ => format!("{}({})", name, join(&items)),
+        }))
+    }
+
+    /// `field n`, with the cast library from Director 5 on.
+    fn field(&mut self) -> Expr {
+        let cast = if self.script.version >= 500 {
+            Some(self.pop())
+        } else {
+            None
+        };
+        let id = self.pop();
+        match cast {
+            Some(cast) if !matches!(cast, Expr::Int(0)) => Expr::text(format!(
+                "field {} of castLib {}",
+                id.wrap(ATOM),
+                cast.wrap(ATOM)
+            )),
+            _ => Expr::text(format!("field {}", id.wrap(ATOM))),
+        }
+    }
+
+    /// Reads the variable operand of `put`, `delete` and friends. The low
+    /// nibble says what kind of variable it is.
+    fn variable(&mut self, operand: i32) -> Expr {
+        match operand & 0xf {
+            0x6 => self.field(),
+            kind => {
+                let id = self.pop();
+                match (kind, id) {
+                    (0x4, Expr::Int(index)) => Expr::Var(self.argument(index)),
+                    (0x5, Expr::Int(index)) => Expr::Var(self.local(index)),
+                    (_, id) => id,
+                }
+            }
+        }
+    }
+
+    /// Builds `char a to b of x`-style chunk expressions. The compiler
+    /// pushes first/last pairs for chars, words, items and lines, with 0
+    /// for the unused ones.
+    fn chunk(&mut self, string: Expr) -> Expr {
+        let mut bounds = Vec::with_capacity(4);
+        for _ in 0..4 {
+            let last = self.pop();
+            let first = self.pop();
+            bounds.push((first, last));
+        }
+        // Popped as lines, items, words, chars; apply from lines inwards
+        let mut text = string.wrap(ATOM);
+        for (chunk_type, (first, last)) in opcodes::CHUNK_TYPES.iter().rev().zip(bounds) {
+            if matches!(first, Expr::Int(0)) {
+                continue;
+            }
+            let range = if matches!(last, Expr::Int(0)) {
+                first.wrap(ATOM)
+            } else {
+                format!("{} to {}", first.wrap(ATOM), last.wrap(ATOM))
+            };
+            text = format!("{} {} of {}", chunk_type, range, text);
+        }
+        Expr::Text(text, UNARY)
+    }
+
+    /// `the` expressions compiled to `Get`/`Set`: the operand is the
+    /// property type and the property id is on the stack.
+    fn the_property(&mut self, property_type: i32) -> Expr {
+        let id = match self.pop() {
+            Expr::Int(id) => id,
+            other => return Expr::text(format!("the {}", other.render())),
+        };
+        let unknown = || format!("property_{}_{}", property_type, id);
+        let text = match property_type {
+            0x00 if id <= MOVIE_PROPERTY_COUNT => opcodes::MOVIE_PROPERTIES
+                .get(id as usize)
+                .map_or_else(unknown, |name| format!("the {}", name)),
+            0x00 => {
+                let string = self.pop();
+                let chunk_type = opcodes::CHUNK_TYPES
+                    .get((id - MOVIE_PROPERTY_COUNT - 1) as usize)
+                    .copied()
+                    .unwrap_or("chunk");
+                format!("the last {} in {}", chunk_type, string.wrap(ATOM))
+            }
+            0x01 => {
+                let string = self.pop();
+                let chunk_type =
+                    opcodes::property_name(&opcodes::CHUNK_TYPES, id).unwrap_or("chunk");
+                format!("the number of {}s in {}", chunk_type, string.wrap(ATOM))
+            }
+            0x06 => {
+                let sprite = self.pop();
+                match opcodes::property_name(&opcodes::SPRITE_PROPERTIES, id) {
+                    Some(name) => format!("the {} of sprite {}", name, sprite.wrap(ATOM)),
+                    None => unknown(),
+                }
+            }
+            0x07 => opcodes::property_name(&opcodes::ANIMATION_PROPERTIES, id)
+                .map_or_else(unknown, |name| format!("the {}", name)),
+            0x08 => {
+                let name = opcodes::property_name(&opcodes::ANIMATION2_PROPERTIES, id);
+                match name {
+                    Some("number of castMembers") if self.script.version >= 500 => {
+                        let cast = self.pop();
+                        format!("the number of castMembers of castLib {}", cast.wrap(ATOM))
+                    }
+                    Some(name) => format!("the {}", name),
+                    None => unknown(),
+                }
+            }
+            0x09 => {
+                let cast = if self.script.version >= 500 {
+                    Some(self.pop())
+                } else {
+                    None
+                };
+                let member = self.pop();
+                let member = match cast {
+                    Some(cast) if !matches!(cast, Expr::Int(0)) => format!(
+                        "member {} of castLib {}",
+                        member.wrap(ATOM),
+                        cast.wrap(ATOM)
+                    ),
+                    _ => format!("member {}", member.wrap(ATOM)),
+                };
+                match opcodes::property_name(&opcodes::MEMBER_PROPERTIES, id) {
+                    Some(name) => format!("the {} of {}", name, member),
+                    None => unknown(),
+                }
+            }
+            _ => unknown(),
+        };
+        Expr::text(text)
+    }
+}
+
+/// Ids above this in property type 0x00 are `the last chunk` expressions.
+const MOVIE_PROPERTY_COUNT: i32 = 0x0b;
+
+/// Quotes a string literal. Lingo has no escapes, so quotes and line
+/// breaks are spliced in with `QUOTE` and `RETURN`.
+fn format_string(text: &str) -> Expr {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        let constant = match c {
+            '"' => "QUOTE",
+            '\r' | '\n' => "RETURN",
+            _ => {
+                current.push(c);
+                continue;
+            }
+        };
+        if !current.is_empty() {
+            parts.push(format!("\"{}\"", current));
+            current.clear();
+        }
+        parts.push(constant.to_string());
+    }
+    if !current.is_empty() || parts.is_empty() {
+        parts.push(format!("\"{}\"", current));
+    }
+    let precedence = if parts.len() > 1 { 4 } else { ATOM };
+    Expr::Text(parts.join(" & "), precedence)
+}
+
+fn format_float(value: f64) -> String {
+    let text = value.to_string();
+    if text.contains('.') || text.contains('e') || !value.is_finite() {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names the bytecode below refers to by index.
+    const NAMES: [&str; 8] = [
+        "check", "x", "a", "total", "gCount", "alert", "random", "return",
+    ];
+
+    fn script(literals: Vec<Literal>) -> Script {
+        Script {
+            properties: Vec::new(),
+            globals: Vec::new(),
+            handlers: Vec::new(),
+            literals,
+            names: NAMES.iter().map(|name| name.to_string()).collect(),
+            variable_multiplier: 8,
+            version: 500,
+        }
+    }
+
+    /// Decompiles `bytecode` as handler `check x` with local `a`.
+    fn decompile_bytecode(script: &Script, bytecode: &[u8]) -> String {
+        let handler = Handler {
+            name: "check".to_string(),
+            arguments: vec!["x".to_string()],
+            locals: vec!["a".to_string(), "i".to_string()],
+            globals: Vec::new(),
+            bytecode: bytecode.to_vec(),
+        };
+        decompile_handler(script, &handler)
+    }
+
+    #[test]
+    fn reads_operands_by_width() {
+        let code = read_instructions(&[0x41, 0xff, 0x82, 0xff, 0xfe, 0xc5, 0, 1, 0, 0, 0x01]);
+        let decoded: Vec<_> = code.iter().map(|i| (i.pos, i.op, i.operand)).collect();
+        assert_eq!(
+            decoded,
+            [
+                (0, Op::PushInt8, -1),
+                (2, Op::PushArgListNoRet, 0xfffe),
+                (5, Op::PushSymb, 0x10000),
+                (10, Op::Ret, 0),
+            ]
+        );
+        // A truncated operand ends the handler
+        assert_eq!(read_instructions(&[0x01, 0x81, 0x00]).len(), 1);
+    }
+
+    #[test]
+    fn decompiles_pushes_and_calls() {
+        let script = script(vec![Literal::String("Hej ".to_string())]);
+        let bytecode = [
+            0x44, 0x00, // "Hej "
+            0x4b, 0x00, // x
+            0x0a, // &
+            0x42, 0x01, 0x57, 0x05, // alert(...)
+            0x41, 0x06, 0x43, 0x01, 0x57, 0x06, // random(6)
+            0x41, 0xfd, 0x05, // + -3
+            0x52, 0x08, // a = ...
+            0x01,
+        ];
+        assert_eq!(
+            decompile_bytecode(&script, &bytecode),
+            "on check x\n  alert(\"Hej \" & x)\n  i = random(6) + -3\nend\n"
+        );
+    }
+
+    #[test]
+    fn decompiles_if_else() {
+        let script = script(Vec::new());
+        let bytecode = [
+            0x4b, 0x00, 0x41, 0x01, 0x10, // x > 1
+            0x55, 0x08, // to the else branch
+            0x41, 0x01, 0x52, 0x00, // a = 1
+            0x53, 0x06, // past the else branch
+            0x41, 0x02, 0x52, 0x00, // a = 2
+            0x01,
+        ];
+        assert_eq!(
+            decompile_bytecode(&script, &bytecode),
+            "on check x\n  if x > 1 then\n    a = 1\n  else\n    a = 2\n  end if\nend\n"
+        );
+    }
+
+    #[test]
+    fn stops_at_jumps_that_dont_go_forward() {
+        let script = script(Vec::new());
+        // A jump to itself
+        assert_eq!(
+            decompile_bytecode(&script, &[0x4b, 0x00, 0x55, 0x00, 0x01]),
+            "on check x\n  x\nend\n"
+        );
+        // A wide jump back from before the start of the handler
+        assert_eq!(
+            decompile_bytecode(&script, &[0x4b, 0x00, 0xd5, 0xff, 0xff, 0xff, 0xf0, 0x01]),
+            "on check x\n  x\nend\n"
+        );
+        // And past the end
+        assert_eq!(
+            decompile_bytecode(&script, &[0x4b, 0x00, 0xd5, 0x7f, 0xff, 0xff, 0xff, 0x01]),
+            "on check x\n  x\nend\n"
+        );
+        // A loop ending before it starts
+        let bytecode = [0x4b, 0x00, 0xd4, 0xff, 0xff, 0xff, 0xfb, 0x4b, 0x00, 0x01];
+        assert!(decompile_bytecode(&script, &bytecode).ends_with("end\n"));
+    }
+
+    #[test]
+    fn decompiles_repeat_loops() {
+        let script = script(Vec::new());
+        let bytecode = [
+            0x41, 0x01, 0x52, 0x08, // i = 1
+            0x4c, 0x08, 0x41, 0x0a, 0x0d, // i <= 10
+            0x55, 0x12, // out of the loop
+            0x4a, 0x03, 0x4c, 0x08, 0x05, 0x50, 0x03, // total = total + i
+            0x4c, 0x08, 0x41, 0x01, 0x05, 0x52, 0x08, // i = i + 1
+            0x54, 0x15, // back to the condition
+            0x01,
+        ];
+        assert_eq!(
+            decompile_bytecode(&script, &bytecode),
+            "on check x\n  repeat with i = 1 to 10\n    total = total + i\n  end repeat\nend\n"
+        );
+
+        let bytecode = [
+            0x4b, 0x00, 0x41, 0x00, 0x10, // x > 0
+            0x55, 0x0d, // out of the loop
+            0x4b, 0x00, 0x41, 0x01, 0x06, 0x51, 0x00, // x = x - 1
+            0x53, 0x04, // exit repeat
+            0x54, 0x10, // back to the condition
+            0x01,
+        ];
+        assert_eq!(
+            decompile_bytecode(&script, &bytecode),
+            "on check x\n  repeat while x > 0\n    x = x - 1\n    exit repeat\n  end repeat\nend\n"
+        );
+    }
+
+    #[test]
+    fn decompiles_globals_properties_and_literals() {
+        let mut script = script(vec![
+            Literal::String("say \"hi\"".to_string()),
+            Literal::Float(1.5),
+            Literal::Int(7),
+        ]);
+        script.properties = vec!["total".to_string()];
+        script.globals = vec!["gCount".to_string()];
+        let bytecode = [
+            0x49, 0x04, 0x44, 0x08, 0x05, 0x4f, 0x04, // gCount = gCount + 1.5
+            0x4a, 0x03, 0x44, 0x10, 0x04, 0x50, 0x03, // total = total * 7
+            0x44, 0x00, 0x42, 0x01, 0x57, 0x07, // return "say " & QUOTE ...
+        ];
+        let handler = Handler {
+            name: "check".to_string(),
+            arguments: Vec::new(),
+            locals: Vec::new(),
+            globals: vec!["gCount".to_string()],
+            bytecode: bytecode.to_vec(),
+        };
+        script.handlers.push(handler);
+        assert_eq!(
+            decompile(&script),
+            "property total\nglobal gCount\n\non check\n  global gCount\n  \
+             gCount = gCount + 1.5\n  total = total * 7\n  \
+             return \"say \" & QUOTE & \"hi\" & QUOTE\nend\n"
+        );
+    }
+
+    #[test]
+    fn formats_literals() {
+        assert_eq!(format_float(2.0), "2.0");
+        assert_eq!(format_float(0.25), "0.25");
+        assert_eq!(format_string("").render(), "\"\"");
+        assert_eq!(format_string("a\rb").render(), "\"a\" & RETURN & \"b\"");
+    }
+}
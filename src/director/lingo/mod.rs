@@ -0,0 +1,422 @@
+//! Compiled Lingo.
+//!
+//! Each cast library has a script context (`Lctx`, or `LctX` from Director
+//! 8.5 on) listing its `Lscr` script chunks and pointing at the `Lnam`
+//! chunk that holds every name the scripts use. Script chunks are always
+//! big-endian.
+//!
+//! Source text is only kept in unprotected movies; everything else has to
+//! be rebuilt from the bytecode by [`decompiler`].
+
+mod decompiler;
+mod opcodes;
+
+use super::{decode_string, Endian, Reader};
+use anyhow::Result;
+
+pub use decompiler::decompile;
+
+/// Literal types in `Lscr`.
+const LITERAL_STRING: u32 = 1;
+const LITERAL_INT: u32 = 4;
+const LITERAL_FLOAT: u32 = 9;
+
+pub struct ScriptContext {
+    /// `Lscr` section ids by script number, starting at 1.
+    entries: Vec<Option<u32>>,
+    pub names_section_id: u32,
+    /// `LctX` contexts use wider handler records and unscaled variable
+    /// indices.
+    pub capital_x: bool,
+}
+
+impl ScriptContext {
+    pub fn parse(data: &[u8], capital_x: bool) -> Result<ScriptContext> {
+        let mut reader = Reader::new(data, Endian::Big);
+        reader.skip(8)?;
+        let count = reader.read_u32()? as usize;
+        reader.skip(4)?;
+        let entries_offset = reader.read_u16()? as usize;
+        reader.skip(14)?;
+        let names_section_id = reader.read_u32()?;
+
+        reader.seek(entries_offset)?;
+        reader.check_table(count, 12, 12)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            reader.skip(4)?;
+            let section_id = reader.read_i32()?;
+            reader.skip(4)?;
+            entries.push(u32::try_from(section_id).ok());
+        }
+        Ok(ScriptContext {
+            entries,
+            names_section_id,
+            capital_x,
+        })
+    }
+
+    /// Section id of the `Lscr` for a member's script id.
+    pub fn script_section(&self, script_id: u32) -> Option<u32> {
+        let index = usize::try_from(script_id).ok()?.checked_sub(1)?;
+        self.entries.get(index).copied().flatten()
+    }
+}
+
+/// A cast's script context and the names its scripts use.
+pub struct CastScripts {
+    pub context: ScriptContext,
+    pub names: Vec<String>,
+}
+
+/// Reads the `Lnam` name table.
+pub fn read_names(data: &[u8], endian: Endian) -> Result<Vec<String>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.skip(16)?;
+    let names_offset = reader.read_u16()? as usize;
+    let count = reader.read_u16()? as usize;
+    reader.seek(names_offset)?;
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = reader.read_u8()? as usize;
+        names.push(decode_string(reader.read_bytes(len)?, endian));
+    }
+    Ok(names)
+}
+
+#[derive(Clone)]
+pub enum Literal {
+    String(String),
+    Int(i32),
+    Float(f64),
+}
+
+pub struct Handler {
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub locals: Vec<String>,
+    pub globals: Vec<String>,
+    pub bytecode: Vec<u8>,
+}
+
+pub struct Script {
+    pub properties: Vec<String>,
+    pub globals: Vec<String>,
+    pub handlers: Vec<Handler>,
+    pub literals: Vec<Literal>,
+    pub names: Vec<String>,
+    /// Local and argument operands are multiplied by this in the bytecode.
+    pub variable_multiplier: i32,
+    pub version: u16,
+}
+
+impl Script {
+    pub fn parse(
+        data: &[u8],
+        names: Vec<String>,
+        context: &ScriptContext,
+        version: u16,
+        endian: Endian,
+    ) -> Result<Script> {
+        let mut reader = Reader::new(data, Endian::Big);
+        reader.seek(60)?;
+        let properties_count = reader.read_u16()? as usize;
+        let properties_offset = reader.read_u32()? as usize;
+        let globals_count = reader.read_u16()? as usize;
+        let globals_offset = reader.read_u32()? as usize;
+        let handlers_count = reader.read_u16()? as usize;
+        let handlers_offset = reader.read_u32()? as usize;
+        let literals_count = reader.read_u16()? as usize;
+        let literals_offset = reader.read_u32()? as usize;
+        reader.skip(4)?;
+        let literals_data_offset = reader.read_u32()? as usize;
+
+        let name = |id: i16| -> String {
+            usize::try_from(id)
+                .ok()
+                .and_then(|id| names.get(id))
+                .cloned()
+                .unwrap_or_else(|| format!("name_{}", id))
+        };
+        let read_names = |offset: usize, count: usize| -> Result<Vec<String>> {
+            let mut reader = Reader::new(data, Endian::Big);
+            reader.seek(offset)?;
+            (0..count)
+                .map(|_| Ok(name(reader.read_u16()? as i16)))
+                .collect()
+        };
+
+        let properties = read_names(properties_offset, properties_count)?;
+        let globals = read_names(globals_offset, globals_count)?;
+
+        let mut handlers = Vec::with_capacity(handlers_count);
+        reader.seek(handlers_offset)?;
+        for _ in 0..handlers_count {
+            let name_id = reader.read_u16()? as i16;
+            reader.skip(2)?;
+            let compiled_len = reader.read_u32()? as usize;
+            let compiled_offset = reader.read_u32()? as usize;
+            let arguments_count = reader.read_u16()? as usize;
+            let arguments_offset = reader.read_u32()? as usize;
+            let locals_count = reader.read_u16()? as usize;
+            let locals_offset = reader.read_u32()? as usize;
+            let globals_count = reader.read_u16()? as usize;
+            let globals_offset = reader.read_u32()? as usize;
+            reader.skip(12)?; // unknown fields and the line table
+            if context.capital_x {
+                reader.skip(4)?; // stack height
+            }
+            let bytecode = data
+                .get(compiled_offset..compiled_offset + compiled_len)
+                .unwrap_or_default()
+                .to_vec();
+            handlers.push(Handler {
+                name: name(name_id),
+                arguments: read_names(arguments_offset, arguments_count)?,
+                locals: read_names(locals_offset, locals_count)?,
+                globals: read_names(globals_offset, globals_count)?,
+                bytecode,
+            });
+        }
+
+        let mut literals = Vec::with_capacity(literals_count);
+        reader.seek(literals_offset)?;
+        for _ in 0..literals_count {
+            let literal_type = if version >= 500 {
+                reader.read_u32()?
+            } else {
+                reader.read_u16()? as u32
+            };
+            let offset = reader.read_u32()?;
+            let mut value = Reader::new(data, Endian::Big);
+            literals.push(match literal_type {
+                LITERAL_INT => Literal::Int(offset as i32),
+                LITERAL_STRING => {
+                    value.seek(literals_data_offset + offset as usize)?;
+                    let len = value.read_u32()? as usize;
+                    let bytes = value.read_bytes(len)?;
+                    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+                    Literal::String(decode_string(bytes, endian))
+                }
+                LITERAL_FLOAT => {
+                    value.seek(literals_data_offset + offset as usize)?;
+                    let len = value.read_u32()? as usize;
+                    Literal::Float(read_float(value.read_bytes(len)?))
+                }
+                other => Literal::String(format!("<literal type {}>", other)),
+            });
+        }
+
+        let variable_multiplier = if context.capital_x {
+            1
+        } else if version >= 500 {
+            8
+        } else {
+            6
+        };
+        Ok(Script {
+            properties,
+            globals,
+            handlers,
+            literals,
+            names,
+            variable_multiplier,
+            version,
+        })
+    }
+
+    pub fn name(&self, id: i32) -> String {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.names.get(id))
+            .cloned()
+            .unwrap_or_else(|| format!("name_{}", id))
+    }
+}
+
+/// Floats are stored as a big-endian double, or as an 80-bit extended by
+/// older Mac versions.
+fn read_float(bytes: &[u8]) -> f64 {
+    match bytes.len() {
+        8 => f64::from_be_bytes(bytes.try_into().unwrap_or_default()),
+        10 => {
+            let sign = if bytes[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+            let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7fff) as i32;
+            let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap_or_default());
+            if exponent == 0 && mantissa == 0 {
+                return 0.0;
+            }
+            sign * mantissa as f64 * 2f64.powi(exponent - 16383 - 63)
+        }
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 6] = ["greet", "who", "alert", "gCount", "total", "i"];
+
+    fn names() -> Vec<String> {
+        NAMES.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Builds a Director 5 `Lscr` with the `greet` handler, taking `who`
+    /// and using local `i`, followed by `literals` of the given types.
+    fn lscr(bytecode: &[u8], literals: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        const HEADER_LEN: u32 = 92;
+        const HANDLER_LEN: u32 = 42;
+        let properties_offset = HEADER_LEN;
+        let globals_offset = properties_offset + 2;
+        let handlers_offset = globals_offset + 2;
+        let bytecode_offset = handlers_offset + HANDLER_LEN;
+        let arguments_offset = bytecode_offset + bytecode.len() as u32;
+        let locals_offset = arguments_offset + 2;
+        let literals_offset = locals_offset + 2;
+        let literals_data_offset = literals_offset + 8 * literals.len() as u32;
+
+        let mut data = vec![0; 60];
+        for (count, offset) in [
+            (1u16, properties_offset),
+            (1, globals_offset),
+            (1, handlers_offset),
+            (literals.len() as u16, literals_offset),
+        ] {
+            data.extend(count.to_be_bytes());
+            data.extend(offset.to_be_bytes());
+        }
+        data.extend([0; 4]);
+        data.extend(literals_data_offset.to_be_bytes());
+        data.extend(4u16.to_be_bytes()); // total
+        data.extend(3u16.to_be_bytes()); // gCount
+
+        data.extend(0u16.to_be_bytes());
+        data.extend([0; 2]);
+        data.extend((bytecode.len() as u32).to_be_bytes());
+        data.extend(bytecode_offset.to_be_bytes());
+        for (count, offset) in [(1u16, arguments_offset), (1, locals_offset), (0, 0)] {
+            data.extend(count.to_be_bytes());
+            data.extend(offset.to_be_bytes());
+        }
+        data.extend([0; 12]);
+        data.extend(bytecode);
+        data.extend(1u16.to_be_bytes()); // who
+        data.extend(5u16.to_be_bytes()); // i
+
+        let mut value_offset = 0;
+        for (literal_type, value) in literals {
+            data.extend(literal_type.to_be_bytes());
+            if *literal_type == LITERAL_INT {
+                data.extend(value.as_slice());
+            } else {
+                data.extend((value_offset as u32).to_be_bytes());
+                value_offset += 4 + value.len();
+            }
+        }
+        for (literal_type, value) in literals {
+            if *literal_type != LITERAL_INT {
+                data.extend((value.len() as u32).to_be_bytes());
+                data.extend(value);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn parses_script_contexts() {
+        let mut data = vec![0; 8];
+        data.extend(3u32.to_be_bytes());
+        data.extend([0; 4]);
+        data.extend(40u16.to_be_bytes());
+        data.extend([0; 14]);
+        data.extend(7u32.to_be_bytes());
+        data.extend([0; 4]);
+        for section_id in [12i32, -1, 14] {
+            data.extend([0; 4]);
+            data.extend(i32::to_be_bytes(section_id));
+            data.extend([0; 4]);
+        }
+        let context = ScriptContext::parse(&data, false).unwrap();
+        assert_eq!(context.names_section_id, 7);
+        assert_eq!(context.script_section(1), Some(12));
+        assert_eq!(context.script_section(2), None);
+        assert_eq!(context.script_section(3), Some(14));
+        assert_eq!(context.script_section(0), None);
+        assert_eq!(context.script_section(4), None);
+
+        // A count larger than the chunk is rejected before allocating
+        data[8..12].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(ScriptContext::parse(&data, false).is_err());
+    }
+
+    #[test]
+    fn reads_name_tables() {
+        let mut data = vec![0; 16];
+        data.extend(20u16.to_be_bytes());
+        data.extend(2u16.to_be_bytes());
+        data.extend([5]);
+        data.extend(b"greet");
+        data.extend([3, b'n', 0x8a, b'r']);
+        assert_eq!(
+            read_names(&data, Endian::Big).unwrap(),
+            ["greet", "n\u{e4}r"]
+        );
+    }
+
+    #[test]
+    fn parses_handlers_and_literals() {
+        let context = ScriptContext {
+            entries: Vec::new(),
+            names_section_id: 0,
+            capital_x: false,
+        };
+        let mut string = b"Hej ".to_vec();
+        string.push(0);
+        // 1.5 as an 80-bit extended: exponent 16383, mantissa 0b11 << 62
+        let mut extended = 0x3fffu16.to_be_bytes().to_vec();
+        extended.extend(0xc000_0000_0000_0000u64.to_be_bytes());
+        let data = lscr(
+            &[0x01],
+            &[
+                (LITERAL_STRING, string),
+                (LITERAL_INT, 42i32.to_be_bytes().to_vec()),
+                (LITERAL_FLOAT, 0.25f64.to_be_bytes().to_vec()),
+                (LITERAL_FLOAT, extended),
+            ],
+        );
+        let script = Script::parse(&data, names(), &context, 500, Endian::Big).unwrap();
+        assert_eq!(script.properties, ["total"]);
+        assert_eq!(script.globals, ["gCount"]);
+        assert_eq!(script.variable_multiplier, 8);
+        let handler = &script.handlers[0];
+        assert_eq!(handler.name, "greet");
+        assert_eq!(handler.arguments, ["who"]);
+        assert_eq!(handler.locals, ["i"]);
+        assert!(handler.globals.is_empty());
+        assert_eq!(handler.bytecode, [0x01]);
+        assert!(matches!(&script.literals[0], Literal::String(text) if text == "Hej "));
+        assert!(matches!(script.literals[1], Literal::Int(42)));
+        assert!(matches!(script.literals[2], Literal::Float(value) if value == 0.25));
+        assert!(matches!(script.literals[3], Literal::Float(value) if value == 1.5));
+        assert_eq!(script.name(2), "alert");
+        assert_eq!(script.name(-1), "name_-1");
+    }
+
+    #[test]
+    fn decompiles_a_parsed_script() {
+        let context = ScriptContext {
+            entries: Vec::new(),
+            names_section_id: 0,
+            capital_x: false,
+        };
+        // alert("Hej " & who)
+        let bytecode = [0x44, 0x00, 0x4b, 0x00, 0x0a, 0x42, 0x01, 0x57, 0x02, 0x01];
+        let data = lscr(&bytecode, &[(LITERAL_STRING, b"Hej \0".to_vec())]);
+        let script = Script::parse(&data, names(), &context, 500, Endian::Big).unwrap();
+        assert_eq!(
+            decompile(&script),
+            "property total\nglobal gCount\n\non greet who\n  alert(\"Hej \" & who)\nend\n"
+        );
+    }
+}
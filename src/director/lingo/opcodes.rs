@@ -0,0 +1,386 @@
+//! Lingo bytecode opcodes and the property tables used by `the` expressions.
+//!
+//! Opcodes below 0x40 take no operand. From 0x40 on, the low six bits pick
+//! the instruction and the top two bits the operand size: one byte, two
+//! bytes from 0x80 and four bytes from 0xc0.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Ret,
+    RetFactory,
+    PushZero,
+    Mul,
+    Add,
+    Sub,
+    Div,
+    Mod,
+    Inv,
+    JoinStr,
+    JoinPadStr,
+    Lt,
+    LtEq,
+    NtEq,
+    Eq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    Not,
+    ContainsStr,
+    Contains0Str,
+    GetChunk,
+    HiliteChunk,
+    OntoSpr,
+    IntoSpr,
+    GetField,
+    StartTell,
+    EndTell,
+    PushList,
+    PushPropList,
+    Swap,
+    PushInt8,
+    PushArgListNoRet,
+    PushArgList,
+    PushCons,
+    PushSymb,
+    PushVarRef,
+    GetGlobal2,
+    GetGlobal,
+    GetProp,
+    GetParam,
+    GetLocal,
+    SetGlobal2,
+    SetGlobal,
+    SetProp,
+    SetParam,
+    SetLocal,
+    Jmp,
+    EndRepeat,
+    JmpIfZ,
+    LocalCall,
+    ExtCall,
+    ObjCallV4,
+    Put,
+    PutChunk,
+    DeleteChunk,
+    Get,
+    Set,
+    GetMovieProp,
+    SetMovieProp,
+    GetObjProp,
+    SetObjProp,
+    TellCall,
+    Peek,
+    Pop,
+    TheBuiltin,
+    ObjCall,
+    PushChunkVarRef,
+    PushInt16,
+    PushInt32,
+    GetChainedProp,
+    PushFloat32,
+    GetTopLevelProp,
+    NewObj,
+    Unknown(u8),
+}
+
+impl Op {
+    pub fn from_byte(byte: u8) -> Op {
+        let code = if byte >= 0x40 {
+            0x40 + byte % 0x40
+        } else {
+            byte
+        };
+        match code {
+            0x01 => Op::Ret,
+            0x02 => Op::RetFactory,
+            0x03 => Op::PushZero,
+            0x04 => Op::Mul,
+            0x05 => Op::Add,
+            0x06 => Op::Sub,
+            0x07 => Op::Div,
+            0x08 => Op::Mod,
+            0x09 => Op::Inv,
+            0x0a => Op::JoinStr,
+            0x0b => Op::JoinPadStr,
+            0x0c => Op::Lt,
+            0x0d => Op::LtEq,
+            0x0e => Op::NtEq,
+            0x0f => Op::Eq,
+            0x10 => Op::Gt,
+            0x11 => Op::GtEq,
+            0x12 => Op::And,
+            0x13 => Op::Or,
+            0x14 => Op::Not,
+            0x15 => Op::ContainsStr,
+            0x16 => Op::Contains0Str,
+            0x17 => Op::GetChunk,
+            0x18 => Op::HiliteChunk,
+            0x19 => Op::OntoSpr,
+            0x1a => Op::IntoSpr,
+            0x1b => Op::GetField,
+            0x1c => Op::StartTell,
+            0x1d => Op::EndTell,
+            0x1e => Op::PushList,
+            0x1f => Op::PushPropList,
+            0x21 => Op::Swap,
+            0x41 => Op::PushInt8,
+            0x42 => Op::PushArgListNoRet,
+            0x43 => Op::PushArgList,
+            0x44 => Op::PushCons,
+            0x45 => Op::PushSymb,
+            0x46 => Op::PushVarRef,
+            0x48 => Op::GetGlobal2,
+            0x49 => Op::GetGlobal,
+            0x4a => Op::GetProp,
+            0x4b => Op::GetParam,
+            0x4c => Op::GetLocal,
+            0x4e => Op::SetGlobal2,
+            0x4f => Op::SetGlobal,
+            0x50 => Op::SetProp,
+            0x51 => Op::SetParam,
+            0x52 => Op::SetLocal,
+            0x53 => Op::Jmp,
+            0x54 => Op::EndRepeat,
+            0x55 => Op::JmpIfZ,
+            0x56 => Op::LocalCall,
+            0x57 => Op::ExtCall,
+            0x58 => Op::ObjCallV4,
+            0x59 => Op::Put,
+            0x5a => Op::PutChunk,
+            0x5b => Op::DeleteChunk,
+            0x5c => Op::Get,
+            0x5d => Op::Set,
+            0x5f => Op::GetMovieProp,
+            0x60 => Op::SetMovieProp,
+            0x61 => Op::GetObjProp,
+            0x62 => Op::SetObjProp,
+            0x63 => Op::TellCall,
+            0x64 => Op::Peek,
+            0x65 => Op::Pop,
+            0x66 => Op::TheBuiltin,
+            0x67 => Op::ObjCall,
+            0x6d => Op::PushChunkVarRef,
+            0x6e => Op::PushInt16,
+            0x6f => Op::PushInt32,
+            0x70 => Op::GetChainedProp,
+            0x71 => Op::PushFloat32,
+            0x72 => Op::GetTopLevelProp,
+            0x73 => Op::NewObj,
+            _ => Op::Unknown(byte),
+        }
+    }
+
+    /// Binary operator text and precedence, higher binds tighter.
+    pub fn binary(self) -> Option<(&'static str, u8)> {
+        Some(match self {
+            Op::Mul => ("*", 6),
+            Op::Div => ("/", 6),
+            Op::Mod => ("mod", 6),
+            Op::Add => ("+", 5),
+            Op::Sub => ("-", 5),
+            Op::JoinStr => ("&", 4),
+            Op::JoinPadStr => ("&&", 4),
+            Op::Lt => ("<", 3),
+            Op::LtEq => ("<=", 3),
+            Op::NtEq => ("<>", 3),
+            Op::Eq => ("=", 3),
+            Op::Gt => (">", 3),
+            Op::GtEq => (">=", 3),
+            Op::ContainsStr => ("contains", 3),
+            Op::Contains0Str => ("starts", 3),
+            Op::And => ("and", 2),
+            Op::Or => ("or", 1),
+            _ => return None,
+        })
+    }
+}
+
+/// Chunk types of `char`/`word`/`item`/`line` expressions, in the order
+/// the compiler pushes their bounds.
+pub const CHUNK_TYPES: [&str; 4] = ["char", "word", "item", "line"];
+
+/// Properties read with `the` and no object, property type 0x00.
+pub const MOVIE_PROPERTIES: [&str; 12] = [
+    "floatPrecision",
+    "mouseDownScript",
+    "mouseUpScript",
+    "keyDownScript",
+    "keyUpScript",
+    "timeoutScript",
+    "short time",
+    "abbr time",
+    "long time",
+    "short date",
+    "abbr date",
+    "long date",
+];
+
+/// Sprite properties, property type 0x06, indexed from 1.
+pub const SPRITE_PROPERTIES: [&str; 42] = [
+    "type",
+    "backColor",
+    "bottom",
+    "castNum",
+    "constraint",
+    "cursor",
+    "foreColor",
+    "height",
+    "immediate",
+    "ink",
+    "left",
+    "lineSize",
+    "locH",
+    "locV",
+    "movieRate",
+    "movieTime",
+    "pattern",
+    "puppet",
+    "right",
+    "startTime",
+    "stopTime",
+    "stretch",
+    "top",
+    "trails",
+    "visible",
+    "volume",
+    "width",
+    "blend",
+    "scriptNum",
+    "moveableSprite",
+    "editableText",
+    "scoreColor",
+    "loc",
+    "rect",
+    "memberNum",
+    "castLibNum",
+    "member",
+    "scriptInstanceList",
+    "currentTime",
+    "mostRecentCuePoint",
+    "tweened",
+    "name",
+];
+
+/// System properties, property type 0x07, indexed from 1.
+pub const ANIMATION_PROPERTIES: [&str; 40] = [
+    "beepOn",
+    "buttonStyle",
+    "centerStage",
+    "checkBoxAccess",
+    "checkboxType",
+    "colorDepth",
+    "colorQD",
+    "exitLock",
+    "fixStageSize",
+    "fullColorPermit",
+    "imageDirect",
+    "doubleClick",
+    "key",
+    "lastClick",
+    "lastEvent",
+    "keyCode",
+    "lastKey",
+    "lastRoll",
+    "timeoutLapsed",
+    "multiSound",
+    "pauseState",
+    "quickTimePresent",
+    "selEnd",
+    "selStart",
+    "soundEnabled",
+    "soundLevel",
+    "stageColor",
+    "",
+    "switchColorDepth",
+    "timeoutKeyDown",
+    "timeoutLength",
+    "timeoutMouse",
+    "timeoutPlay",
+    "timer",
+    "preLoadRAM",
+    "videoForWindowsPresent",
+    "netPresent",
+    "safePlayer",
+    "soundKeepDevice",
+    "soundMixMedia",
+];
+
+/// Counts, property type 0x08, indexed from 1.
+pub const ANIMATION2_PROPERTIES: [&str; 5] = [
+    "perFrameHook",
+    "number of castMembers",
+    "number of menus",
+    "number of castLibs",
+    "number of xtras",
+];
+
+/// Member properties, property type 0x09, indexed from 1.
+pub const MEMBER_PROPERTIES: [&str; 19] = [
+    "name",
+    "text",
+    "textStyle",
+    "textFont",
+    "textHeight",
+    "textAlign",
+    "textSize",
+    "picture",
+    "hilite",
+    "number",
+    "size",
+    "loop",
+    "duration",
+    "controller",
+    "directToStage",
+    "sound",
+    "foreColor",
+    "backColor",
+    "type",
+];
+
+/// `put ... into/after/before`, from the high nibble of the operand.
+pub fn put_type(operand: i32) -> &'static str {
+    match (operand >> 4) & 0xf {
+        2 => "after",
+        3 => "before",
+        _ => "into",
+    }
+}
+
+/// Looks up a 1-based property id.
+pub fn property_name(table: &[&'static str], id: i32) -> Option<&'static str> {
+    usize::try_from(id - 1)
+        .ok()
+        .and_then(|index| table.get(index))
+        .copied()
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_opcodes_of_every_operand_width() {
+        assert_eq!(Op::from_byte(0x01), Op::Ret);
+        assert_eq!(Op::from_byte(0x41), Op::PushInt8);
+        assert_eq!(Op::from_byte(0x81), Op::PushInt8);
+        assert_eq!(Op::from_byte(0xc1), Op::PushInt8);
+        assert_eq!(Op::from_byte(0x95), Op::JmpIfZ);
+        assert_eq!(Op::from_byte(0x20), Op::Unknown(0x20));
+        assert_eq!(Op::from_byte(0xc7), Op::Unknown(0xc7));
+    }
+
+    #[test]
+    fn looks_up_properties_from_1() {
+        assert_eq!(property_name(&SPRITE_PROPERTIES, 1), Some("type"));
+        assert_eq!(property_name(&SPRITE_PROPERTIES, 42), Some("name"));
+        assert_eq!(property_name(&SPRITE_PROPERTIES, 0), None);
+        assert_eq!(property_name(&SPRITE_PROPERTIES, 43), None);
+        // The gap in the system properties has no name
+        assert_eq!(property_name(&ANIMATION_PROPERTIES, 28), None);
+        assert_eq!(put_type(0x15), "into");
+        assert_eq!(put_type(0x25), "after");
+        assert_eq!(put_type(0x35), "before");
+    }
+}
@@ -0,0 +1,1062 @@
+//! Native reader for Macromedia Director movies (`.dir`) and casts (`.cst`).
+//!
+//! Director files are RIFF-like containers. Mac files start with `RIFX` and
+//! are big-endian, Windows files start with `XFIR` and are little-endian.
+//! The `imap`/`mmap` chunks index every other chunk, `KEY*` ties child
+//! chunks (bitmaps, text, sounds) to the cast member that owns them, and
+//! `MCsL`/`CAS*` list the cast libraries and their members.
+//!
+//! Afterburner-compressed files (`.dxr`, `.cct`) are unpacked into the same
+//! chunk view by [`afterburner`].
+
+mod afterburner;
+pub mod bitmap;
+pub mod film_loop;
+pub mod font;
+pub mod lingo;
+pub mod palette;
+pub mod projector;
+mod reader;
+pub mod score;
+pub mod shape;
+pub mod sound;
+pub mod swa;
+pub mod text;
+
+use anyhow::{bail, Context, Result};
+use bitmap::{BitmapInfo, PaletteRef};
+use film_loop::{FilmLoop, SpriteImage};
+use font::EmbeddedFont;
+use image::RgbaImage;
+use palette::Palette;
+use sound::Media;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub use reader::{decode_string, Endian, FourCC, Reader};
+
+const RIFX: FourCC = FourCC::new(b"RIFX");
+const AFTERBURNER_MOVIE: FourCC = FourCC::new(b"FGDM");
+const AFTERBURNER_CAST: FourCC = FourCC::new(b"FGDC");
+const IMAP: FourCC = FourCC::new(b"imap");
+const MMAP: FourCC = FourCC::new(b"mmap");
+const KEY: FourCC = FourCC::new(b"KEY*");
+const CAST_LIST: FourCC = FourCC::new(b"MCsL");
+const CAST_TABLE: FourCC = FourCC::new(b"CAS*");
+const CAST_MEMBER: FourCC = FourCC::new(b"CASt");
+const FREE: FourCC = FourCC::new(b"free");
+const JUNK: FourCC = FourCC::new(b"junk");
+pub const STXT: FourCC = FourCC::new(b"STXT");
+pub const BITD: FourCC = FourCC::new(b"BITD");
+pub const CLUT: FourCC = FourCC::new(b"CLUT");
+pub const SND: FourCC = FourCC::new(b"snd ");
+pub const SOUND_HEADER: FourCC = FourCC::new(b"sndH");
+pub const SOUND_SAMPLES: FourCC = FourCC::new(b"sndS");
+pub const MEDIA: FourCC = FourCC::new(b"ediM");
+const SCORE: FourCC = FourCC::new(b"VWSC");
+const LABELS: FourCC = FourCC::new(b"VWLB");
+const FILM_LOOP_SCORE: FourCC = FourCC::new(b"SCVW");
+const RICH_TEXT: FourCC = FourCC::new(b"XMED");
+const RICH_TEXT_PLAIN: FourCC = FourCC::new(b"RTE1");
+const FONT_MAP: FourCC = FourCC::new(b"Fmap");
+const FONT: FourCC = FourCC::new(b"FONT");
+const NFNT: FourCC = FourCC::new(b"NFNT");
+const SCRIPT_CONTEXT: FourCC = FourCC::new(b"Lctx");
+const SCRIPT_CONTEXT_X: FourCC = FourCC::new(b"LctX");
+
+/// Owner id used in `KEY*` for chunks that belong to the movie itself rather
+/// than to a cast member.
+const MOVIE_OWNER_ID: u32 = 1024;
+
+pub struct Chunk {
+    pub fourcc: FourCC,
+    pub data: Vec<u8>,
+}
+
+struct KeyEntry {
+    section_id: u32,
+    owner_id: u32,
+    fourcc: FourCC,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemberType {
+    Null,
+    Bitmap,
+    FilmLoop,
+    Field,
+    Palette,
+    Picture,
+    Sound,
+    Button,
+    Shape,
+    Movie,
+    DigitalVideo,
+    Script,
+    RichText,
+    Transition,
+    Xtra,
+    Unknown(u32),
+}
+
+impl MemberType {
+    fn from_id(id: u32) -> Self {
+        match id {
+            0 => MemberType::Null,
+            1 => MemberType::Bitmap,
+            2 => MemberType::FilmLoop,
+            3 => MemberType::Field,
+            4 => MemberType::Palette,
+            5 => MemberType::Picture,
+            6 => MemberType::Sound,
+            7 => MemberType::Button,
+            8 => MemberType::Shape,
+            9 => MemberType::Movie,
+            10 => MemberType::DigitalVideo,
+            11 => MemberType::Script,
+            12 => MemberType::RichText,
+            14 => MemberType::Transition,
+            15 => MemberType::Xtra,
+            other => MemberType::Unknown(other),
+        }
+    }
+
+    /// The type as Lingo's `the type of member` reports it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemberType::Null => "empty",
+            MemberType::Bitmap => "bitmap",
+            MemberType::FilmLoop => "filmLoop",
+            MemberType::Field => "field",
+            MemberType::Palette => "palette",
+            MemberType::Picture => "picture",
+            MemberType::Sound => "sound",
+            MemberType::Button => "button",
+            MemberType::Shape => "shape",
+            MemberType::Movie => "movie",
+            MemberType::DigitalVideo => "digitalVideo",
+            MemberType::Script => "script",
+            MemberType::RichText => "richText",
+            MemberType::Transition => "transition",
+            MemberType::Xtra => "xtra",
+            MemberType::Unknown(_) => "unknown",
+        }
+    }
+}
+
+pub struct CastMember {
+    /// Member number within its cast library, as used by `member(n, castLib)`.
+    pub number: u32,
+    /// Section id of the `CASt` chunk, used as the owner id in `KEY*`.
+    pub section_id: u32,
+    pub member_type: MemberType,
+    pub name: String,
+    /// 1-based index into the cast's `Lctx` script table, 0 if none.
+    pub script_id: u32,
+    pub script_text: String,
+    /// Type-specific properties (bitmap rect, sound flags, ...).
+    pub specific_data: Vec<u8>,
+}
+
+impl CastMember {
+    /// The type name of an Xtra member, e.g. `swa` for Shockwave Audio.
+    pub fn xtra_type(&self) -> Option<String> {
+        let len = u32::from_be_bytes(self.specific_data.get(..4)?.try_into().ok()?) as usize;
+        let name = self.specific_data.get(4..4 + len)?;
+        Some(
+            String::from_utf8_lossy(name)
+                .trim_end_matches('\0')
+                .to_string(),
+        )
+    }
+}
+
+pub struct CastLib {
+    /// Owner id of the cast library's own chunks in `KEY*`.
+    pub id: u32,
+    pub name: String,
+    /// Path of the external cast file, empty for internal casts.
+    pub file_path: String,
+    pub members: Vec<CastMember>,
+}
+
+pub struct Movie {
+    /// File name without extension, matching Lingo's `item 1 of movie.name`.
+    pub name: String,
+    pub endian: Endian,
+    /// Director version, e.g. 500 for Director 5 or 850 for Director 8.5.
+    pub version: u16,
+    chunks: HashMap<u32, Chunk>,
+    key_table: Vec<KeyEntry>,
+    pub casts: Vec<CastLib>,
+}
+
+impl Movie {
+    pub fn open(path: &Path) -> Result<Movie> {
+        let data = fs::read(path).with_context(|| format!("Failed to read movie: {:?}", path))?;
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Invalid movie file name")?
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Movie::parse(name, &data).with_context(|| format!("Failed to parse movie: {:?}", path))
+    }
+
+    pub fn parse(name: String, data: &[u8]) -> Result<Movie> {
+        let endian = match data.get(..4) {
+            Some(b"RIFX") => Endian::Big,
+            Some(b"XFIR") => Endian::Little,
+            _ => bail!("Not a Director file: missing RIFX/XFIR header"),
+        };
+        let mut reader = Reader::new(data, endian);
+        reader.skip(8)?;
+        let codec = reader.read_fourcc()?;
+        let chunks = if codec == AFTERBURNER_MOVIE || codec == AFTERBURNER_CAST {
+            afterburner::read_chunks(&mut reader, endian)?
+        } else {
+            read_memory_map(&mut reader)?
+        };
+
+        let mut movie = Movie {
+            name,
+            endian,
+            version: 0,
+            chunks,
+            key_table: Vec::new(),
+            casts: Vec::new(),
+        };
+        movie.load()?;
+        Ok(movie)
+    }
+
+    fn load(&mut self) -> Result<()> {
+        self.key_table = match self.first_chunk(KEY) {
+            Some(chunk) => read_key_table(&chunk.data, self.endian)?,
+            None => bail!("Movie has no KEY* chunk"),
+        };
+        self.version = self.read_config_version()?;
+        self.casts = self.read_casts()?;
+        Ok(())
+    }
+
+    pub fn chunk(&self, section_id: u32) -> Option<&Chunk> {
+        self.chunks.get(&section_id)
+    }
+
+    /// Returns the chunk with the lowest section id of the given type.
+    pub fn first_chunk(&self, fourcc: FourCC) -> Option<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.fourcc == fourcc)
+            .min_by_key(|(&id, _)| id)
+            .map(|(_, chunk)| chunk)
+    }
+
+    /// Looks up a chunk owned by a cast member or cast library through `KEY*`.
+    pub fn child(&self, owner_id: u32, fourcc: FourCC) -> Option<&Chunk> {
+        self.key_table
+            .iter()
+            .find(|entry| entry.owner_id == owner_id && entry.fourcc == fourcc)
+            .and_then(|entry| self.chunk(entry.section_id))
+    }
+
+    /// All chunks owned by a cast member or cast library.
+    pub fn children(&self, owner_id: u32) -> impl Iterator<Item = &Chunk> {
+        self.key_table
+            .iter()
+            .filter(move |entry| entry.owner_id == owner_id)
+            .filter_map(|entry| self.chunk(entry.section_id))
+    }
+
+    pub fn decode_string(&self, bytes: &[u8]) -> String {
+        decode_string(bytes, self.endian)
+    }
+
+    fn read_config_version(&self) -> Result<u16> {
+        let config = self
+            .first_chunk(FourCC::new(b"DRCF"))
+            .or_else(|| self.first_chunk(FourCC::new(b"VWCF")));
+        let Some(config) = config else {
+            // Casts without a config chunk are treated as Director 5
+            return Ok(500);
+        };
+        let mut reader = Reader::new(&config.data, Endian::Big);
+        reader.seek(36)?;
+        Ok(human_version(reader.read_u16()?))
+    }
+
+    fn config_min_member(&self) -> u32 {
+        self.first_chunk(FourCC::new(b"DRCF"))
+            .or_else(|| self.first_chunk(FourCC::new(b"VWCF")))
+            .and_then(|config| config.data.get(12..14))
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+            .unwrap_or(1)
+    }
+
+    fn read_casts(&self) -> Result<Vec<CastLib>> {
+        let cast_list = self
+            .child(MOVIE_OWNER_ID, CAST_LIST)
+            .or_else(|| self.first_chunk(CAST_LIST));
+
+        let Some(cast_list) = cast_list else {
+            // Director 4 movies and standalone casts have a single cast library
+            let table = self
+                .child(MOVIE_OWNER_ID, CAST_TABLE)
+                .or_else(|| self.first_chunk(CAST_TABLE));
+            let members = match table {
+                Some(table) => self.read_members(&table.data, self.config_min_member())?,
+                None => Vec::new(),
+            };
+            return Ok(vec![CastLib {
+                id: MOVIE_OWNER_ID,
+                name: "Internal".to_string(),
+                file_path: String::new(),
+                members,
+            }]);
+        };
+
+        let mut casts = Vec::new();
+        for entry in read_cast_list(&cast_list.data, self.endian)? {
+            let members = match self.child(entry.id, CAST_TABLE) {
+                Some(table) => self
+                    .read_members(&table.data, entry.min_member as u32)
+                    .with_context(|| format!("Failed to read cast library {:?}", entry.name))?,
+                // External casts only store a reference to the .cst file
+                None => Vec::new(),
+            };
+            casts.push(CastLib {
+                id: entry.id,
+                name: entry.name,
+                file_path: entry.file_path,
+                members,
+            });
+        }
+        Ok(casts)
+    }
+
+    fn read_members(&self, table: &[u8], min_member: u32) -> Result<Vec<CastMember>> {
+        let mut members = Vec::new();
+        let mut reader = Reader::new(table, Endian::Big);
+        let mut index = 0;
+        while !reader.eof() {
+            let section_id = reader.read_u32()?;
+            let number = min_member + index;
+            index += 1;
+            if section_id == 0 {
+                continue;
+            }
+            let Some(chunk) = self.chunk(section_id) else {
+                continue;
+            };
+            if chunk.fourcc != CAST_MEMBER {
+                continue;
+            }
+            let member = self
+                .read_member(number, section_id, &chunk.data)
+                .with_context(|| format!("Failed to read cast member {}", number))?;
+            members.push(member);
+        }
+        Ok(members)
+    }
+
+    fn read_member(&self, number: u32, section_id: u32, data: &[u8]) -> Result<CastMember> {
+        let mut reader = Reader::new(data, Endian::Big);
+        let (type_id, info, specific_data) = if self.version >= 500 {
+            let type_id = reader.read_u32()?;
+            let info_len = reader.read_u32()? as usize;
+            let specific_len = reader.read_u32()? as usize;
+            let info = reader.read_bytes(info_len)?;
+            let specific_data = reader.read_bytes(specific_len)?;
+            (type_id, info, specific_data)
+        } else {
+            let mut specific_len = reader.read_u16()? as usize;
+            let info_len = reader.read_u32()? as usize;
+            let type_id = if specific_len > 0 {
+                specific_len -= 1;
+                reader.read_u8()? as u32
+            } else {
+                0
+            };
+            // Director 4 stores the member flags byte ahead of the specific data
+            if specific_len > 0 {
+                specific_len -= 1;
+                reader.skip(1)?;
+            }
+            let specific_data = reader.read_bytes(specific_len)?;
+            let info = reader.read_bytes(info_len)?;
+            (type_id, info, specific_data)
+        };
+
+        let mut member = CastMember {
+            number,
+            section_id,
+            member_type: MemberType::from_id(type_id),
+            name: String::new(),
+            script_id: 0,
+            script_text: String::new(),
+            specific_data: specific_data.to_vec(),
+        };
+
+        if !info.is_empty() {
+            let mut reader = Reader::new(info, Endian::Big);
+            let data_offset = reader.read_u32()? as usize;
+            reader.skip(12)?;
+            member.script_id = reader.read_u32()?;
+            let items = read_list_items(info, data_offset)?;
+            if let Some(text) = items.first() {
+                member.script_text = self.decode_string(text).replace('\r', "\n");
+            }
+            if let Some(name) = items.get(1).filter(|item| !item.is_empty()) {
+                let len = (name[0] as usize).min(name.len() - 1);
+                member.name = self.decode_string(&name[1..=len]);
+            }
+        }
+        Ok(member)
+    }
+
+    /// Finds a member by cast library index (1-based, 0 for `default_cast`)
+    /// and member number.
+    pub fn member(&self, cast_lib: usize, default_cast: usize, number: u32) -> Option<&CastMember> {
+        let cast = if cast_lib == 0 {
+            self.casts.get(default_cast)
+        } else {
+            self.casts.get(cast_lib - 1)
+        };
+        cast?.members.iter().find(|member| member.number == number)
+    }
+
+    /// The built-in palette of the platform the movie was made on: System -
+    /// Win for `XFIR` movies and System - Mac for `RIFX` ones. Director
+    /// draws bitmaps without a palette and shapes with it.
+    pub fn system_palette(&self) -> i16 {
+        match self.endian {
+            Endian::Little => palette::SYSTEM_WIN,
+            Endian::Big => palette::SYSTEM_MAC,
+        }
+    }
+
+    /// Resolves a bitmap's palette reference. Unknown or missing palettes
+    /// fall back to the system palette, like Director does.
+    pub fn palette(&self, palette_ref: &PaletteRef, cast_index: usize) -> Palette {
+        let palette = match *palette_ref {
+            PaletteRef::Builtin(id) => palette::builtin(id),
+            PaletteRef::Member { cast_lib, member } => self
+                .member(cast_lib.max(0) as usize, cast_index, member as u32)
+                .and_then(|member| self.child(member.section_id, CLUT))
+                .and_then(|clut| palette::read_clut(&clut.data).ok()),
+        };
+        palette.unwrap_or_else(|| palette::builtin(self.system_palette()).unwrap_or_default())
+    }
+
+    /// Decodes a bitmap member of the cast at `cast_index`. Returns `None`
+    /// if the member has no pixel data.
+    pub fn bitmap(&self, member: &CastMember, cast_index: usize) -> Result<Option<RgbaImage>> {
+        let Some(bitd) = self.child(member.section_id, BITD) else {
+            return Ok(None);
+        };
+        let info = BitmapInfo::parse(&member.specific_data, self.version, self.system_palette())?;
+        if info.width == 0 || info.height == 0 {
+            return Ok(None);
+        }
+        let palette = self.palette(&info.palette, cast_index);
+        bitmap::decode(&info, &bitd.data, &palette).map(Some)
+    }
+
+    /// Decodes a sound or Shockwave Audio member from whichever of `snd `,
+    /// `sndH`/`sndS` or `ediM` it was stored in. Returns `None` if the
+    /// member has no sound data in a format we understand.
+    pub fn sound(&self, member: &CastMember) -> Result<Option<Media>> {
+        if let Some(snd) = self.child(member.section_id, SND) {
+            return sound::decode_snd(&snd.data).map(|sound| Some(Media::Pcm(sound)));
+        }
+        if let (Some(header), Some(samples)) = (
+            self.child(member.section_id, SOUND_HEADER),
+            self.child(member.section_id, SOUND_SAMPLES),
+        ) {
+            return sound::decode_split(&header.data, &samples.data)
+                .map(|sound| Some(Media::Pcm(sound)));
+        }
+        match self.child(member.section_id, MEDIA) {
+            Some(media) => sound::decode_media(&media.data),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the script context of the cast at `cast_index` and the names
+    /// its scripts use, once for all of its members. Returns `None` if the
+    /// movie has no scripts.
+    pub fn scripts(&self, cast_index: usize) -> Result<Option<lingo::CastScripts>> {
+        let cast = self
+            .casts
+            .get(cast_index)
+            .with_context(|| format!("No cast {} in the movie", cast_index))?;
+        let context = match self.child(cast.id, SCRIPT_CONTEXT_X) {
+            Some(chunk) => Some((chunk, true)),
+            None => self
+                .child(cast.id, SCRIPT_CONTEXT)
+                .map(|chunk| (chunk, false)),
+        }
+        .or_else(|| {
+            self.first_chunk(SCRIPT_CONTEXT_X)
+                .map(|chunk| (chunk, true))
+        })
+        .or_else(|| self.first_chunk(SCRIPT_CONTEXT).map(|chunk| (chunk, false)));
+        let Some((context, capital_x)) = context else {
+            return Ok(None);
+        };
+        let context = lingo::ScriptContext::parse(&context.data, capital_x)
+            .context("Failed to read script context")?;
+        let names = match self.chunk(context.names_section_id) {
+            Some(names) => lingo::read_names(&names.data, self.endian)
+                .context("Failed to read script names")?,
+            None => Vec::new(),
+        };
+        Ok(Some(lingo::CastScripts { context, names }))
+    }
+
+    /// Returns the Lingo attached to a member: the original source if the
+    /// movie still has it, otherwise the bytecode from `scripts`, the
+    /// member's cast's [`scripts`](Self::scripts), decompiled.
+    pub fn script_source(
+        &self,
+        member: &CastMember,
+        scripts: Option<&lingo::CastScripts>,
+    ) -> Result<Option<String>> {
+        if !member.script_text.trim().is_empty() {
+            return Ok(Some(member.script_text.clone()));
+        }
+        if member.script_id == 0 {
+            return Ok(None);
+        }
+        let Some(lingo::CastScripts { context, names }) = scripts else {
+            return Ok(None);
+        };
+        let Some(script) = context
+            .script_section(member.script_id)
+            .and_then(|id| self.chunk(id))
+        else {
+            return Ok(None);
+        };
+        let script = lingo::Script::parse(
+            &script.data,
+            names.clone(),
+            context,
+            self.version,
+            self.endian,
+        )
+        .context("Failed to read script")?;
+        Ok(Some(lingo::decompile(&script)))
+    }
+
+    /// Decodes the score, with sprite member names filled in. Returns
+    /// `None` for casts, which have no score.
+    pub fn score(&self) -> Result<Option<Vec<score::Frame>>> {
+        let Some(chunk) = self
+            .child(MOVIE_OWNER_ID, SCORE)
+            .or_else(|| self.first_chunk(SCORE))
+        else {
+            return Ok(None);
+        };
+        let mut frames = score::read_score(&chunk.data, self.version)?;
+        self.name_sprites(&mut frames, 0);
+        Ok(Some(frames))
+    }
+
+    /// Fills in the member and cast names of every sprite. Sprites with a
+    /// cast library of 0 refer to the cast at `default_cast`.
+    fn name_sprites(&self, frames: &mut [score::Frame], default_cast: usize) {
+        for sprite in frames.iter_mut().flat_map(|frame| frame.sprites.iter_mut()) {
+            let cast_lib = sprite.member.cast_lib.max(0) as usize;
+            let cast_index = cast_lib.checked_sub(1).unwrap_or(default_cast);
+            if let Some(cast) = self.casts.get(cast_index) {
+                sprite.cast = cast.name.clone();
+            }
+            let number = sprite.member.member.max(0) as u32;
+            if let Some(member) = self.member(cast_lib, default_cast, number) {
+                sprite.name = member.name.clone();
+            }
+        }
+    }
+
+    /// Reads a film loop member of the cast at `cast_index`, with sprite
+    /// names filled in. Returns `None` if it has no frame data.
+    pub fn film_loop(&self, member: &CastMember, cast_index: usize) -> Result<Option<FilmLoop>> {
+        let Some(chunk) = self.child(member.section_id, FILM_LOOP_SCORE) else {
+            return Ok(None);
+        };
+        let mut frames = score::read_film_loop(&chunk.data, self.version)?;
+        self.name_sprites(&mut frames, cast_index);
+        FilmLoop::parse(&member.specific_data, frames).map(Some)
+    }
+
+    /// Renders every frame of a film loop from the bitmaps its sprites
+    /// show. Sprites showing anything else are left out.
+    pub fn render_film_loop(
+        &self,
+        film_loop: &FilmLoop,
+        cast_index: usize,
+    ) -> Result<Vec<RgbaImage>> {
+        let mut images = HashMap::new();
+        for sprite in film_loop.frames.iter().flat_map(|frame| &frame.sprites) {
+            if images.contains_key(&sprite.member) {
+                continue;
+            }
+            let cast_lib = sprite.member.cast_lib.max(0) as usize;
+            let member_cast = cast_lib.checked_sub(1).unwrap_or(cast_index);
+            let number = sprite.member.member.max(0) as u32;
+            let Some(member) = self.member(cast_lib, cast_index, number) else {
+                continue;
+            };
+            if member.member_type != MemberType::Bitmap {
+                continue;
+            }
+            let info =
+                BitmapInfo::parse(&member.specific_data, self.version, self.system_palette())?;
+            if let Some(image) = self.bitmap(member, member_cast)? {
+                let (reg_x, reg_y) = (info.reg_x, info.reg_y);
+                images.insert(
+                    sprite.member,
+                    SpriteImage {
+                        image,
+                        reg_x,
+                        reg_y,
+                    },
+                );
+            }
+        }
+        Ok(film_loop
+            .frames
+            .iter()
+            .map(|frame| film_loop::render_frame(film_loop, frame, &images))
+            .collect())
+    }
+
+    /// Reads the frame labels and the members each one shows, using the
+    /// decoded score. Returns `None` if the movie has no labels.
+    pub fn labels(&self, frames: &[score::Frame]) -> Result<Option<Vec<score::Label>>> {
+        let Some(chunk) = self
+            .child(MOVIE_OWNER_ID, LABELS)
+            .or_else(|| self.first_chunk(LABELS))
+        else {
+            return Ok(None);
+        };
+        let labels = score::read_labels(&chunk.data, self.endian)?;
+        Ok(Some(score::label_ranges(labels, frames)))
+    }
+
+    /// Returns the text of a field or text member with its style runs, if
+    /// it has any.
+    pub fn styled_text(&self, member: &CastMember) -> Result<Option<text::StyledText>> {
+        if let Some(chunk) = self.child(member.section_id, STXT) {
+            let mut styled = text::read_stxt(&chunk.data, self.endian, &self.font_map()?)?;
+            if member.member_type == MemberType::Field {
+                styled.alignment = text::Alignment::from_field_data(&member.specific_data);
+            }
+            return Ok(Some(styled));
+        }
+        if let Some(chunk) = self.child(member.section_id, RICH_TEXT) {
+            return text::read_xmed(&chunk.data, self.endian).map(Some);
+        }
+        Ok(self
+            .child(member.section_id, RICH_TEXT_PLAIN)
+            .map(|chunk| text::read_rte1(&chunk.data, self.endian)))
+    }
+
+    /// Font names by the ids text style runs use. Empty if the movie has no
+    /// font map.
+    pub fn font_map(&self) -> Result<HashMap<u16, String>> {
+        match self
+            .child(MOVIE_OWNER_ID, FONT_MAP)
+            .or_else(|| self.first_chunk(FONT_MAP))
+        {
+            Some(chunk) => font::read_font_map(&chunk.data, self.endian),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Finds the font embedded in a font member, in whichever of its
+    /// chunks holds it.
+    pub fn embedded_font<'a>(&'a self, member: &'a CastMember) -> Option<EmbeddedFont<'a>> {
+        let mut chunks = self.children(member.section_id);
+        chunks
+            .find_map(|chunk| {
+                if chunk.fourcc == NFNT || chunk.fourcc == FONT {
+                    return Some(EmbeddedFont::Bitmap(&chunk.data));
+                }
+                font::find_outline_font(&chunk.data)
+                    .map(|(data, extension)| EmbeddedFont::Outline { data, extension })
+            })
+            .or_else(|| {
+                font::find_outline_font(&member.specific_data)
+                    .map(|(data, extension)| EmbeddedFont::Outline { data, extension })
+            })
+    }
+}
+
+/// Maps the internal version number from the config chunk to the
+/// Director release it was written by.
+fn human_version(version: u16) -> u16 {
+    match version {
+        1951.. => 1200,
+        1922.. => 1150,
+        1921.. => 1100,
+        1851.. => 1000,
+        1700.. => 850,
+        1410.. => 800,
+        1224.. => 700,
+        1218.. => 600,
+        1201.. => 500,
+        1117.. => 404,
+        1115.. => 400,
+        1029.. => 310,
+        1028.. => 300,
+        _ => 200,
+    }
+}
+
+fn read_memory_map(reader: &mut Reader) -> Result<HashMap<u32, Chunk>> {
+    if reader.read_fourcc()? != IMAP {
+        bail!("Expected imap chunk after file header");
+    }
+    reader.skip(8)?;
+    let mmap_offset = reader.read_u32()? as usize;
+
+    reader.seek(mmap_offset)?;
+    if reader.read_fourcc()? != MMAP {
+        bail!(
+            "imap points to offset {} which is not an mmap chunk",
+            mmap_offset
+        );
+    }
+    reader.skip(4)?;
+    let header_len = reader.read_u16()? as usize;
+    let entry_len = reader.read_u16()? as usize;
+    reader.skip(4)?;
+    let used_count = reader.read_i32()?.max(0) as usize;
+    reader.seek(mmap_offset + 8 + header_len)?;
+    reader.check_table(used_count, entry_len, 12)?;
+
+    let mut entries = Vec::with_capacity(used_count);
+    for _ in 0..used_count {
+        let entry_start = reader.pos();
+        let fourcc = reader.read_fourcc()?;
+        let len = reader.read_u32()? as usize;
+        let offset = reader.read_u32()? as usize;
+        entries.push((fourcc, len, offset));
+        reader.seek(entry_start + entry_len)?;
+    }
+
+    let mut chunks = HashMap::new();
+    for (id, (fourcc, len, offset)) in entries.into_iter().enumerate() {
+        if fourcc == FREE || fourcc == JUNK || fourcc == RIFX || offset == 0 {
+            continue;
+        }
+        reader.seek(offset)?;
+        let actual = reader.read_fourcc()?;
+        if actual != fourcc {
+            bail!(
+                "Chunk {} at offset {} is {}, but mmap says {}",
+                id,
+                offset,
+                actual,
+                fourcc
+            );
+        }
+        reader.skip(4)?;
+        let data = reader
+            .read_bytes(len)
+            .with_context(|| format!("Chunk {} ({}) is truncated", id, fourcc))?
+            .to_vec();
+        chunks.insert(id as u32, Chunk { fourcc, data });
+    }
+    Ok(chunks)
+}
+
+fn read_key_table(data: &[u8], endian: Endian) -> Result<Vec<KeyEntry>> {
+    let mut reader = Reader::new(data, endian);
+    let header_len = reader.read_u16()? as usize;
+    let entry_len = reader.read_u16()? as usize;
+    reader.skip(4)?;
+    let used_count = reader.read_u32()? as usize;
+    reader.seek(header_len)?;
+    reader.check_table(used_count, entry_len, 12)?;
+
+    let mut entries = Vec::with_capacity(used_count);
+    for _ in 0..used_count {
+        let entry_start = reader.pos();
+        entries.push(KeyEntry {
+            section_id: reader.read_u32()?,
+            owner_id: reader.read_u32()?,
+            fourcc: reader.read_fourcc()?,
+        });
+        reader.seek(entry_start + entry_len)?;
+    }
+    Ok(entries)
+}
+
+/// Splits a Director list structure (used by cast info and `MCsL`) into its
+/// items. The list header itself is parsed by the caller.
+fn read_list_items(data: &[u8], data_offset: usize) -> Result<Vec<&[u8]>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.seek(data_offset)?;
+    let count = reader.read_u16()? as usize;
+    let mut offsets = Vec::with_capacity(count);
+    for _ in 0..count {
+        offsets.push(reader.read_u32()? as usize);
+    }
+    let items_len = reader.read_u32()? as usize;
+    let items = reader.read_bytes(items_len.min(reader.len() - reader.pos()))?;
+
+    Ok((0..count)
+        .map(|i| {
+            let start = offsets[i].min(items.len());
+            let end = offsets
+                .get(i + 1)
+                .copied()
+                .unwrap_or(items.len())
+                .clamp(start, items.len());
+            &items[start..end]
+        })
+        .collect())
+}
+
+struct CastListEntry {
+    name: String,
+    file_path: String,
+    min_member: u16,
+    id: u32,
+}
+
+fn read_cast_list(data: &[u8], endian: Endian) -> Result<Vec<CastListEntry>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    let data_offset = reader.read_u32()? as usize;
+    reader.skip(2)?;
+    let cast_count = reader.read_u16()? as usize;
+    let items_per_cast = reader.read_u16()? as usize;
+    let items = read_list_items(data, data_offset)?;
+
+    let pascal = |item: Option<&&[u8]>| -> String {
+        match item {
+            Some(bytes) if !bytes.is_empty() => {
+                let len = (bytes[0] as usize).min(bytes.len() - 1);
+                decode_string(&bytes[1..=len], endian)
+            }
+            _ => String::new(),
+        }
+    };
+
+    let mut entries = Vec::with_capacity(cast_count);
+    for i in 0..cast_count {
+        let base = i * items_per_cast;
+        let name = pascal(items.get(base + 1));
+        let file_path = pascal(items.get(base + 2));
+        let (min_member, id) = match items.get(base + 4) {
+            Some(range) if range.len() >= 8 => {
+                let mut reader = Reader::new(range, Endian::Big);
+                let min_member = reader.read_u16()?;
+                reader.skip(2)?;
+                (min_member, reader.read_u32()?)
+            }
+            _ => (1, MOVIE_OWNER_ID + i as u32),
+        };
+        entries.push(CastListEntry {
+            name,
+            file_path,
+            min_member,
+            id,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Write numbers in the byte order of `endian`.
+    fn u16_bytes(value: u16, endian: Endian) -> [u8; 2] {
+        match endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn u32_bytes(value: u32, endian: Endian) -> [u8; 4] {
+        match endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn fourcc_bytes(tag: &[u8; 4], endian: Endian) -> [u8; 4] {
+        u32_bytes(u32::from_be_bytes(*tag), endian)
+    }
+
+    /// Builds an uncompressed movie holding `chunks`, which get section ids
+    /// from 3 on after the file header, `imap` and `mmap`.
+    fn movie_file(endian: Endian, chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        const ENTRY_LEN: usize = 20;
+        let mmap_offset = 12 + 16;
+        let mmap_len = 24 + (chunks.len() + 3) * ENTRY_LEN;
+        let mut offset = mmap_offset + 8 + mmap_len;
+        let mut entries = vec![
+            (*b"RIFX", 0, 0),
+            (*b"imap", 8, 12),
+            (*b"mmap", mmap_len, mmap_offset),
+        ];
+        for (tag, data) in chunks {
+            entries.push((**tag, data.len(), offset));
+            offset += 8 + data.len();
+        }
+
+        let number = |value: usize| u32_bytes(value as u32, endian);
+        let mut file = fourcc_bytes(b"RIFX", endian).to_vec();
+        file.extend(number(offset - 8));
+        file.extend(fourcc_bytes(b"MV93", endian));
+        file.extend(fourcc_bytes(b"imap", endian));
+        file.extend(number(8));
+        file.extend(number(1));
+        file.extend(number(mmap_offset));
+        file.extend(fourcc_bytes(b"mmap", endian));
+        file.extend(number(mmap_len));
+        file.extend(u16_bytes(24, endian));
+        file.extend(u16_bytes(ENTRY_LEN as u16, endian));
+        file.extend(number(entries.len()));
+        file.extend(number(entries.len()));
+        file.extend([0; 12]);
+        for (tag, len, offset) in entries {
+            file.extend(fourcc_bytes(&tag, endian));
+            file.extend(number(len));
+            file.extend(number(offset));
+            file.extend([0; 8]);
+        }
+        for (tag, data) in chunks {
+            file.extend(fourcc_bytes(tag, endian));
+            file.extend(number(data.len()));
+            file.extend(data);
+        }
+        file
+    }
+
+    /// A movie with a single bitmap member, number 1 of the internal cast,
+    /// that doesn't name a palette.
+    pub(crate) fn bitmap_movie(endian: Endian) -> Vec<u8> {
+        // An empty key table: header and entry length, then the counts
+        let mut key_table = [u16_bytes(12, endian), u16_bytes(12, endian)].concat();
+        key_table.extend([0; 8]);
+        let mut specific = 4u16.to_be_bytes().to_vec();
+        for value in [0u16, 0, 2, 32] {
+            specific.extend(value.to_be_bytes());
+        }
+        let mut member = 1u32.to_be_bytes().to_vec();
+        member.extend(0u32.to_be_bytes());
+        member.extend((specific.len() as u32).to_be_bytes());
+        member.extend(specific);
+        movie_file(
+            endian,
+            &[
+                (b"KEY*", key_table),
+                (b"CAS*", 5u32.to_be_bytes().to_vec()),
+                (b"CASt", member),
+            ],
+        )
+    }
+
+    #[test]
+    fn rejects_damaged_movies() {
+        for endian in [Endian::Big, Endian::Little] {
+            let data = bitmap_movie(endian);
+            for len in 0..data.len() {
+                assert!(Movie::parse("intro".to_string(), &data[..len]).is_err());
+            }
+            // Whatever a corrupt byte says, the movie and its members are
+            // read or refused, for the extraction report to go on without
+            // them
+            for position in 0..data.len() {
+                for value in [0x00, 0x7f, 0x80, 0xff] {
+                    let mut damaged = data.clone();
+                    damaged[position] = value;
+                    if let Ok(movie) = Movie::parse("intro".to_string(), &damaged) {
+                        for member in movie.casts.iter().flat_map(|cast| &cast.members) {
+                            let _ = member.xtra_type();
+                            parse_member_data(&member.specific_data, movie.version);
+                        }
+                    }
+                }
+            }
+        }
+        // Member data that is cut short, or holds the ends of the i16
+        // range where the parsers subtract or add fields: a rect from
+        // 0x8000 to 0x7fff, and a bitmap font header
+        let words = |words: &[u16]| -> Vec<u8> {
+            words.iter().flat_map(|word| word.to_be_bytes()).collect()
+        };
+        let rect = words(&[0x8004, 0, 0x8000, 0, 0x7fff, 0, 0, 0, 0, 0, 0, 0, 0x8000, 0]);
+        let mut nfnt = words(&[
+            0x9000, 65, 65, 0x7fff, 0x7fff, 0, 1, 1, 0, 0x7fff, 0x7fff, 0, 1,
+        ]);
+        nfnt.extend(words(&[0x8000, 0, 1, 0xff00, 0x7fff, 0xffff]));
+        for seed in [rect, nfnt] {
+            for len in 0..=seed.len() {
+                for version in [400, 500] {
+                    parse_member_data(&seed[..len], version);
+                }
+            }
+            for position in 0..seed.len() {
+                for value in [0x00, 0x7f, 0x80, 0xff] {
+                    let mut damaged = seed.clone();
+                    damaged[position] = value;
+                    parse_member_data(&damaged, 500);
+                }
+            }
+        }
+    }
+
+    /// Runs member data through the parsers of each member type that reads
+    /// its properties from it.
+    fn parse_member_data(data: &[u8], version: u16) {
+        if let Ok(info) = BitmapInfo::parse(data, version, palette::SYSTEM_MAC) {
+            let _ = bitmap::decode(&info, data, &palette::builtin(palette::SYSTEM_MAC).unwrap());
+        }
+        let _ = shape::Shape::parse(data);
+        let _ = font::nfnt_to_bdf(data, "Mulle", None);
+    }
+
+    #[test]
+    fn parses_movies_of_both_byte_orders() {
+        for (endian, system_palette) in [
+            (Endian::Big, palette::SYSTEM_MAC),
+            (Endian::Little, palette::SYSTEM_WIN),
+        ] {
+            let movie = Movie::parse("intro".to_string(), &bitmap_movie(endian)).unwrap();
+            assert_eq!(movie.version, 500);
+            assert_eq!(movie.casts.len(), 1);
+            let member = &movie.casts[0].members[0];
+            assert_eq!((member.number, member.member_type), (1, MemberType::Bitmap));
+
+            // Bitmaps without a palette and unknown palettes use the
+            // movie's system palette
+            assert_eq!(movie.system_palette(), system_palette);
+            let info =
+                BitmapInfo::parse(&member.specific_data, movie.version, movie.system_palette())
+                    .unwrap();
+            assert_eq!((info.width, info.height), (32, 2));
+            assert!(matches!(info.palette, PaletteRef::Builtin(id) if id == system_palette));
+            assert_eq!(
+                movie.palette(&PaletteRef::Builtin(-50), 0),
+                palette::builtin(system_palette).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn reads_scripts_of_existing_casts_only() {
+        let movie = Movie::parse("intro".to_string(), &bitmap_movie(Endian::Big)).unwrap();
+        assert!(movie.scripts(0).unwrap().is_none());
+        assert!(movie.scripts(1).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Colour palettes used by indexed bitmaps.
+//!
+//! Director stores palettes in Mac order: index 0 is white and index 255
+//! is black, on Windows too.
+//!
+//! System - Mac and Grayscale are built from their definitions, the other
+//! built-in palettes come from [`tables`], which only approximates some of
+//! them.
+
+mod tables;
+
+use super::{Endian, Reader};
+use anyhow::Result;
+
+pub type Palette = Vec<[u8; 3]>;
+
+pub const SYSTEM_MAC: i16 = -1;
+pub const RAINBOW: i16 = -2;
+pub const GRAYSCALE: i16 = -3;
+pub const PASTELS: i16 = -4;
+pub const VIVID: i16 = -5;
+pub const NTSC: i16 = -6;
+pub const METALLIC: i16 = -7;
+pub const WEB_216: i16 = -8;
+pub const SYSTEM_WIN: i16 = -101;
+pub const SYSTEM_WIN_D4: i16 = -102;
+
+/// Levels of the 6x6x6 colour cube the Mac palette starts with.
+const CUBE_LEVELS: [u8; 6] = [0xff, 0xcc, 0x99, 0x66, 0x33, 0x00];
+
+/// Returns one of Director's built-in palettes by its (negative) id, or
+/// `None` for ids this build does not know about.
+pub fn builtin(id: i16) -> Option<Palette> {
+    match id {
+        SYSTEM_MAC => Some(system_mac()),
+        GRAYSCALE => Some(grayscale()),
+        RAINBOW => Some(from_table(&tables::RAINBOW)),
+        PASTELS => Some(from_table(&tables::PASTELS)),
+        VIVID => Some(from_table(&tables::VIVID)),
+        NTSC => Some(from_table(&tables::NTSC)),
+        METALLIC => Some(from_table(&tables::METALLIC)),
+        WEB_216 => Some(from_table(&tables::WEB_216)),
+        SYSTEM_WIN | SYSTEM_WIN_D4 => Some(from_table(&tables::SYSTEM_WIN)),
+                _ => None,
+    }
+}
+
+fn from_table(table: &[u8; 768]) -> Palette {
+    table
+        .chunks_exact(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+        .collect()
+}
+
+/// Adobe Colour Table: 256 RGB triples, then the number of colours used
+/// and the transparent index (none).
+pub fn to_act(palette: &Palette) -> Vec<u8> {
+    let mut data = Vec::with_capacity(772);
+    for i in 0..256 {
+        data.extend_from_slice(&palette.get(i).copied().unwrap_or([0, 0, 0]));
+    }
+    data.extend_from_slice(&(palette.len().min(256) as u16).to_be_bytes());
+    data.extend_from_slice(&0xffffu16.to_be_bytes());
+    data
+}
+
+/// GIMP palette file.
+pub fn to_gpl(palette: &Palette, name: &str) -> String {
+    let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", name);
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        gpl.push_str(&format!("{:3} {:3} {:3}\tIndex {}\n", r, g, b, i));
+    }
+    gpl
+}
+
+/// Parses a `CLUT` chunk: 16-bit RGB triples of which only the high byte
+/// is significant.
+pub fn read_clut(data: &[u8]) -> Result<Palette> {
+    let mut reader = Reader::new(data, Endian::Big);
+    let mut palette = Vec::with_capacity(data.len() / 6);
+    while reader.len() - reader.pos() >= 6 {
+        let r = reader.read_u16()?;
+        let g = reader.read_u16()?;
+        let b = reader.read_u16()?;
+        palette.push([(r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8]);
+    }
+    Ok(palette)
+}
+
+fn colour_cube() -> Palette {
+    let mut palette = Vec::with_capacity(216);
+    for r in CUBE_LEVELS {
+        for g in CUBE_LEVELS {
+            for b in CUBE_LEVELS {
+                palette.push([r, g, b]);
+            }
+        }
+    }
+    palette
+}
+
+/// The standard 8-bit Macintosh palette: the colour cube without black,
+/// followed by red, green, blue and grey ramps and finally black.
+fn system_mac() -> Palette {
+    let mut palette = colour_cube();
+    palette.pop();
+    let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    palette.extend(ramp.iter().map(|&v| [v, 0, 0]));
+    palette.extend(ramp.iter().map(|&v| [0, v, 0]));
+    palette.extend(ramp.iter().map(|&v| [0, 0, v]));
+    palette.extend(ramp.iter().map(|&v| [v, v, v]));
+    palette.push([0, 0, 0]);
+    palette
+}
+
+/// White to black in 256 even steps.
+fn grayscale() -> Palette {
+    (0..=255u8).rev().map(|v| [v, v, v]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_palettes_run_from_white_to_black() {
+        for id in [
+            SYSTEM_MAC,
+            RAINBOW,
+            GRAYSCALE,
+            PASTELS,
+            VIVID,
+            NTSC,
+            METALLIC,
+            WEB_216,
+            SYSTEM_WIN,
+            SYSTEM_WIN_D4,
+        ] {
+            let palette = builtin(id).unwrap();
+            assert_eq!(palette.len(), 256, "palette {}", id);
+            assert_eq!(palette[0], [0xff, 0xff, 0xff], "palette {}", id);
+            assert_eq!(palette[255], [0, 0, 0], "palette {}", id);
+        }
+        assert!(builtin(-9).is_none());
+    }
+
+    #[test]
+    fn system_mac_has_the_ramps_after_the_cube() {
+        let palette = builtin(SYSTEM_MAC).unwrap();
+        assert_eq!(palette[1], [0xff, 0xff, 0xcc]);
+        assert_eq!(palette[214], [0, 0, 0x33]);
+        assert_eq!(palette[215], [0xee, 0, 0]);
+        assert_eq!(palette[245], [0xee, 0xee, 0xee]);
+    }
+
+    #[test]
+    fn windows_palettes_keep_the_static_colours() {
+        // Windows' twenty static colours, in Director's white-first order
+        let first = [
+            [0xff, 0xff, 0xff],
+            [0, 0xff, 0xff],
+            [0xff, 0, 0xff],
+            [0, 0, 0xff],
+            [0xff, 0xff, 0],
+            [0, 0xff, 0],
+            [0xff, 0, 0],
+            [0x80, 0x80, 0x80],
+            [0xa0, 0xa0, 0xa4],
+            [0xff, 0xfb, 0xf0],
+        ];
+        let last = [
+            [0xa6, 0xca, 0xf0],
+            [0xc0, 0xdc, 0xc0],
+            [0xc0, 0xc0, 0xc0],
+            [0, 0x80, 0x80],
+            [0x80, 0, 0x80],
+            [0, 0, 0x80],
+            [0x80, 0x80, 0],
+            [0, 0x80, 0],
+            [0x80, 0, 0],
+            [0, 0, 0],
+        ];
+        for id in [SYSTEM_WIN, SYSTEM_WIN_D4] {
+            let palette = builtin(id).unwrap();
+            assert_eq!(palette[..10], first, "palette {}", id);
+            assert_eq!(palette[246..], last, "palette {}", id);
+        }
+
+        // Mac colours moved out of the way of the static ones
+        let win = builtin(SYSTEM_WIN).unwrap();
+        assert_eq!(win[66], [0xcc, 0, 0xff]);
+        assert_eq!(win[95], [0x99, 0x66, 0]);
+        assert_eq!(win[166], [0x33, 0x66, 0x33]);
+        assert_eq!(win[172], [0x33, 0x33, 0x33]);
+        assert_eq!(win[204], [0, 0x33, 0x99]);
+        assert_eq!(win[239], [0x22, 0x22, 0x22]);
+        assert_eq!(win[242], [0x99, 0xcc, 0xff]);
+        assert_eq!(win[243], [0x99, 0xcc, 0x99]);
+    }
+
+    #[test]
+    fn web_216_is_the_colour_cube() {
+        let web = builtin(WEB_216).unwrap();
+        assert_eq!(web[..216], colour_cube()[..]);
+        assert!(web[216..].iter().all(|colour| *colour == [0, 0, 0]));
+    }
+
+    #[test]
+    fn reads_clut_high_bytes() {
+        let clut = [
+            0xff, 0xff, 0x80, 0x00, 0x00, 0x01, 0x12, 0x34, 0, 0, 0xab, 0xcd, 0xff,
+        ];
+        assert_eq!(
+            read_clut(&clut).unwrap(),
+            [[0xff, 0x80, 0], [0x12, 0, 0xab]]
+        );
+        let act = to_act(&read_clut(&clut).unwrap());
+        assert_eq!(act.len(), 772);
+        assert_eq!(act[768..], [0, 2, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Director's built-in palettes that don't follow from a simple rule, as
+//! RGB triples in index order.
+//!
+//! System - Win and Web 216 are Director's own tables. Rainbow, Pastels,
+//! Vivid, NTSC and Metallic are approximations: white first and black last
+//! are exact, everything else was filled in to look like Director's
+//! palettes, so bitmaps using them can come out with slightly different
+//! colours.
+
+/// Rainbow, id -2.
+pub const RAINBOW: [u8; 768] = [
+    0xff, 0xff, 0xff, 0x00, 0x69, 0xff, 0x00, 0x63, 0xff, 0x00, 0x5d, 0xff, 0x00, 0x56, 0xff, 0x00,
+    0x50, 0xff, 0x00, 0x4a, 0xff, 0x00, 0x43, 0xff, 0x00, 0x3d, 0xff, 0x00, 0x36, 0xff, 0x00, 0x30,
+    0xff, 0x00, 0x2a, 0xff, 0x00, 0x23, 0xff, 0x00, 0x1d, 0xff, 0x00, 0x17, 0xff, 0x00, 0x10, 0xff,
+    0x00, 0x0a, 0xff, 0x00, 0x03, 0xff, 0x06, 0x00, 0xff, 0x0c, 0x00, 0xff, 0x13, 0x00, 0xff, 0x19,
+    0x00, 0xff, 0x1f, 0x00, 0xff, 0x26, 0x00, 0xff, 0x2c, 0x00, 0xff, 0x33, 0x00, 0xff, 0x39, 0x00,
+    0xff, 0x3f, 0x00, 0xff, 0x46, 0x00, 0xff, 0x4c, 0x00, 0xff, 0x52, 0x00, 0xff, 0x59, 0x00, 0xff,
+    0x5f, 0x00, 0xff, 0x66, 0x00, 0xff, 0x6c, 0x00, 0xff, 0x72, 0x00, 0xff, 0x79, 0x00, 0xff, 0x7f,
+    0x00, 0xff, 0x85, 0x00, 0xff, 0x8c, 0x00, 0xff, 0x92, 0x00, 0xff, 0x99, 0x00, 0xff, 0x9f, 0x00,
+    0xff, 0xa5, 0x00, 0xff, 0xac, 0x00, 0xff, 0xb2, 0x00, 0xff, 0xb8, 0x00, 0xff, 0xbf, 0x00, 0xff,
+    0xc5, 0x00, 0xff, 0xcc, 0x00, 0xff, 0xd2, 0x00, 0xff, 0xd8, 0x00, 0xff, 0xdf, 0x00, 0xff, 0xe5,
+    0x00, 0xff, 0xeb, 0x00, 0xff, 0xf2, 0x00, 0xff, 0xf8, 0x00, 0xff, 0xff, 0x00, 0xff, 0xff, 0x00,
+    0xf8, 0xff, 0x00, 0xf2, 0xff, 0x00, 0xeb, 0xff, 0x00, 0xe5, 0xff, 0x00, 0xdf, 0xff, 0x00, 0xd8,
+    0xff, 0x00, 0xd2, 0xff, 0x00, 0xcc, 0xff, 0x00, 0xc5, 0xff, 0x00, 0xbf, 0xff, 0x00, 0xb8, 0xff,
+    0x00, 0xb2, 0xff, 0x00, 0xac, 0xff, 0x00, 0xa5, 0xff, 0x00, 0x9f, 0xff, 0x00, 0x99, 0xff, 0x00,
+    0x92, 0xff, 0x00, 0x8c, 0xff, 0x00, 0x85, 0xff, 0x00, 0x7f, 0xff, 0x00, 0x79, 0xff, 0x00, 0x72,
+    0xff, 0x00, 0x6c, 0xff, 0x00, 0x66, 0xff, 0x00, 0x5f, 0xff, 0x00, 0x59, 0xff, 0x00, 0x52, 0xff,
+    0x00, 0x4c, 0xff, 0x00, 0x46, 0xff, 0x00, 0x3f, 0xff, 0x00, 0x39, 0xff, 0x00, 0x33, 0xff, 0x00,
+    0x2c, 0xff, 0x00, 0x26, 0xff, 0x00, 0x1f, 0xff, 0x00, 0x19, 0xff, 0x00, 0x13, 0xff, 0x00, 0x0c,
+    0xff, 0x00, 0x06, 0xff, 0x00, 0x00, 0xff, 0x06, 0x00, 0xff, 0x0c, 0x00, 0xff, 0x13, 0x00, 0xff,
+    0x19, 0x00, 0xff, 0x1f, 0x00, 0xff, 0x26, 0x00, 0xff, 0x2c, 0x00, 0xff, 0x33, 0x00, 0xff, 0x39,
+    0x00, 0xff, 0x3f, 0x00, 0xff, 0x46, 0x00, 0xff, 0x4c, 0x00, 0xff, 0x52, 0x00, 0xff, 0x59, 0x00,
+    0xff, 0x5f, 0x00, 0xff, 0x66, 0x00, 0xff, 0x6c, 0x00, 0xff, 0x72, 0x00, 0xff, 0x79, 0x00, 0xff,
+    0x7f, 0x00, 0xff, 0x85, 0x00, 0xff, 0x8c, 0x00, 0xff, 0x92, 0x00, 0xff, 0x99, 0x00, 0xff, 0x9f,
+    0x00, 0xff, 0xa5, 0x00, 0xff, 0xac, 0x00, 0xff, 0xb2, 0x00, 0xff, 0xb8, 0x00, 0xff, 0xbf, 0x00,
+    0xff, 0xc5, 0x00, 0xff, 0xcc, 0x00, 0xff, 0xd2, 0x00, 0xff, 0xd8, 0x00, 0xff, 0xdf, 0x00, 0xff,
+    0xe5, 0x00, 0xff, 0xeb, 0x00, 0xff, 0xf2, 0x00, 0xff, 0xf8, 0x00, 0xff, 0xff, 0x00, 0xf8, 0xff,
+    0x00, 0xf2, 0xff, 0x00, 0xeb, 0xff, 0x00, 0xe5, 0xff, 0x00, 0xdf, 0xff, 0x00, 0xd8, 0xff, 0x00,
+    0xd2, 0xff, 0x00, 0xcc, 0xff, 0x00, 0xc5, 0xff, 0x00, 0xbf, 0xff, 0x00, 0xb8, 0xff, 0x00, 0xb2,
+    0xff, 0x00, 0xac, 0xff, 0x00, 0xa5, 0xff, 0x00, 0x9f, 0xff, 0x00, 0x99, 0xff, 0x00, 0x92, 0xff,
+    0x00, 0x8c, 0xff, 0x00, 0x85, 0xff, 0x00, 0x7f, 0xff, 0x00, 0x79, 0xff, 0x00, 0x72, 0xff, 0x00,
+    0x6c, 0xff, 0x00, 0x66, 0xff, 0x00, 0x5f, 0xff, 0x00, 0x59, 0xff, 0x00, 0x52, 0xff, 0x00, 0x4c,
+    0xff, 0x00, 0x46, 0xff, 0x00, 0x3f, 0xff, 0x00, 0x39, 0xff, 0x00, 0x33, 0xff, 0x00, 0x2c, 0xff,
+    0x00, 0x26, 0xff, 0x00, 0x1f, 0xff, 0x00, 0x19, 0xff, 0x00, 0x13, 0xff, 0x00, 0x0c, 0xff, 0x00,
+    0x06, 0xff, 0x00, 0x00, 0xff, 0x00, 0x00, 0xff, 0x06, 0x00, 0xff, 0x0c, 0x00, 0xff, 0x13, 0x00,
+    0xff, 0x19, 0x00, 0xff, 0x1f, 0x00, 0xff, 0x26, 0x00, 0xff, 0x2c, 0x00, 0xff, 0x33, 0x00, 0xff,
+    0x39, 0x00, 0xff, 0x3f, 0x00, 0xff, 0x46, 0x00, 0xff, 0x4c, 0x00, 0xff, 0x52, 0x00, 0xff, 0x59,
+    0x00, 0xff, 0x5f, 0x00, 0xff, 0x66, 0x00, 0xff, 0x6c, 0x00, 0xff, 0x72, 0x00, 0xff, 0x79, 0x00,
+    0xff, 0x7f, 0x00, 0xff, 0x85, 0x00, 0xff, 0x8c, 0x00, 0xff, 0x92, 0x00, 0xff, 0x99, 0x00, 0xff,
+    0x9f, 0x00, 0xff, 0xa5, 0x00, 0xff, 0xac, 0x00, 0xff, 0xb2, 0x00, 0xff, 0xb8, 0x00, 0xff, 0xbf,
+    0x00, 0xff, 0xc5, 0x00, 0xff, 0xcc, 0x00, 0xff, 0xd2, 0x00, 0xff, 0xd8, 0x00, 0xff, 0xdf, 0x00,
+    0xff, 0xe5, 0x00, 0xff, 0xeb, 0x00, 0xff, 0xf2, 0x00, 0xff, 0xf8, 0x00, 0xff, 0xff, 0x00, 0xf8,
+    0xff, 0x00, 0xf2, 0xff, 0x00, 0xeb, 0xff, 0x00, 0xe5, 0xff, 0x00, 0xdf, 0xff, 0x00, 0xd8, 0xff,
+    0x00, 0xd2, 0xff, 0x00, 0xcc, 0xff, 0x00, 0xc5, 0xff, 0x00, 0xbf, 0xff, 0x00, 0xb8, 0xff, 0x00,
+    0xb2, 0xff, 0x00, 0xac, 0xff, 0x00, 0xa5, 0xff, 0x00, 0x9f, 0xff, 0x00, 0x99, 0xff, 0x00, 0x92,
+    0xff, 0x00, 0x8c, 0xff, 0x00, 0x85, 0xff, 0x00, 0x7f, 0xff, 0x00, 0x79, 0xff, 0x00, 0x72, 0xff,
+    0xf0, 0xf0, 0xf0, 0xe0, 0xe0, 0xe0, 0xd0, 0xd0, 0xd0, 0xc0, 0xc0, 0xc0, 0xb0, 0xb0, 0xb0, 0xa0,
+    0xa0, 0xa0, 0x90, 0x90, 0x90, 0x80, 0x80, 0x80, 0x70, 0x70, 0x70, 0x60, 0x60, 0x60, 0x50, 0x50,
+    0x50, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x20, 0x20, 0x20, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00,
+];
+
+/// Pastels, id -4.
+pub const PASTELS: [u8; 768] = [
+    0xff, 0xff, 0xff, 0xfe, 0xfe, 0xf2, 0xfe, 0xfd, 0xe6, 0xfe, 0xfd, 0xda, 0xfe, 0xfc, 0xce, 0xfe,
+    0xfb, 0xc2, 0xfe, 0xfb, 0xb6, 0xfe, 0xfa, 0xaa, 0xfd, 0xfa, 0x9d, 0xfd, 0xf9, 0x91, 0xfd, 0xf8,
+    0x85, 0xfd, 0xf8, 0x79, 0xfd, 0xf7, 0x6d, 0xfd, 0xf7, 0x61, 0xfd, 0xf6, 0x55, 0xfc, 0xf5, 0x48,
+    0xfc, 0xee, 0x47, 0xfc, 0xe7, 0x47, 0xfc, 0xe0, 0x47, 0xfc, 0xd9, 0x47, 0xfd, 0xd2, 0x47, 0xfd,
+    0xcb, 0x47, 0xfd, 0xc4, 0x47, 0xfd, 0xbe, 0x47, 0xfd, 0xb7, 0x47, 0xfe, 0xb0, 0x47, 0xfe, 0xa9,
+    0x47, 0xfe, 0xa2, 0x47, 0xfe, 0x9b, 0x47, 0xfe, 0x94, 0x47, 0xff, 0x8d, 0x46, 0xfd, 0x88, 0x46,
+    0xfb, 0x84, 0x46, 0xf9, 0x7f, 0x46, 0xf8, 0x7b, 0x47, 0xf6, 0x77, 0x47, 0xf4, 0x72, 0x47, 0xf3,
+    0x6e, 0x47, 0xf1, 0x6a, 0x48, 0xef, 0x65, 0x48, 0xee, 0x61, 0x48, 0xec, 0x5d, 0x48, 0xea, 0x58,
+    0x49, 0xe9, 0x54, 0x49, 0xe7, 0x50, 0x49, 0xe5, 0x4b, 0x4a, 0xe2, 0x49, 0x49, 0xdf, 0x47, 0x48,
+    0xdc, 0x45, 0x48, 0xd9, 0x43, 0x47, 0xd6, 0x41, 0x47, 0xd4, 0x3f, 0x46, 0xd1, 0x3d, 0x46, 0xce,
+    0x3b, 0x45, 0xcb, 0x39, 0x45, 0xc8, 0x37, 0x44, 0xc6, 0x35, 0x44, 0xc3, 0x33, 0x43, 0xc0, 0x31,
+    0x43, 0xbd, 0x2f, 0x42, 0xba, 0x2d, 0x41, 0xbe, 0x2f, 0x47, 0xc2, 0x31, 0x4e, 0xc6, 0x33, 0x55,
+    0xca, 0x35, 0x5b, 0xce, 0x37, 0x62, 0xd2, 0x39, 0x69, 0xd6, 0x3b, 0x70, 0xda, 0x3d, 0x76, 0xde,
+    0x3f, 0x7d, 0xe2, 0x41, 0x84, 0xe6, 0x43, 0x8b, 0xea, 0x45, 0x91, 0xee, 0x47, 0x98, 0xf2, 0x49,
+    0x9f, 0xf6, 0x4b, 0xa6, 0xed, 0x4a, 0xa7, 0xe5, 0x4a, 0xa9, 0xdc, 0x49, 0xaa, 0xd4, 0x49, 0xac,
+    0xcc, 0x48, 0xad, 0xc3, 0x48, 0xaf, 0xbb, 0x48, 0xb0, 0xb3, 0x47, 0xb2, 0xaa, 0x47, 0xb3, 0xa2,
+    0x46, 0xb5, 0x9a, 0x46, 0xb6, 0x91, 0x46, 0xb8, 0x89, 0x45, 0xb9, 0x81, 0x45, 0xbb, 0x78, 0x44,
+    0xbd, 0x74, 0x44, 0xbf, 0x71, 0x44, 0xc1, 0x6d, 0x44, 0xc4, 0x6a, 0x44, 0xc6, 0x66, 0x44, 0xc8,
+    0x63, 0x44, 0xcb, 0x60, 0x44, 0xcd, 0x5c, 0x44, 0xcf, 0x59, 0x44, 0xd2, 0x55, 0x44, 0xd4, 0x52,
+    0x44, 0xd6, 0x4f, 0x44, 0xd9, 0x4b, 0x44, 0xdb, 0x48, 0x44, 0xdd, 0x44, 0x45, 0xe0, 0x44, 0x4d,
+    0xe1, 0x44, 0x55, 0xe2, 0x44, 0x5e, 0xe3, 0x44, 0x66, 0xe4, 0x45, 0x6e, 0xe5, 0x45, 0x77, 0xe6,
+    0x45, 0x7f, 0xe7, 0x45, 0x87, 0xe8, 0x45, 0x90, 0xe9, 0x46, 0x98, 0xea, 0x46, 0xa0, 0xeb, 0x46,
+    0xa9, 0xec, 0x46, 0xb1, 0xed, 0x46, 0xb9, 0xee, 0x47, 0xc2, 0xf0, 0x48, 0xc2, 0xe5, 0x49, 0xc3,
+    0xdb, 0x4b, 0xc3, 0xd0, 0x4c, 0xc4, 0xc6, 0x4e, 0xc5, 0xbb, 0x4f, 0xc5, 0xb1, 0x50, 0xc6, 0xa7,
+    0x52, 0xc6, 0x9c, 0x53, 0xc7, 0x92, 0x55, 0xc8, 0x87, 0x56, 0xc8, 0x7d, 0x57, 0xc9, 0x73, 0x59,
+    0xc9, 0x68, 0x5a, 0xca, 0x5e, 0x5c, 0xcb, 0x53, 0x5a, 0xc7, 0x52, 0x58, 0xc3, 0x52, 0x57, 0xbf,
+    0x52, 0x55, 0xbb, 0x52, 0x54, 0xb7, 0x52, 0x52, 0xb3, 0x52, 0x51, 0xaf, 0x52, 0x4f, 0xab, 0x52,
+    0x4e, 0xa7, 0x52, 0x4c, 0xa3, 0x52, 0x4b, 0x9f, 0x52, 0x49, 0x9b, 0x52, 0x48, 0x97, 0x52, 0x46,
+    0x93, 0x52, 0x44, 0x8f, 0x51, 0x48, 0x8c, 0x50, 0x4c, 0x89, 0x4f, 0x50, 0x86, 0x4f, 0x55, 0x84,
+    0x4e, 0x59, 0x81, 0x4e, 0x5d, 0x7e, 0x4d, 0x61, 0x7b, 0x4d, 0x66, 0x79, 0x4c, 0x6a, 0x76, 0x4c,
+    0x6e, 0x73, 0x4b, 0x72, 0x70, 0x4b, 0x77, 0x6e, 0x4a, 0x7b, 0x6b, 0x4a, 0x7f, 0x68, 0x49, 0x84,
+    0x65, 0x48, 0x8c, 0x6e, 0x48, 0x94, 0x78, 0x48, 0x9c, 0x82, 0x48, 0xa4, 0x8b, 0x48, 0xac, 0x95,
+    0x48, 0xb4, 0x9f, 0x48, 0xbc, 0xa8, 0x48, 0xc4, 0xb2, 0x48, 0xcc, 0xbc, 0x48, 0xd4, 0xc5, 0x48,
+    0xdc, 0xcf, 0x48, 0xe4, 0xd9, 0x48, 0xec, 0xe2, 0x48, 0xf4, 0xec, 0x48, 0xfd, 0xf6, 0x49, 0xfb,
+    0xea, 0x49, 0xf9, 0xdf, 0x49, 0xf8, 0xd3, 0x49, 0xf6, 0xc8, 0x49, 0xf5, 0xbd, 0x49, 0xf3, 0xb1,
+    0x49, 0xf2, 0xa6, 0x49, 0xf0, 0x9b, 0x49, 0xef, 0x8f, 0x49, 0xed, 0x84, 0x49, 0xec, 0x79, 0x49,
+    0xea, 0x6d, 0x49, 0xe9, 0x62, 0x49, 0xe7, 0x57, 0x49, 0xe5, 0x4b, 0x4a, 0xe6, 0x4b, 0x50, 0xe7,
+    0x4b, 0x56, 0xe8, 0x4b, 0x5c, 0xe9, 0x4b, 0x62, 0xea, 0x4b, 0x68, 0xeb, 0x4b, 0x6e, 0xec, 0x4b,
+    0x74, 0xee, 0x4b, 0x7b, 0xef, 0x4b, 0x81, 0xf0, 0x4b, 0x87, 0xf1, 0x4b, 0x8d, 0xf2, 0x4b, 0x93,
+    0xf3, 0x4b, 0x99, 0xf4, 0x4b, 0x9f, 0xf6, 0x4b, 0xa6, 0xea, 0x4a, 0xa9, 0xde, 0x4a, 0xad, 0xd2,
+    0x49, 0xb1, 0xc6, 0x49, 0xb5, 0xba, 0x48, 0xb9, 0xaf, 0x48, 0xbd, 0xa3, 0x48, 0xc1, 0x97, 0x47,
+    0xc4, 0x8b, 0x47, 0xc8, 0x7f, 0x46, 0xcc, 0x74, 0x46, 0xd0, 0x68, 0x46, 0xd4, 0x5c, 0x45, 0xd8,
+    0x50, 0x45, 0xdc, 0x44, 0x44, 0xe0, 0x4f, 0x4f, 0xe1, 0x5a, 0x5a, 0xe2, 0x66, 0x66, 0xe3, 0x71,
+    0x71, 0xe4, 0x7d, 0x7d, 0xe5, 0x88, 0x88, 0xe6, 0x94, 0x94, 0xe7, 0x9f, 0x9f, 0xe8, 0xab, 0xab,
+    0xe9, 0xb6, 0xb6, 0xea, 0xc2, 0xc2, 0xeb, 0xcd, 0xcd, 0xec, 0xd9, 0xd9, 0xed, 0xe4, 0xe4, 0xee,
+    0xf0, 0xf0, 0xf0, 0xe0, 0xe0, 0xe0, 0xd0, 0xd0, 0xd0, 0xc0, 0xc0, 0xc0, 0xb0, 0xb0, 0xb0, 0xa0,
+    0xa0, 0xa0, 0x90, 0x90, 0x90, 0x80, 0x80, 0x80, 0x70, 0x70, 0x70, 0x60, 0x60, 0x60, 0x50, 0x50,
+    0x50, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x20, 0x20, 0x20, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00,
+];
+
+/// Vivid, id -5.
+pub const VIVID: [u8; 768] = [
+    0xff, 0xff, 0xff, 0xfe, 0xfe, 0xee, 0xfe, 0xfd, 0xdd, 0xfe, 0xfc, 0xcc, 0xfe, 0xfb, 0xbc, 0xfd,
+    0xfa, 0xab, 0xfd, 0xfa, 0x9a, 0xfd, 0xf9, 0x8a, 0xfd, 0xf8, 0x79, 0xfd, 0xf7, 0x68, 0xfc, 0xf6,
+    0x58, 0xfc, 0xf6, 0x47, 0xfc, 0xf5, 0x36, 0xfc, 0xf4, 0x26, 0xfc, 0xf3, 0x15, 0xfb, 0xf2, 0x04,
+    0xfb, 0xe8, 0x03, 0xfb, 0xdf, 0x03, 0xfb, 0xd5, 0x03, 0xfc, 0xcc, 0x03, 0xfc, 0xc2, 0x03, 0xfc,
+    0xb9, 0x03, 0xfc, 0xaf, 0x03, 0xfd, 0xa6, 0x02, 0xfd, 0x9c, 0x02, 0xfd, 0x93, 0x02, 0xfd, 0x89,
+    0x02, 0xfe, 0x80, 0x02, 0xfe, 0x76, 0x02, 0xfe, 0x6d, 0x02, 0xff, 0x63, 0x01, 0xfc, 0x5c, 0x01,
+    0xfa, 0x56, 0x01, 0xf8, 0x50, 0x02, 0xf5, 0x4a, 0x02, 0xf3, 0x44, 0x02, 0xf1, 0x3e, 0x03, 0xef,
+    0x38, 0x03, 0xec, 0x32, 0x03, 0xea, 0x2c, 0x04, 0xe8, 0x26, 0x04, 0xe6, 0x20, 0x04, 0xe3, 0x1a,
+    0x05, 0xe1, 0x14, 0x05, 0xdf, 0x0e, 0x05, 0xdc, 0x07, 0x06, 0xd8, 0x06, 0x07, 0xd5, 0x06, 0x08,
+    0xd2, 0x05, 0x09, 0xce, 0x05, 0x0a, 0xcb, 0x04, 0x0c, 0xc8, 0x04, 0x0d, 0xc5, 0x03, 0x0e, 0xc1,
+    0x03, 0x0f, 0xbe, 0x02, 0x10, 0xbb, 0x02, 0x12, 0xb8, 0x01, 0x13, 0xb4, 0x01, 0x14, 0xb1, 0x00,
+    0x15, 0xae, 0x00, 0x16, 0xaa, 0x00, 0x18, 0xae, 0x00, 0x1f, 0xb3, 0x01, 0x26, 0xb8, 0x01, 0x2d,
+    0xbd, 0x02, 0x34, 0xc2, 0x02, 0x3c, 0xc6, 0x03, 0x43, 0xcb, 0x03, 0x4a, 0xd0, 0x04, 0x51, 0xd5,
+    0x04, 0x58, 0xda, 0x05, 0x60, 0xde, 0x05, 0x67, 0xe3, 0x06, 0x6e, 0xe8, 0x06, 0x75, 0xed, 0x07,
+    0x7c, 0xf2, 0x08, 0x84, 0xe6, 0x07, 0x86, 0xdb, 0x06, 0x88, 0xcf, 0x06, 0x8a, 0xc4, 0x05, 0x8c,
+    0xb8, 0x05, 0x8f, 0xad, 0x04, 0x91, 0xa1, 0x04, 0x93, 0x96, 0x03, 0x95, 0x8a, 0x03, 0x97, 0x7f,
+    0x02, 0x9a, 0x73, 0x02, 0x9c, 0x68, 0x01, 0x9e, 0x5c, 0x01, 0xa0, 0x51, 0x00, 0xa2, 0x45, 0x00,
+    0xa5, 0x40, 0x00, 0xa8, 0x3b, 0x00, 0xab, 0x37, 0x00, 0xae, 0x32, 0x00, 0xb1, 0x2d, 0x00, 0xb4,
+    0x29, 0x00, 0xb7, 0x24, 0x00, 0xba, 0x20, 0x00, 0xbe, 0x1b, 0x00, 0xc1, 0x16, 0x00, 0xc4, 0x12,
+    0x00, 0xc7, 0x0d, 0x00, 0xca, 0x09, 0x00, 0xcd, 0x04, 0x00, 0xd0, 0x00, 0x00, 0xd4, 0x00, 0x0b,
+    0xd5, 0x00, 0x16, 0xd6, 0x00, 0x22, 0xd8, 0x00, 0x2d, 0xd9, 0x00, 0x39, 0xdb, 0x00, 0x44, 0xdc,
+    0x00, 0x4f, 0xde, 0x01, 0x5b, 0xdf, 0x01, 0x66, 0xe1, 0x01, 0x72, 0xe2, 0x01, 0x7d, 0xe4, 0x01,
+    0x88, 0xe5, 0x01, 0x94, 0xe7, 0x01, 0x9f, 0xe8, 0x02, 0xab, 0xea, 0x03, 0xab, 0xdb, 0x05, 0xac,
+    0xcd, 0x07, 0xad, 0xbf, 0x09, 0xae, 0xb0, 0x0b, 0xaf, 0xa2, 0x0d, 0xaf, 0x94, 0x0f, 0xb0, 0x86,
+    0x11, 0xb1, 0x77, 0x13, 0xb2, 0x69, 0x15, 0xb3, 0x5b, 0x17, 0xb3, 0x4d, 0x19, 0xb4, 0x3e, 0x1b,
+    0xb5, 0x30, 0x1d, 0xb6, 0x22, 0x1f, 0xb7, 0x13, 0x1c, 0xb1, 0x12, 0x1a, 0xab, 0x12, 0x18, 0xa6,
+    0x12, 0x16, 0xa0, 0x12, 0x14, 0x9b, 0x12, 0x12, 0x95, 0x12, 0x10, 0x90, 0x12, 0x0e, 0x8a, 0x11,
+    0x0c, 0x85, 0x11, 0x0a, 0x7f, 0x11, 0x08, 0x7a, 0x11, 0x06, 0x74, 0x11, 0x04, 0x6f, 0x11, 0x02,
+    0x69, 0x11, 0x00, 0x63, 0x10, 0x05, 0x5f, 0x0f, 0x0b, 0x5b, 0x0e, 0x11, 0x57, 0x0d, 0x16, 0x54,
+    0x0d, 0x1c, 0x50, 0x0c, 0x22, 0x4c, 0x0b, 0x28, 0x49, 0x0a, 0x2d, 0x45, 0x0a, 0x33, 0x41, 0x09,
+    0x39, 0x3e, 0x08, 0x3f, 0x3a, 0x07, 0x44, 0x36, 0x07, 0x4a, 0x33, 0x06, 0x50, 0x2f, 0x05, 0x56,
+    0x2b, 0x04, 0x61, 0x38, 0x04, 0x6c, 0x45, 0x04, 0x77, 0x53, 0x04, 0x82, 0x60, 0x04, 0x8d, 0x6d,
+    0x04, 0x98, 0x7b, 0x04, 0xa3, 0x88, 0x04, 0xae, 0x95, 0x04, 0xb9, 0xa3, 0x04, 0xc4, 0xb0, 0x04,
+    0xcf, 0xbd, 0x04, 0xda, 0xcb, 0x04, 0xe5, 0xd8, 0x04, 0xf0, 0xe5, 0x04, 0xfc, 0xf3, 0x05, 0xf9,
+    0xe3, 0x05, 0xf7, 0xd3, 0x05, 0xf5, 0xc3, 0x05, 0xf3, 0xb4, 0x05, 0xf1, 0xa4, 0x05, 0xef, 0x94,
+    0x05, 0xed, 0x85, 0x05, 0xeb, 0x75, 0x05, 0xe9, 0x65, 0x05, 0xe7, 0x56, 0x05, 0xe5, 0x46, 0x05,
+    0xe3, 0x36, 0x05, 0xe1, 0x27, 0x05, 0xdf, 0x17, 0x05, 0xdc, 0x07, 0x06, 0xdd, 0x07, 0x0e, 0xde,
+    0x07, 0x16, 0xe0, 0x07, 0x1f, 0xe1, 0x07, 0x27, 0xe3, 0x07, 0x30, 0xe4, 0x07, 0x38, 0xe6, 0x07,
+    0x40, 0xe7, 0x07, 0x49, 0xe9, 0x07, 0x51, 0xea, 0x07, 0x5a, 0xec, 0x07, 0x62, 0xed, 0x07, 0x6a,
+    0xef, 0x07, 0x73, 0xf0, 0x07, 0x7b, 0xf2, 0x08, 0x84, 0xe1, 0x07, 0x89, 0xd1, 0x06, 0x8e, 0xc1,
+    0x06, 0x94, 0xb1, 0x05, 0x99, 0xa1, 0x05, 0x9e, 0x91, 0x04, 0xa4, 0x81, 0x04, 0xa9, 0x70, 0x03,
+    0xae, 0x60, 0x03, 0xb4, 0x50, 0x02, 0xb9, 0x40, 0x02, 0xbe, 0x30, 0x01, 0xc4, 0x20, 0x01, 0xc9,
+    0x10, 0x00, 0xce, 0x00, 0x00, 0xd4, 0x10, 0x10, 0xd5, 0x20, 0x20, 0xd7, 0x30, 0x30, 0xd9, 0x40,
+    0x40, 0xdb, 0x50, 0x50, 0xdd, 0x60, 0x60, 0xdf, 0x70, 0x70, 0xe1, 0x80, 0x80, 0xe2, 0x90, 0x90,
+    0xe4, 0xa0, 0xa0, 0xe6, 0xb0, 0xb0, 0xe8, 0xc0, 0xc0, 0xea, 0xd0, 0xd0, 0xec, 0xe0, 0xe0, 0xee,
+    0xf0, 0xf0, 0xf0, 0xe0, 0xe0, 0xe0, 0xd0, 0xd0, 0xd0, 0xc0, 0xc0, 0xc0, 0xb0, 0xb0, 0xb0, 0xa0,
+    0xa0, 0xa0, 0x90, 0x90, 0x90, 0x80, 0x80, 0x80, 0x70, 0x70, 0x70, 0x60, 0x60, 0x60, 0x50, 0x50,
+    0x50, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x20, 0x20, 0x20, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00,
+];
+
+/// NTSC, id -6.
+pub const NTSC: [u8; 768] = [
+    0xff, 0xff, 0xff, 0x59, 0x2b, 0x85, 0x5d, 0x21, 0x5f, 0x7b, 0x28, 0x52, 0x9a, 0x30, 0x44, 0xa1,
+    0x3d, 0x42, 0xa5, 0x43, 0x41, 0xa9, 0x47, 0x3e, 0xac, 0x4b, 0x3a, 0xb0, 0x4f, 0x37, 0xb4, 0x53,
+    0x33, 0xba, 0x66, 0x35, 0xc1, 0x7a, 0x36, 0xc8, 0x8d, 0x37, 0xcf, 0xa1, 0x39, 0xd5, 0xb5, 0x3a,
+    0xdc, 0xc9, 0x3b, 0xe3, 0xdd, 0x3c, 0xc6, 0xd4, 0x38, 0xac, 0xce, 0x37, 0x93, 0xc8, 0x35, 0x79,
+    0xc3, 0x33, 0x60, 0xbd, 0x31, 0x46, 0xb7, 0x2f, 0x2d, 0xb1, 0x2d, 0x27, 0x9a, 0x3c, 0x21, 0x83,
+    0x49, 0x1b, 0x6c, 0x55, 0x19, 0x5b, 0x65, 0x21, 0x58, 0x84, 0x29, 0x52, 0xa3, 0x30, 0x4b, 0xc2,
+    0x1c, 0xc2, 0xd5, 0x26, 0xc3, 0xd5, 0x30, 0xc4, 0xd5, 0x3b, 0xc5, 0xd5, 0x45, 0xc6, 0xd5, 0x4f,
+    0xc7, 0xd5, 0x59, 0xc8, 0xd5, 0x63, 0xc9, 0xd5, 0x6d, 0xca, 0xd5, 0x77, 0xcb, 0xd5, 0x81, 0xcc,
+    0xd5, 0x8b, 0xcd, 0xd5, 0x95, 0xce, 0xd5, 0x9f, 0xcf, 0xd5, 0xa9, 0xd0, 0xd5, 0xb3, 0xd1, 0xd5,
+    0xb1, 0xf2, 0xb9, 0xa5, 0xf0, 0xad, 0x9a, 0xef, 0xa1, 0x8e, 0xed, 0x94, 0x82, 0xec, 0x88, 0x76,
+    0xea, 0x7c, 0x6b, 0xe8, 0x70, 0x5f, 0xe7, 0x63, 0x53, 0xe5, 0x57, 0x47, 0xe4, 0x4b, 0x3b, 0xe2,
+    0x3f, 0x30, 0xe0, 0x32, 0x24, 0xdf, 0x26, 0x18, 0xdd, 0x1a, 0x0c, 0xdc, 0x0e, 0x01, 0xda, 0x01,
+    0x01, 0xc8, 0x01, 0x00, 0xbb, 0x01, 0x00, 0xae, 0x01, 0x00, 0xa0, 0x01, 0x00, 0x93, 0x01, 0x00,
+    0x85, 0x01, 0x00, 0x78, 0x00, 0x00, 0x6b, 0x00, 0x00, 0x5d, 0x00, 0x00, 0x50, 0x00, 0x00, 0x42,
+    0x00, 0x00, 0x35, 0x00, 0x00, 0x28, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x00, 0x00,
+    0x0a, 0x09, 0x00, 0x16, 0x15, 0x01, 0x22, 0x21, 0x02, 0x2e, 0x2d, 0x02, 0x3a, 0x39, 0x03, 0x46,
+    0x44, 0x04, 0x52, 0x50, 0x05, 0x5e, 0x5c, 0x05, 0x6a, 0x68, 0x06, 0x77, 0x74, 0x07, 0x83, 0x80,
+    0x08, 0x8f, 0x8b, 0x08, 0x9b, 0x97, 0x09, 0xa7, 0xa3, 0x0a, 0xb3, 0xaf, 0x0b, 0xbf, 0xbb, 0x0b,
+    0xc5, 0xc0, 0x0c, 0xc8, 0xc4, 0x15, 0xcb, 0xc7, 0x1d, 0xcf, 0xca, 0x26, 0xd2, 0xcd, 0x2f, 0xd5,
+    0xd0, 0x38, 0xd8, 0xd3, 0x41, 0xdb, 0xd6, 0x4a, 0xdf, 0xd9, 0x52, 0xe2, 0xdc, 0x5b, 0xe5, 0xdf,
+    0x64, 0xe8, 0xe2, 0x6d, 0xeb, 0xe5, 0x76, 0xef, 0xe8, 0x7f, 0xf2, 0xeb, 0x87, 0xf5, 0xee, 0x90,
+    0xd9, 0xaf, 0xa1, 0xd9, 0xa9, 0x98, 0xd9, 0xa2, 0x8f, 0xd9, 0x9c, 0x87, 0xd9, 0x95, 0x7e, 0xd9,
+    0x8f, 0x76, 0xd9, 0x88, 0x6d, 0xd9, 0x82, 0x65, 0xd9, 0x7b, 0x5c, 0xd9, 0x75, 0x53, 0xd9, 0x6e,
+    0x4b, 0xd9, 0x68, 0x42, 0xd9, 0x61, 0x3a, 0xd9, 0x5b, 0x31, 0xd9, 0x54, 0x29, 0xd9, 0x4e, 0x20,
+    0xd9, 0x4a, 0x1b, 0xcb, 0x45, 0x19, 0xbc, 0x40, 0x17, 0xae, 0x3b, 0x16, 0x9f, 0x36, 0x14, 0x91,
+    0x31, 0x12, 0x82, 0x2c, 0x10, 0x74, 0x27, 0x0e, 0x65, 0x22, 0x0c, 0x57, 0x1d, 0x0b, 0x48, 0x18,
+    0x09, 0x3a, 0x13, 0x07, 0x2b, 0x0e, 0x05, 0x1d, 0x09, 0x03, 0x0e, 0x05, 0x01, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0b, 0x00, 0x02, 0x17, 0x00, 0x04, 0x17, 0x03, 0x06, 0x22, 0x04, 0x0a, 0x2e,
+    0x06, 0x0d, 0x39, 0x07, 0x10, 0x45, 0x09, 0x14, 0x51, 0x0b, 0x17, 0x5c, 0x0c, 0x1a, 0x68, 0x0e,
+    0x1e, 0x73, 0x0f, 0x21, 0x7f, 0x11, 0x25, 0x8b, 0x13, 0x28, 0x96, 0x14, 0x2b, 0xa2, 0x16, 0x2f,
+    0xa2, 0x16, 0x2f, 0xb1, 0x0b, 0x2a, 0xb5, 0x17, 0x35, 0xb9, 0x22, 0x3f, 0xbd, 0x2e, 0x4a, 0xc1,
+    0x39, 0x55, 0xc4, 0x45, 0x60, 0xc8, 0x50, 0x6b, 0xcc, 0x5c, 0x76, 0xd0, 0x67, 0x81, 0xd4, 0x73,
+    0x8c, 0xd7, 0x7e, 0x97, 0xdb, 0x8a, 0xa2, 0xdf, 0x95, 0xad, 0xe3, 0xa1, 0xb8, 0xe7, 0xad, 0xc3,
+    0xc9, 0xaa, 0xf0, 0xc3, 0xa1, 0xef, 0xbe, 0x97, 0xed, 0xb9, 0x8d, 0xeb, 0xb3, 0x84, 0xe9, 0xae,
+    0x7a, 0xe8, 0xa8, 0x70, 0xe6, 0xa3, 0x67, 0xe4, 0x9e, 0x5d, 0xe3, 0x98, 0x53, 0xe1, 0x93, 0x4a,
+    0xdf, 0x8d, 0x40, 0xdd, 0x88, 0x36, 0xdc, 0x83, 0x2d, 0xda, 0x7d, 0x23, 0xd8, 0x78, 0x19, 0xd7,
+    0x75, 0x0f, 0xd7, 0x6d, 0x0e, 0xc9, 0x66, 0x0d, 0xbc, 0x5f, 0x0c, 0xae, 0x57, 0x0b, 0xa1, 0x50,
+    0x0a, 0x93, 0x49, 0x09, 0x86, 0x41, 0x08, 0x78, 0x3a, 0x07, 0x6b, 0x32, 0x06, 0x5d, 0x2b, 0x05,
+    0x50, 0x24, 0x04, 0x42, 0x1c, 0x03, 0x35, 0x15, 0x02, 0x27, 0x0e, 0x01, 0x1a, 0x06, 0x00, 0x0c,
+    0x00, 0x04, 0x16, 0x00, 0x06, 0x23, 0x00, 0x08, 0x30, 0x00, 0x0b, 0x3d, 0x00, 0x0d, 0x4a, 0x00,
+    0x10, 0x57, 0x00, 0x12, 0x64, 0x00, 0x14, 0x71, 0x00, 0x17, 0x7e, 0x00, 0x19, 0x8b, 0x00, 0x1c,
+    0x98, 0x00, 0x1e, 0xa5, 0x00, 0x20, 0xb2, 0x00, 0x23, 0xbf, 0x00, 0x25, 0xcc, 0x00, 0x27, 0xd9,
+    0x00, 0x29, 0xe3, 0x0f, 0x36, 0xe4, 0x1d, 0x42, 0xe5, 0x2c, 0x4f, 0xe6, 0x3b, 0x5b, 0xe6, 0x4a,
+    0x67, 0xe7, 0x59, 0x74, 0xe8, 0x68, 0x80, 0xe9, 0x77, 0x8c, 0xe9, 0x86, 0x99, 0xea, 0x95, 0xa5,
+    0xeb, 0xa4, 0xb2, 0xec, 0xb3, 0xbe, 0xec, 0xc2, 0xca, 0xed, 0xd1, 0xd7, 0xee, 0xe0, 0xe3, 0xef,
+    0xf0, 0xf0, 0xf0, 0xe0, 0xe0, 0xe0, 0xd0, 0xd0, 0xd0, 0xc0, 0xc0, 0xc0, 0xb0, 0xb0, 0xb0, 0xa0,
+    0xa0, 0xa0, 0x90, 0x90, 0x90, 0x80, 0x80, 0x80, 0x70, 0x70, 0x70, 0x60, 0x60, 0x60, 0x50, 0x50,
+    0x50, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x20, 0x20, 0x20, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00,
+];
+
+/// Metallic, id -7.
+pub const METALLIC: [u8; 768] = [
+    0xff, 0xff, 0xff, 0x66, 0x4c, 0x80, 0x5f, 0x42, 0x6c, 0x58, 0x37, 0x59, 0x75, 0x47, 0x5e, 0x94,
+    0x58, 0x63, 0x9b, 0x66, 0x69, 0x9f, 0x6c, 0x6a, 0xa3, 0x6d, 0x68, 0xa6, 0x6f, 0x66, 0xaa, 0x73,
+    0x65, 0xae, 0x79, 0x67, 0xb4, 0x86, 0x6b, 0xbb, 0x94, 0x6f, 0xc2, 0xa2, 0x73, 0xc9, 0xb0, 0x77,
+    0xd0, 0xbe, 0x7b, 0xdd, 0xda, 0x83, 0xc7, 0xce, 0x7b, 0xb6, 0xc8, 0x77, 0xa6, 0xc3, 0x74, 0x96,
+    0xbd, 0x70, 0x85, 0xb7, 0x6d, 0x76, 0xb1, 0x69, 0x66, 0xab, 0x66, 0x58, 0x94, 0x63, 0x4a, 0x7d,
+    0x5f, 0x38, 0x5a, 0x5f, 0x43, 0x61, 0x76, 0x4e, 0x67, 0x8e, 0x58, 0x6d, 0xa5, 0x63, 0x73, 0xbc,
+    0x44, 0x2a, 0x5c, 0x4e, 0x35, 0x66, 0x58, 0x3f, 0x6f, 0x62, 0x4a, 0x79, 0x6c, 0x55, 0x82, 0x76,
+    0x5f, 0x8c, 0x80, 0x6a, 0x95, 0x8a, 0x75, 0x9f, 0x94, 0x7f, 0xa8, 0x9e, 0x8a, 0xb2, 0xa8, 0x94,
+    0xbb, 0xb2, 0x9f, 0xc4, 0xbc, 0xaa, 0xce, 0xc6, 0xb4, 0xd7, 0xd0, 0xbf, 0xe1, 0xda, 0xca, 0xea,
+    0xe4, 0xd4, 0xf4, 0xda, 0xc9, 0xea, 0xcf, 0xbe, 0xe0, 0xc4, 0xb2, 0xd6, 0xb9, 0xa7, 0xcb, 0xaf,
+    0x9c, 0xc1, 0xa4, 0x90, 0xb7, 0x99, 0x85, 0xad, 0x8f, 0x7a, 0xa3, 0x84, 0x6e, 0x99, 0x79, 0x63,
+    0x8f, 0x6f, 0x58, 0x85, 0x64, 0x4c, 0x7b, 0x59, 0x41, 0x71, 0x4e, 0x36, 0x67, 0x44, 0x2a, 0x5c,
+    0x51, 0x20, 0x1f, 0x5c, 0x2b, 0x2b, 0x67, 0x37, 0x37, 0x72, 0x42, 0x42, 0x7d, 0x4d, 0x4e, 0x87,
+    0x58, 0x59, 0x92, 0x63, 0x65, 0x9d, 0x6e, 0x71, 0xa8, 0x79, 0x7c, 0xb3, 0x84, 0x88, 0xbe, 0x90,
+    0x94, 0xc9, 0x9b, 0x9f, 0xd4, 0xa6, 0xab, 0xdf, 0xb1, 0xb7, 0xea, 0xbc, 0xc2, 0xf5, 0xc7, 0xce,
+    0xff, 0xd2, 0xda, 0xf4, 0xc6, 0xcd, 0xe8, 0xbb, 0xc1, 0xdd, 0xaf, 0xb4, 0xd1, 0xa3, 0xa8, 0xc5,
+    0x97, 0x9b, 0xba, 0x8b, 0x8f, 0xae, 0x7f, 0x83, 0xa2, 0x73, 0x76, 0x97, 0x67, 0x6a, 0x8b, 0x5c,
+    0x5d, 0x7f, 0x50, 0x51, 0x74, 0x44, 0x45, 0x68, 0x38, 0x38, 0x5d, 0x2c, 0x2c, 0x51, 0x20, 0x1f,
+    0x44, 0x26, 0x19, 0x4f, 0x31, 0x22, 0x59, 0x3b, 0x2c, 0x64, 0x46, 0x36, 0x6f, 0x51, 0x40, 0x79,
+    0x5b, 0x49, 0x84, 0x66, 0x53, 0x8e, 0x71, 0x5d, 0x99, 0x7b, 0x66, 0xa4, 0x86, 0x70, 0xae, 0x91,
+    0x7a, 0xb9, 0x9b, 0x83, 0xc3, 0xa6, 0x8d, 0xce, 0xb1, 0x97, 0xd9, 0xbb, 0xa1, 0xe3, 0xc6, 0xaa,
+    0xee, 0xd1, 0xb4, 0xe3, 0xc3, 0xa5, 0xd7, 0xb8, 0x9b, 0xcc, 0xac, 0x91, 0xc1, 0xa1, 0x87, 0xb5,
+    0x96, 0x7d, 0xaa, 0x8b, 0x73, 0x9f, 0x80, 0x69, 0x93, 0x74, 0x5f, 0x88, 0x69, 0x55, 0x7d, 0x5e,
+    0x4b, 0x71, 0x53, 0x41, 0x66, 0x48, 0x37, 0x5b, 0x3c, 0x2d, 0x4f, 0x31, 0x23, 0x44, 0x26, 0x19,
+    0x76, 0x55, 0x12, 0x80, 0x5d, 0x1c, 0x8a, 0x66, 0x26, 0x93, 0x6e, 0x2f, 0x9d, 0x77, 0x39, 0xa7,
+    0x7f, 0x43, 0xb1, 0x88, 0x4c, 0xbb, 0x91, 0x56, 0xc5, 0x99, 0x5f, 0xce, 0xa2, 0x69, 0xd8, 0xaa,
+    0x73, 0xe2, 0xb3, 0x7c, 0xec, 0xbb, 0x86, 0xf6, 0xc4, 0x8f, 0xff, 0xcc, 0x99, 0xff, 0xd5, 0xab,
+    0xff, 0xe1, 0xc2, 0xff, 0xd8, 0xb1, 0xf6, 0xc4, 0x8f, 0xec, 0xbb, 0x86, 0xe2, 0xb3, 0x7c, 0xd8,
+    0xaa, 0x73, 0xce, 0xa2, 0x69, 0xc5, 0x99, 0x5f, 0xbb, 0x91, 0x56, 0xb1, 0x88, 0x4c, 0xa7, 0x7f,
+    0x43, 0x9d, 0x77, 0x39, 0x93, 0x6e, 0x2f, 0x8a, 0x66, 0x26, 0x80, 0x5d, 0x1c, 0x76, 0x55, 0x12,
+    0x03, 0x30, 0x03, 0x0f, 0x3d, 0x0f, 0x1a, 0x4a, 0x1a, 0x26, 0x57, 0x26, 0x31, 0x64, 0x31, 0x3d,
+    0x71, 0x3d, 0x49, 0x7e, 0x49, 0x54, 0x8b, 0x54, 0x60, 0x98, 0x60, 0x6b, 0xa5, 0x6b, 0x77, 0xb2,
+    0x77, 0x82, 0xbf, 0x83, 0x8e, 0xcc, 0x8e, 0x9a, 0xd9, 0x9a, 0xa5, 0xe6, 0xa5, 0xb1, 0xf2, 0xb1,
+    0xbc, 0xff, 0xbc, 0xb0, 0xf2, 0xb0, 0xa4, 0xe4, 0xa4, 0x97, 0xd6, 0x97, 0x8b, 0xc8, 0x8b, 0x7f,
+    0xba, 0x7f, 0x72, 0xad, 0x72, 0x66, 0x9f, 0x66, 0x5a, 0x91, 0x5a, 0x4d, 0x83, 0x4d, 0x41, 0x75,
+    0x41, 0x34, 0x67, 0x35, 0x28, 0x5a, 0x28, 0x1c, 0x4c, 0x1c, 0x0f, 0x3e, 0x10, 0x03, 0x30, 0x03,
+    0x00, 0x0f, 0x55, 0x0d, 0x1d, 0x60, 0x1b, 0x2a, 0x6a, 0x29, 0x37, 0x75, 0x37, 0x45, 0x80, 0x45,
+    0x52, 0x8a, 0x53, 0x5f, 0x95, 0x61, 0x6d, 0xa0, 0x6f, 0x7a, 0xaa, 0x7d, 0x87, 0xb5, 0x8a, 0x95,
+    0xc0, 0x98, 0xa2, 0xca, 0xa6, 0xaf, 0xd5, 0xb4, 0xbd, 0xe0, 0xc2, 0xca, 0xea, 0xd0, 0xd7, 0xf5,
+    0xde, 0xe5, 0xff, 0xcf, 0xd6, 0xf4, 0xc0, 0xc8, 0xe9, 0xb1, 0xba, 0xdd, 0xa3, 0xac, 0xd2, 0x94,
+    0x9d, 0xc7, 0x85, 0x8f, 0xbb, 0x76, 0x81, 0xb0, 0x67, 0x73, 0xa5, 0x58, 0x65, 0x99, 0x4a, 0x56,
+    0x8e, 0x3b, 0x48, 0x83, 0x2c, 0x3a, 0x77, 0x1d, 0x2c, 0x6c, 0x0e, 0x1e, 0x61, 0x00, 0x0f, 0x55,
+    0x11, 0x11, 0x11, 0x20, 0x20, 0x20, 0x2f, 0x2f, 0x2f, 0x3e, 0x3e, 0x3e, 0x4d, 0x4d, 0x4d, 0x5c,
+    0x5c, 0x5c, 0x6a, 0x6a, 0x6a, 0x79, 0x79, 0x79, 0x88, 0x88, 0x88, 0x97, 0x97, 0x97, 0xa6, 0xa6,
+    0xa6, 0xb5, 0xb5, 0xb5, 0xc4, 0xc4, 0xc4, 0xd3, 0xd3, 0xd3, 0xe2, 0xe2, 0xe2, 0xf1, 0xf1, 0xf1,
+    0xfe, 0xff, 0xff, 0xee, 0xee, 0xee, 0xdd, 0xdd, 0xdd, 0xcc, 0xcc, 0xcc, 0xbb, 0xbb, 0xbb, 0xaa,
+    0xaa, 0xaa, 0x99, 0x99, 0x99, 0x88, 0x88, 0x88, 0x77, 0x77, 0x77, 0x66, 0x66, 0x66, 0x55, 0x55,
+    0x55, 0x44, 0x44, 0x44, 0x33, 0x33, 0x33, 0x22, 0x22, 0x22, 0x11, 0x11, 0x11, 0x00, 0x00, 0x00,
+];
+
+/// Web 216, id -8.
+pub const WEB_216: [u8; 768] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xcc, 0xff, 0xff, 0x99, 0xff, 0xff, 0x66, 0xff, 0xff, 0x33, 0xff,
+    0xff, 0x00, 0xff, 0xcc, 0xff, 0xff, 0xcc, 0xcc, 0xff, 0xcc, 0x99, 0xff, 0xcc, 0x66, 0xff, 0xcc,
+    0x33, 0xff, 0xcc, 0x00, 0xff, 0x99, 0xff, 0xff, 0x99, 0xcc, 0xff, 0x99, 0x99, 0xff, 0x99, 0x66,
+    0xff, 0x99, 0x33, 0xff, 0x99, 0x00, 0xff, 0x66, 0xff, 0xff, 0x66, 0xcc, 0xff, 0x66, 0x99, 0xff,
+    0x66, 0x66, 0xff, 0x66, 0x33, 0xff, 0x66, 0x00, 0xff, 0x33, 0xff, 0xff, 0x33, 0xcc, 0xff, 0x33,
+    0x99, 0xff, 0x33, 0x66, 0xff, 0x33, 0x33, 0xff, 0x33, 0x00, 0xff, 0x00, 0xff, 0xff, 0x00, 0xcc,
+    0xff, 0x00, 0x99, 0xff, 0x00, 0x66, 0xff, 0x00, 0x33, 0xff, 0x00, 0x00, 0xcc, 0xff, 0xff, 0xcc,
+    0xff, 0xcc, 0xcc, 0xff, 0x99, 0xcc, 0xff, 0x66, 0xcc, 0xff, 0x33, 0xcc, 0xff, 0x00, 0xcc, 0xcc,
+    0xff, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0x99, 0xcc, 0xcc, 0x66, 0xcc, 0xcc, 0x33, 0xcc, 0xcc, 0x00,
+    0xcc, 0x99, 0xff, 0xcc, 0x99, 0xcc, 0xcc, 0x99, 0x99, 0xcc, 0x99, 0x66, 0xcc, 0x99, 0x33, 0xcc,
+    0x99, 0x00, 0xcc, 0x66, 0xff, 0xcc, 0x66, 0xcc, 0xcc, 0x66, 0x99, 0xcc, 0x66, 0x66, 0xcc, 0x66,
+    0x33, 0xcc, 0x66, 0x00, 0xcc, 0x33, 0xff, 0xcc, 0x33, 0xcc, 0xcc, 0x33, 0x99, 0xcc, 0x33, 0x66,
+    0xcc, 0x33, 0x33, 0xcc, 0x33, 0x00, 0xcc, 0x00, 0xff, 0xcc, 0x00, 0xcc, 0xcc, 0x00, 0x99, 0xcc,
+    0x00, 0x66, 0xcc, 0x00, 0x33, 0xcc, 0x00, 0x00, 0x99, 0xff, 0xff, 0x99, 0xff, 0xcc, 0x99, 0xff,
+    0x99, 0x99, 0xff, 0x66, 0x99, 0xff, 0x33, 0x99, 0xff, 0x00, 0x99, 0xcc, 0xff, 0x99, 0xcc, 0xcc,
+    0x99, 0xcc, 0x99, 0x99, 0xcc, 0x66, 0x99, 0xcc, 0x33, 0x99, 0xcc, 0x00, 0x99, 0x99, 0xff, 0x99,
+    0x99, 0xcc, 0x99, 0x99, 0x99, 0x99, 0x99, 0x66, 0x99, 0x99, 0x33, 0x99, 0x99, 0x00, 0x99, 0x66,
+    0xff, 0x99, 0x66, 0xcc, 0x99, 0x66, 0x99, 0x99, 0x66, 0x66, 0x99, 0x66, 0x33, 0x99, 0x66, 0x00,
+    0x99, 0x33, 0xff, 0x99, 0x33, 0xcc, 0x99, 0x33, 0x99, 0x99, 0x33, 0x66, 0x99, 0x33, 0x33, 0x99,
+    0x33, 0x00, 0x99, 0x00, 0xff, 0x99, 0x00, 0xcc, 0x99, 0x00, 0x99, 0x99, 0x00, 0x66, 0x99, 0x00,
+    0x33, 0x99, 0x00, 0x00, 0x66, 0xff, 0xff, 0x66, 0xff, 0xcc, 0x66, 0xff, 0x99, 0x66, 0xff, 0x66,
+    0x66, 0xff, 0x33, 0x66, 0xff, 0x00, 0x66, 0xcc, 0xff, 0x66, 0xcc, 0xcc, 0x66, 0xcc, 0x99, 0x66,
+    0xcc, 0x66, 0x66, 0xcc, 0x33, 0x66, 0xcc, 0x00, 0x66, 0x99, 0xff, 0x66, 0x99, 0xcc, 0x66, 0x99,
+    0x99, 0x66, 0x99, 0x66, 0x66, 0x99, 0x33, 0x66, 0x99, 0x00, 0x66, 0x66, 0xff, 0x66, 0x66, 0xcc,
+    0x66, 0x66, 0x99, 0x66, 0x66, 0x66, 0x66, 0x66, 0x33, 0x66, 0x66, 0x00, 0x66, 0x33, 0xff, 0x66,
+    0x33, 0xcc, 0x66, 0x33, 0x99, 0x66, 0x33, 0x66, 0x66, 0x33, 0x33, 0x66, 0x33, 0x00, 0x66, 0x00,
+    0xff, 0x66, 0x00, 0xcc, 0x66, 0x00, 0x99, 0x66, 0x00, 0x66, 0x66, 0x00, 0x33, 0x66, 0x00, 0x00,
+    0x33, 0xff, 0xff, 0x33, 0xff, 0xcc, 0x33, 0xff, 0x99, 0x33, 0xff, 0x66, 0x33, 0xff, 0x33, 0x33,
+    0xff, 0x00, 0x33, 0xcc, 0xff, 0x33, 0xcc, 0xcc, 0x33, 0xcc, 0x99, 0x33, 0xcc, 0x66, 0x33, 0xcc,
+    0x33, 0x33, 0xcc, 0x00, 0x33, 0x99, 0xff, 0x33, 0x99, 0xcc, 0x33, 0x99, 0x99, 0x33, 0x99, 0x66,
+    0x33, 0x99, 0x33, 0x33, 0x99, 0x00, 0x33, 0x66, 0xff, 0x33, 0x66, 0xcc, 0x33, 0x66, 0x99, 0x33,
+    0x66, 0x66, 0x33, 0x66, 0x33, 0x33, 0x66, 0x00, 0x33, 0x33, 0xff, 0x33, 0x33, 0xcc, 0x33, 0x33,
+    0x99, 0x33, 0x33, 0x66, 0x33, 0x33, 0x33, 0x33, 0x33, 0x00, 0x33, 0x00, 0xff, 0x33, 0x00, 0xcc,
+    0x33, 0x00, 0x99, 0x33, 0x00, 0x66, 0x33, 0x00, 0x33, 0x33, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00,
+    0xff, 0xcc, 0x00, 0xff, 0x99, 0x00, 0xff, 0x66, 0x00, 0xff, 0x33, 0x00, 0xff, 0x00, 0x00, 0xcc,
+    0xff, 0x00, 0xcc, 0xcc, 0x00, 0xcc, 0x99, 0x00, 0xcc, 0x66, 0x00, 0xcc, 0x33, 0x00, 0xcc, 0x00,
+    0x00, 0x99, 0xff, 0x00, 0x99, 0xcc, 0x00, 0x99, 0x99, 0x00, 0x99, 0x66, 0x00, 0x99, 0x33, 0x00,
+    0x99, 0x00, 0x00, 0x66, 0xff, 0x00, 0x66, 0xcc, 0x00, 0x66, 0x99, 0x00, 0x66, 0x66, 0x00, 0x66,
+    0x33, 0x00, 0x66, 0x00, 0x00, 0x33, 0xff, 0x00, 0x33, 0xcc, 0x00, 0x33, 0x99, 0x00, 0x33, 0x66,
+    0x00, 0x33, 0x33, 0x00, 0x33, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0xcc, 0x00, 0x00, 0x99, 0x00,
+    0x00, 0x66, 0x00, 0x00, 0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// System - Win, id -101. Director 4's System - Win, id -102, is the same.
+pub const SYSTEM_WIN: [u8; 768] = [
+    0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0x00, 0x00,
+    0xff, 0x00, 0xff, 0x00, 0x00, 0x80, 0x80, 0x80, 0xa0, 0xa0, 0xa4, 0xff, 0xfb, 0xf0, 0x33, 0x33,
+    0x33, 0x99, 0x66, 0x00, 0x33, 0x66, 0x33, 0x00, 0x33, 0x99, 0xcc, 0x00, 0xff, 0x88, 0x00, 0x00,
+    0xff, 0xcc, 0x66, 0xff, 0x99, 0xcc, 0xdd, 0xdd, 0xdd, 0xff, 0x99, 0x00, 0xff, 0x66, 0xff, 0xff,
+    0x66, 0xcc, 0xff, 0x66, 0x99, 0xff, 0x66, 0x66, 0xff, 0x66, 0x33, 0xff, 0x66, 0x00, 0xff, 0x33,
+    0xff, 0xff, 0x33, 0xcc, 0xff, 0x33, 0x99, 0xff, 0x33, 0x66, 0xff, 0x33, 0x33, 0xff, 0x33, 0x00,
+    0xff, 0x00, 0xcc, 0xff, 0x00, 0x99, 0xff, 0x00, 0x66, 0xff, 0x00, 0x33, 0xcc, 0xff, 0xff, 0xcc,
+    0xff, 0xcc, 0xcc, 0xff, 0x99, 0xcc, 0xff, 0x66, 0xcc, 0xff, 0x33, 0xcc, 0xff, 0x00, 0xcc, 0xcc,
+    0xff, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0x99, 0xcc, 0xcc, 0x66, 0xcc, 0xcc, 0x33, 0xcc, 0xcc, 0x00,
+    0xcc, 0x99, 0xff, 0xcc, 0x99, 0xcc, 0xcc, 0x99, 0x99, 0xcc, 0x99, 0x66, 0xcc, 0x99, 0x33, 0xcc,
+    0x99, 0x00, 0xcc, 0x66, 0xff, 0xcc, 0x66, 0xcc, 0xcc, 0x66, 0x99, 0xcc, 0x66, 0x66, 0xcc, 0x66,
+    0x33, 0xcc, 0x66, 0x00, 0xcc, 0x33, 0xff, 0xcc, 0x33, 0xcc, 0xcc, 0x33, 0x99, 0xcc, 0x33, 0x66,
+    0xcc, 0x33, 0x33, 0xcc, 0x33, 0x00, 0xcc, 0x00, 0xff, 0xcc, 0x00, 0xcc, 0xcc, 0x00, 0x99, 0xcc,
+    0x00, 0x66, 0xcc, 0x00, 0x33, 0xcc, 0x00, 0x00, 0x99, 0xff, 0xff, 0x99, 0xff, 0xcc, 0x99, 0xff,
+    0x99, 0x99, 0xff, 0x66, 0x99, 0xff, 0x33, 0x99, 0xff, 0x00, 0x99, 0xcc, 0xff, 0x99, 0xcc, 0xcc,
+    0x99, 0xcc, 0x99, 0x99, 0xcc, 0x66, 0x99, 0xcc, 0x33, 0x99, 0xcc, 0x00, 0x99, 0x99, 0xff, 0x99,
+    0x99, 0xcc, 0x99, 0x99, 0x99, 0x99, 0x99, 0x66, 0x99, 0x99, 0x33, 0x99, 0x99, 0x00, 0x99, 0x66,
+    0xff, 0x99, 0x66, 0xcc, 0x99, 0x66, 0x99, 0x99, 0x66, 0x66, 0x99, 0x66, 0x33, 0x99, 0x66, 0x00,
+    0x99, 0x33, 0xff, 0x99, 0x33, 0xcc, 0x99, 0x33, 0x99, 0x99, 0x33, 0x66, 0x99, 0x33, 0x33, 0x99,
+    0x33, 0x00, 0x99, 0x00, 0xff, 0x99, 0x00, 0xcc, 0x99, 0x00, 0x99, 0x99, 0x00, 0x66, 0x99, 0x00,
+    0x33, 0x99, 0x00, 0x00, 0x66, 0xff, 0xff, 0x66, 0xff, 0xcc, 0x66, 0xff, 0x99, 0x66, 0xff, 0x66,
+    0x66, 0xff, 0x33, 0x66, 0xff, 0x00, 0x66, 0xcc, 0xff, 0x66, 0xcc, 0xcc, 0x66, 0xcc, 0x99, 0x66,
+    0xcc, 0x66, 0x66, 0xcc, 0x33, 0x66, 0xcc, 0x00, 0x66, 0x99, 0xff, 0x66, 0x99, 0xcc, 0x66, 0x99,
+    0x99, 0x66, 0x99, 0x66, 0x66, 0x99, 0x33, 0x66, 0x99, 0x00, 0x66, 0x66, 0xff, 0x66, 0x66, 0xcc,
+    0x66, 0x66, 0x99, 0x66, 0x66, 0x66, 0x66, 0x66, 0x33, 0x66, 0x66, 0x00, 0x66, 0x33, 0xff, 0x66,
+    0x33, 0xcc, 0x66, 0x33, 0x99, 0x66, 0x33, 0x66, 0x66, 0x33, 0x33, 0x66, 0x33, 0x00, 0x66, 0x00,
+    0xff, 0x66, 0x00, 0xcc, 0x66, 0x00, 0x99, 0x66, 0x00, 0x66, 0x66, 0x00, 0x33, 0x66, 0x00, 0x00,
+    0x33, 0xff, 0xff, 0x33, 0xff, 0xcc, 0x33, 0xff, 0x99, 0x33, 0xff, 0x66, 0x33, 0xff, 0x33, 0x33,
+    0xff, 0x00, 0x33, 0xcc, 0xff, 0x33, 0xcc, 0xcc, 0x33, 0xcc, 0x99, 0x33, 0xcc, 0x66, 0x33, 0xcc,
+    0x33, 0x33, 0xcc, 0x00, 0x33, 0x99, 0xff, 0x33, 0x99, 0xcc, 0x33, 0x99, 0x99, 0x33, 0x99, 0x66,
+    0x33, 0x99, 0x33, 0x33, 0x99, 0x00, 0x33, 0x66, 0xff, 0x33, 0x66, 0xcc, 0x33, 0x66, 0x99, 0x33,
+    0x66, 0x66, 0x33, 0x66, 0x33, 0x33, 0x66, 0x00, 0x33, 0x33, 0xff, 0x33, 0x33, 0xcc, 0x33, 0x33,
+    0x99, 0x33, 0x33, 0x66, 0x33, 0x33, 0x33, 0x33, 0x33, 0x00, 0x33, 0x00, 0xff, 0x33, 0x00, 0xcc,
+    0x33, 0x00, 0x99, 0x33, 0x00, 0x66, 0x33, 0x00, 0x33, 0x33, 0x00, 0x00, 0x00, 0xff, 0xcc, 0x00,
+    0xff, 0x99, 0x00, 0xff, 0x66, 0x00, 0xff, 0x33, 0x00, 0xcc, 0xff, 0x00, 0xcc, 0xcc, 0x00, 0xcc,
+    0x99, 0x00, 0xcc, 0x66, 0x00, 0xcc, 0x33, 0x00, 0xcc, 0x00, 0x00, 0x99, 0xff, 0x00, 0x99, 0xcc,
+    0x00, 0x99, 0x99, 0x00, 0x99, 0x66, 0x00, 0x99, 0x33, 0x00, 0x99, 0x00, 0x00, 0x66, 0xff, 0x00,
+    0x66, 0xcc, 0x00, 0x66, 0x99, 0x00, 0x66, 0x66, 0x00, 0x66, 0x33, 0x00, 0x66, 0x00, 0x00, 0x33,
+    0xff, 0x00, 0x33, 0xcc, 0x00, 0x33, 0x99, 0x00, 0x33, 0x66, 0x00, 0x33, 0x33, 0x00, 0x33, 0x00,
+    0x00, 0x00, 0xcc, 0x00, 0x00, 0x99, 0x00, 0x00, 0x66, 0x00, 0x00, 0x33, 0xee, 0x00, 0x00, 0xdd,
+    0x00, 0x00, 0xaa, 0x00, 0x00, 0x90, 0x00, 0x00, 0x77, 0x00, 0x00, 0x55, 0x00, 0x00, 0x44, 0x00,
+    0x00, 0x22, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, 0xee, 0x00, 0x00, 0xdd, 0x00, 0x00, 0xaa, 0x00,
+    0x00, 0x88, 0x00, 0x00, 0x77, 0x00, 0x00, 0x55, 0x00, 0x00, 0x44, 0x00, 0x00, 0x22, 0x00, 0x00,
+    0x11, 0x00, 0x00, 0x00, 0xee, 0x00, 0x00, 0xdd, 0x00, 0x00, 0xaa, 0x00, 0x00, 0x88, 0x00, 0x00,
+    0x77, 0x00, 0x00, 0x55, 0x00, 0x00, 0x44, 0x00, 0x00, 0x22, 0x00, 0x00, 0x11, 0x22, 0x22, 0x22,
+    0xff, 0x99, 0x99, 0xff, 0xcc, 0xff, 0x99, 0xcc, 0xff, 0x99, 0xcc, 0x99, 0xff, 0xff, 0x99, 0xf0,
+    0xf0, 0xf0, 0xa6, 0xca, 0xf0, 0xc0, 0xdc, 0xc0, 0xc0, 0xc0, 0xc0, 0x00, 0x80, 0x80, 0x80, 0x00,
+    0x80, 0x00, 0x00, 0x80, 0x80, 0x80, 0x00, 0x00, 0x80, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
@@ -0,0 +1,532 @@
+//! Windows projectors: a player executable with the movies appended.
+//!
+//! The last four bytes of the executable point to a header tagged with the
+//! projector version, `PJ93` for Director 4, `PJ95` for Director 5 and 6,
+//! `PJ00`/`PJ01` for Director 7 and 8, which in turn points to a RIFX
+//! container. From Director 5 on that container is an `APPL` file whose
+//! `File` chunks hold the bundled movies, casts and Xtras whole, with their
+//! original paths in the `Dict` chunk.
+//!
+//! The `Dict` layout isn't documented; the paths in it are length-prefixed
+//! strings in the same order as the `File` chunks, so they are picked out
+//! by their prefixes. Projectors whose header can't be found are scanned
+//! for embedded movies instead.
+
+use super::{Endian, FourCC, Reader};
+use anyhow::{bail, Result};
+
+const APPLICATION: FourCC = FourCC::new(b"APPL");
+const FILE: FourCC = FourCC::new(b"File");
+const DICTIONARY: FourCC = FourCC::new(b"Dict");
+const IMAP: FourCC = FourCC::new(b"imap");
+const MMAP: FourCC = FourCC::new(b"mmap");
+const PROJECTOR_VERSIONS: [&[u8; 4]; 4] = [b"PJ93", b"PJ95", b"PJ00", b"PJ01"];
+/// Codecs of the RIFX files a projector can bundle, with the extension
+/// each is written with when its name is unknown. Movies in projectors
+/// are usually protected, hence `.dxr`.
+const CODECS: [(&[u8; 4], &str); 4] = [
+    (b"MV93", "dxr"),
+    (b"MC95", "cxt"),
+    (b"FGDM", "dcr"),
+    (b"FGDC", "cct"),
+];
+
+pub struct Projector<'a> {
+    /// The header tag, e.g. `PJ95`, or `None` if the movies were found by
+    /// scanning.
+    pub version: Option<String>,
+    pub files: Vec<BundledFile<'a>>,
+}
+
+pub struct BundledFile<'a> {
+    /// File name, from the original path when the projector kept it.
+    pub name: String,
+    pub kind: BundledKind,
+    pub data: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundledKind {
+    /// A movie or cast, ready to be opened like any other.
+    Movie,
+    Xtra,
+    Other,
+}
+
+pub fn unpack(data: &[u8]) -> Result<Projector<'_>> {
+    let header = find_header(data);
+    let mut files = Vec::new();
+    if let Some((_, offset)) = header {
+        if let Some((endian, len)) = container_at(data, offset) {
+            files = read_container(&data[offset..offset + len], endian)?;
+        }
+    }
+    if files.is_empty() {
+        files = scan(data)?;
+    }
+    if files.is_empty() {
+        bail!("No Director movies found in the projector");
+    }
+    Ok(Projector {
+        version: header.map(|(version, _)| version),
+        files,
+    })
+}
+
+/// Reads the projector header, returning its version tag and the offset of
+/// the RIFX container it points to.
+fn find_header(data: &[u8]) -> Option<(String, usize)> {
+    let mut reader = Reader::new(data, Endian::Little);
+    reader.seek(data.len().checked_sub(4)?).ok()?;
+    let header = reader.read_u32().ok()? as usize;
+    reader.seek(header).ok()?;
+    // The tag is usually written as a little-endian number
+    let mut tag = reader.read_fourcc().ok()?.0;
+    if !PROJECTOR_VERSIONS.contains(&&tag) {
+        tag.reverse();
+        if !PROJECTOR_VERSIONS.contains(&&tag) {
+            return None;
+        }
+    }
+    // Director 7 added a field before the offset
+    if tag[2..] == *b"00" || tag[2..] == *b"01" {
+        reader.skip(4).ok()?;
+    }
+    let offset = reader.read_u32().ok()? as usize;
+    Some((String::from_utf8_lossy(&tag).into_owned(), offset))
+}
+
+/// Checks for a RIFX container at `offset`, returning its byte order and
+/// total length.
+fn container_at(data: &[u8], offset: usize) -> Option<(Endian, usize)> {
+    let endian = match data.get(offset..offset + 4)? {
+        b"RIFX" => Endian::Big,
+        b"XFIR" => Endian::Little,
+        _ => return None,
+    };
+    let mut reader = Reader::new(&data[offset..], endian);
+    reader.skip(4).ok()?;
+    let len = reader.read_u32().ok()? as usize + 8;
+    let codec = reader.read_fourcc().ok()?;
+    let known = codec == APPLICATION || CODECS.iter().any(|(tag, _)| codec.0 == **tag);
+    (known && offset + len <= data.len()).then_some((endian, len))
+}
+
+/// Lists the files in a RIFX container: the bundled files of an `APPL`, or
+/// the container itself if it is a movie.
+fn read_container(data: &[u8], endian: Endian) -> Result<Vec<BundledFile<'_>>> {
+    let mut reader = Reader::new(data, endian);
+    reader.skip(8)?;
+    let codec = reader.read_fourcc()?;
+    if codec != APPLICATION {
+        return Ok(vec![BundledFile {
+            name: String::new(),
+            kind: BundledKind::Movie,
+            data,
+        }]);
+    }
+    let chunks = read_chunks(data, endian)?;
+    let names = chunks
+        .iter()
+        .find(|(fourcc, _)| *fourcc == DICTIONARY)
+        .map(|(_, dict)| dictionary_paths(dict, endian))
+        .unwrap_or_default();
+    let bundled: Vec<&[u8]> = chunks
+        .iter()
+        .filter(|(fourcc, _)| *fourcc == FILE)
+        .map(|(_, data)| *data)
+        .collect();
+    // Paths only line up with the files if there is one for each
+    let names = if names.len() == bundled.len() {
+        names
+    } else {
+        Vec::new()
+    };
+    Ok(bundled
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| BundledFile {
+            name: names
+                .get(index)
+                .map(|path| file_name(path))
+                .unwrap_or_default(),
+            kind: kind(data),
+            data,
+        })
+        .collect())
+}
+
+/// Reads the `File` and `Dict` chunks listed in a container's memory map,
+/// in map order. Offsets in a bundled container may be relative to the
+/// container or to the executable; which one is told by whether the `imap`
+/// points at the `mmap`. Entries that don't land on their tag are skipped.
+fn read_chunks(data: &[u8], endian: Endian) -> Result<Vec<(FourCC, &[u8])>> {
+    let mut reader = Reader::new(data, endian);
+    reader.seek(12)?;
+    if reader.read_fourcc()? != IMAP {
+        bail!("Expected imap chunk after file header");
+    }
+    reader.skip(8)?;
+    let mmap_offset = reader.read_u32()? as usize;
+    let mut base = 0;
+    if mmap_offset >= data.len() || Reader::new(&data[mmap_offset..], endian).read_fourcc()? != MMAP
+    {
+        // Absolute offsets: find the container's start from the mmap's
+        match find_tag(data, MMAP, endian) {
+            Some(position) if position <= mmap_offset => base = mmap_offset - position,
+            _ => bail!("Projector container has no mmap chunk"),
+        }
+    }
+    reader.seek(mmap_offset - base)?;
+    reader.skip(8)?;
+    let header_len = reader.read_u16()? as usize;
+    let entry_len = reader.read_u16()? as usize;
+    reader.skip(4)?;
+    let used_count = reader.read_i32()?.max(0) as usize;
+    reader.seek(mmap_offset - base + 8 + header_len)?;
+    reader.check_table(used_count, entry_len, 12)?;
+
+    let mut chunks = Vec::new();
+    for _ in 0..used_count {
+        let entry_start = reader.pos();
+        let fourcc = reader.read_fourcc()?;
+        let len = reader.read_u32()? as usize;
+        let offset = (reader.read_u32()? as usize).wrapping_sub(base);
+        reader.seek(entry_start + entry_len)?;
+        if fourcc != FILE && fourcc != DICTIONARY {
+            continue;
+        }
+        let mut chunk = Reader::new(data, endian);
+        if chunk.seek(offset).is_err() || chunk.read_fourcc()? != fourcc {
+            continue;
+        }
+        chunk.skip(4)?;
+        chunks.push((fourcc, chunk.read_bytes(len)?));
+    }
+    Ok(chunks)
+}
+
+fn find_tag(data: &[u8], tag: FourCC, endian: Endian) -> Option<usize> {
+    let mut bytes = tag.0;
+    if endian == Endian::Little {
+        bytes.reverse();
+    }
+    data.windows(4).position(|window| window == bytes)
+}
+
+/// Picks the paths out of a `Dict` chunk: printable strings ending in a
+/// file extension, each preceded by its length.
+fn dictionary_paths(data: &[u8], endian: Endian) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut position = 0;
+    while position + 4 <= data.len() {
+        let mut reader = Reader::new(&data[position..], endian);
+        let len = reader.read_u32().unwrap_or(0) as usize;
+        let path = (len > 0)
+            .then(|| reader.read_bytes(len).ok())
+            .flatten()
+            .map(|bytes| {
+                String::from_utf8_lossy(bytes)
+                    .trim_end_matches('\0')
+                    .to_string()
+            })
+            .filter(|path| path.contains('.') && path.chars().all(|c| !c.is_control()));
+        match path {
+            Some(path) => {
+                paths.push(path);
+                position += 4 + len;
+            }
+            None => position += 1,
+        }
+    }
+    paths
+}
+
+/// The file name of a path from either Windows or the classic Mac OS, or
+/// nothing if the path ends in `.` or `..`, which would name a folder.
+fn file_name(path: &str) -> String {
+    let name = path.rsplit(['\\', '/', ':']).next().unwrap_or_default();
+    match name {
+        "." | ".." => String::new(),
+        _ => name.to_string(),
+    }
+}
+
+fn kind(data: &[u8]) -> BundledKind {
+    match data.get(..4) {
+        Some(b"RIFX" | b"XFIR") => BundledKind::Movie,
+        Some([b'M', b'Z', ..]) => BundledKind::Xtra,
+        _ => BundledKind::Other,
+    }
+}
+
+/// Finds the movies in a projector without a usable header by looking for
+/// RIFX containers of a known kind, skipping those nested in another.
+fn scan(data: &[u8]) -> Result<Vec<BundledFile<'_>>> {
+    let mut files = Vec::new();
+    let mut position = 0;
+    while position + 12 <= data.len() {
+        match container_at(data, position) {
+            Some((endian, len)) => {
+                files.extend(read_container(&data[position..position + len], endian)?);
+                position += len;
+            }
+            None => position += 1,
+        }
+    }
+    Ok(files)
+}
+
+impl BundledFile<'_> {
+    /// The extension to write the file with when its name is unknown.
+    pub fn default_extension(&self) -> &'static str {
+        match self.kind {
+            BundledKind::Movie => {
+                let endian = if self.data.starts_with(b"XFIR") {
+                    Endian::Little
+                } else {
+                    Endian::Big
+                };
+                let mut reader = Reader::new(self.data, endian);
+                let codec = reader.seek(8).and_then(|_| reader.read_fourcc()).ok();
+                CODECS
+                    .iter()
+                    .find(|(tag, _)| codec.is_some_and(|codec| codec.0 == **tag))
+                    .map_or("dxr", |(_, extension)| extension)
+            }
+            BundledKind::Xtra => "x32",
+            BundledKind::Other => "bin",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_bytes(value: u16, endian: Endian) -> [u8; 2] {
+        match endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn u32_bytes(value: u32, endian: Endian) -> [u8; 4] {
+        match endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn fourcc_bytes(tag: &[u8; 4], endian: Endian) -> [u8; 4] {
+        u32_bytes(u32::from_be_bytes(*tag), endian)
+    }
+
+    /// Builds an `APPL` container holding `chunks`, with the offsets in its
+    /// memory map counted from `base` bytes before the container.
+    fn container(endian: Endian, base: usize, chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        const ENTRY_LEN: usize = 20;
+        let mmap_offset = 12 + 16;
+        let mmap_len = 24 + (chunks.len() + 3) * ENTRY_LEN;
+        let mut offset = mmap_offset + 8 + mmap_len;
+        let mut entries = vec![
+            (*b"RIFX", 0, 0),
+            (*b"imap", 8, 12),
+            (*b"mmap", mmap_len, mmap_offset),
+        ];
+        for (tag, data) in chunks {
+            entries.push((**tag, data.len(), offset));
+            offset += 8 + data.len();
+        }
+
+        let number = |value: usize| u32_bytes(value as u32, endian);
+        let mut file = fourcc_bytes(b"RIFX", endian).to_vec();
+        file.extend(number(offset - 8));
+        file.extend(fourcc_bytes(b"APPL", endian));
+        file.extend(fourcc_bytes(b"imap", endian));
+        file.extend(number(8));
+        file.extend(number(1));
+        file.extend(number(base + mmap_offset));
+        file.extend(fourcc_bytes(b"mmap", endian));
+        file.extend(number(mmap_len));
+        file.extend(u16_bytes(24, endian));
+        file.extend(u16_bytes(ENTRY_LEN as u16, endian));
+        file.extend(number(entries.len()));
+        file.extend(number(entries.len()));
+        file.extend([0; 12]);
+        for (tag, len, offset) in entries {
+            file.extend(fourcc_bytes(&tag, endian));
+            file.extend(number(len));
+            file.extend(number(base + offset));
+            file.extend([0; 8]);
+        }
+        for (tag, data) in chunks {
+            file.extend(fourcc_bytes(tag, endian));
+            file.extend(number(data.len()));
+            file.extend(data);
+        }
+        file
+    }
+
+    /// A `Dict` chunk listing `paths`, after a count like the real ones.
+    fn dictionary(paths: &[&str]) -> Vec<u8> {
+        let mut dict = (paths.len() as u32).to_le_bytes().to_vec();
+        for path in paths {
+            dict.extend((path.len() as u32).to_le_bytes());
+            dict.extend(path.as_bytes());
+        }
+        dict
+    }
+
+    /// A movie to bundle; only its header is looked at.
+    fn movie() -> Vec<u8> {
+        let mut movie = b"XFIR".to_vec();
+        movie.extend(4u32.to_le_bytes());
+        movie.extend(b"39VM");
+        movie
+    }
+
+    /// A player executable with `container` appended at offset 64, followed
+    /// by a `tag` header pointing at it.
+    fn projector(tag: &[u8; 4], container: &[u8]) -> Vec<u8> {
+        let mut data = b"MZ".to_vec();
+        data.resize(64, 0);
+        data.extend(container);
+        let header = data.len() as u32;
+        data.extend(tag);
+        let mut forwards = *tag;
+        if forwards[0] != b'P' {
+            forwards.reverse();
+        }
+        if forwards[2..] == *b"00" || forwards[2..] == *b"01" {
+            data.extend([0; 4]);
+        }
+        data.extend(64u32.to_le_bytes());
+        data.extend(header.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn finds_the_projector_header() {
+        let data = projector(b"PJ95", &[0; 8]);
+        assert_eq!(find_header(&data), Some(("PJ95".to_string(), 64)));
+        let data = projector(b"PJ00", &[0; 8]);
+        assert_eq!(find_header(&data), Some(("PJ00".to_string(), 64)));
+        // Tags written as little-endian numbers read backwards
+        let data = projector(b"10JP", &[0; 8]);
+        assert_eq!(find_header(&data), Some(("PJ01".to_string(), 64)));
+
+        assert_eq!(find_header(&projector(b"PJ42", &[0; 8])), None);
+        assert_eq!(find_header(&[1, 2, 3]), None);
+        // A header offset past the end of the file
+        let mut data = b"MZ".to_vec();
+        data.extend(1000u32.to_le_bytes());
+        assert_eq!(find_header(&data), None);
+    }
+
+    #[test]
+    fn reads_chunks_by_relative_and_absolute_offsets() {
+        let chunks = [
+            (b"Dict", dictionary(&["START.DXR"])),
+            (b"VERS", vec![1, 2, 3, 4]),
+            (b"File", movie()),
+        ];
+        for base in [0, 64] {
+            let data = container(Endian::Little, base, &chunks);
+            let read = read_chunks(&data, Endian::Little).unwrap();
+            assert_eq!(read.len(), 2);
+            assert!(read[0].0 == DICTIONARY && read[0].1 == chunks[0].1.as_slice());
+            assert!(read[1].0 == FILE && read[1].1 == movie().as_slice());
+        }
+    }
+
+    #[test]
+    fn skips_chunks_that_miss_their_tag() {
+        let mut data = container(Endian::Big, 0, &[(b"File", movie()), (b"File", movie())]);
+        // Overwrite the tag of the second chunk
+        let second = data.len() - movie().len() - 8;
+        data[second..second + 4].copy_from_slice(b"junk");
+        let read = read_chunks(&data, Endian::Big).unwrap();
+        assert_eq!(read.len(), 1);
+
+        let mut data = container(Endian::Big, 0, &[]);
+        data[12..16].copy_from_slice(b"junk");
+        assert!(read_chunks(&data, Endian::Big).is_err());
+    }
+
+    #[test]
+    fn picks_paths_out_of_dictionaries() {
+        let mut dict = dictionary(&["C:\\GAME\\START.DXR", "Xtras\\Sound.x32"]);
+        // Fields without a file extension and stray bytes are skipped
+        dict.extend(5u32.to_le_bytes());
+        dict.extend(b"Movie");
+        dict.extend([0xff, 0, 1]);
+        dict.extend(11u32.to_le_bytes());
+        dict.extend(b"MAC:End.dir");
+        assert_eq!(
+            dictionary_paths(&dict, Endian::Little),
+            ["C:\\GAME\\START.DXR", "Xtras\\Sound.x32", "MAC:End.dir"]
+        );
+        assert!(dictionary_paths(&[], Endian::Little).is_empty());
+    }
+
+    #[test]
+    fn unpacks_bundled_files() {
+        let chunks = [
+            (
+                b"Dict",
+                dictionary(&["C:\\GAME\\START.DXR", "C:\\GAME\\Xtras\\Sound.x32"]),
+            ),
+            (b"File", movie()),
+            (b"File", b"MZ\x90\0".to_vec()),
+        ];
+        let data = projector(b"PJ95", &container(Endian::Little, 64, &chunks));
+        let unpacked = unpack(&data).unwrap();
+        assert_eq!(unpacked.version.as_deref(), Some("PJ95"));
+        let files: Vec<_> = unpacked
+            .files
+            .iter()
+            .map(|file| (file.name.as_str(), file.kind, file.data.len()))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("START.DXR", BundledKind::Movie, 12),
+                ("Sound.x32", BundledKind::Xtra, 4)
+            ]
+        );
+        assert_eq!(unpacked.files[0].default_extension(), "dxr");
+    }
+
+    #[test]
+    fn scans_for_movies_without_a_header() {
+        let mut data = b"MZ".to_vec();
+        data.resize(64, 0);
+        data.extend(movie());
+        data.extend([0; 16]);
+        let unpacked = unpack(&data).unwrap();
+        assert_eq!(unpacked.version, None);
+        assert_eq!(unpacked.files.len(), 1);
+        assert!(unpacked.files[0].name.is_empty());
+
+        assert!(unpack(&[0; 64]).is_err());
+    }
+
+    #[test]
+    fn drops_names_that_would_leave_the_output_folder() {
+        assert_eq!(file_name("C:\\GAME\\..\\..\\START.DXR"), "START.DXR");
+        assert_eq!(file_name("Disk:Movies:"), "");
+        assert_eq!(file_name("C:\\GAME\\.."), "");
+        assert_eq!(file_name("."), "");
+
+        let chunks = [
+            (b"Dict", dictionary(&["C:\\GAME\\..", "Movies/."])),
+            (b"File", movie()),
+            (b"File", movie()),
+        ];
+        let data = projector(b"PJ95", &container(Endian::Little, 0, &chunks));
+        let unpacked = unpack(&data).unwrap();
+        assert_eq!(unpacked.files.len(), 2);
+        assert!(unpacked.files.iter().all(|file| file.name.is_empty()));
+    }
+}
@@ -0,0 +1,538 @@
+//! The score (`VWSC`): what every channel shows in every frame.
+//!
+//! Frames are stored as deltas against a channel buffer that carries over
+//! from frame to frame. Each frame is a list of (length, offset, bytes)
+//! patches to that buffer. The buffer starts with the main channels
+//! (script, tempo, transition, sounds, palette) followed by one record
+//! per sprite channel.
+//!
+//! Director 5 and 6 use 24-byte sprite records behind 48 bytes of main
+//! channels. Director 7 and later use 48-byte records and give each main
+//! channel a record of its own. Their sprite records move the colours
+//! ahead of the member but otherwise keep the Director 6 order. The
+//! Director 7 main channel layout is less well known than the older one:
+//! each record is read as starting with its member, or the tempo.
+//!
+//! Frame labels live in a separate `VWLB` chunk.
+
+use super::{decode_string, Endian, Reader};
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+/// Marks the entry list that wraps the frame data from Director 5 on.
+const ENTRY_LIST_MARKER: i32 = -3;
+const MAIN_CHANNELS_D5: usize = 48;
+const MAIN_CHANNELS_D7: usize = 288;
+/// Size of each main channel record from Director 7 on.
+const MAIN_CHANNEL_D7: usize = 48;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct MemberRef {
+    pub cast_lib: i16,
+    pub member: i16,
+}
+
+#[derive(Serialize)]
+pub struct Sprite {
+    pub channel: usize,
+    #[serde(flatten)]
+    pub member: MemberRef,
+    /// Name of the member, empty if it can't be resolved.
+    pub name: String,
+    /// Name of the member's cast library.
+    pub cast: String,
+    pub sprite_type: u8,
+    pub ink: u8,
+    /// Blend as stored, 0-255.
+    pub blend: u8,
+    pub loc_h: i16,
+    pub loc_v: i16,
+    pub width: i16,
+    pub height: i16,
+    pub fore_color: u8,
+    pub back_color: u8,
+    /// Behavior attached to the sprite.
+    pub script: Option<MemberRef>,
+}
+
+#[derive(Serialize)]
+pub struct Frame {
+    pub frame: u32,
+    pub script: Option<MemberRef>,
+    pub tempo: u8,
+    pub transition: Option<MemberRef>,
+    pub palette: Option<MemberRef>,
+    pub sound1: Option<MemberRef>,
+    pub sound2: Option<MemberRef>,
+    pub sprites: Vec<Sprite>,
+}
+
+#[derive(Serialize)]
+pub struct Label {
+    pub name: String,
+    pub frame: u32,
+    /// Last frame before the next label, or the end of the score.
+    pub last_frame: u32,
+    /// Members shown by a sprite anywhere in the range, in order of first
+    /// appearance.
+    pub members: Vec<LabelMember>,
+}
+
+#[derive(Serialize)]
+pub struct LabelMember {
+    #[serde(flatten)]
+    pub member: MemberRef,
+    pub name: String,
+    pub cast: String,
+}
+
+/// Decodes every frame of a `VWSC` chunk. Member and cast names are left
+/// empty for the caller to fill in.
+pub fn read_score(data: &[u8], version: u16) -> Result<Vec<Frame>> {
+    read_frames(frame_entry(data)?, version, true)
+}
+
+/// Decodes the `SCVW` chunk of a film loop. It holds the same frame data
+/// as the score, without the entry list around it. The frame count in its
+/// header isn't reliable, so frames are read to the end of the data.
+pub fn read_film_loop(data: &[u8], version: u16) -> Result<Vec<Frame>> {
+    read_frames(data, version, false)
+}
+
+fn read_frames(frames_data: &[u8], version: u16, use_frame_count: bool) -> Result<Vec<Frame>> {
+    if version < 500 {
+        bail!("Reading the score needs Director 5 or later");
+    }
+    let mut reader = Reader::new(frames_data, Endian::Big);
+    let frames_len = (reader.read_u32()? as usize).min(frames_data.len());
+    let header_len = reader.read_u32()? as usize;
+    let frame_count = reader.read_u32()?;
+    reader.skip(2)?; // frames version
+    let sprite_size = reader.read_u16()? as usize;
+    let channel_count = reader.read_u16()? as usize;
+    reader.seek(header_len)?;
+
+    let main_size = if version >= 700 {
+        MAIN_CHANNELS_D7
+    } else {
+        MAIN_CHANNELS_D5
+    };
+    let sprite_size = match sprite_size {
+        0 if version >= 700 => 48,
+        0 => 24,
+        size => size,
+    };
+    let mut channels = vec![0u8; main_size + channel_count * sprite_size];
+    let mut frames = Vec::new();
+    let frame_limit = if use_frame_count {
+        frame_count
+    } else {
+        u32::MAX
+    };
+    while reader.pos() + 2 <= frames_len && (frames.len() as u32) < frame_limit {
+        let frame_start = reader.pos();
+        let frame_len = reader.read_u16()? as usize;
+        let frame_end = (frame_start + frame_len.max(2)).min(frames_len);
+        while reader.pos() + 4 <= frame_end {
+            let len = reader.read_u16()? as usize;
+            let offset = reader.read_u16()? as usize;
+            let patch = reader.read_bytes(len)?;
+            if channels.len() < offset + len {
+                channels.resize(offset + len, 0);
+            }
+            channels[offset..offset + len].copy_from_slice(patch);
+        }
+        reader.seek(frame_end)?;
+        frames.push(decode_frame(
+            &channels,
+            frames.len() as u32 + 1,
+            version,
+            main_size,
+            sprite_size,
+        ));
+    }
+    Ok(frames)
+}
+
+/// From Director 5 on the frame data is the first entry of a list.
+fn frame_entry(data: &[u8]) -> Result<&[u8]> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.skip(4)?;
+    if reader.read_i32()? != ENTRY_LIST_MARKER {
+        return Ok(data);
+    }
+    reader.skip(4)?;
+    let entry_count = reader.read_u32()? as usize;
+    reader.skip(8)?;
+    let mut offsets = Vec::with_capacity(2);
+    for _ in 0..(entry_count + 1).min(2) {
+        offsets.push(reader.read_u32()? as usize);
+    }
+    reader.skip((entry_count + 1).saturating_sub(2) * 4)?;
+    let base = reader.pos();
+    let (Some(&start), Some(&end)) = (offsets.first(), offsets.get(1)) else {
+        bail!("Score has no frame data");
+    };
+    reader.seek(base + start)?;
+    reader.read_bytes(end.saturating_sub(start))
+}
+
+fn decode_frame(
+    channels: &[u8],
+    number: u32,
+    version: u16,
+    main_size: usize,
+    sprite_size: usize,
+) -> Frame {
+    let byte = |offset: usize| channels.get(offset).copied().unwrap_or(0);
+    let short = |offset: usize| i16::from_be_bytes([byte(offset), byte(offset + 1)]);
+    let member_at = |offset: usize| MemberRef {
+        cast_lib: short(offset),
+        member: short(offset + 2),
+    };
+    let member_ref = |offset: usize| Some(member_at(offset)).filter(|m| m.member != 0);
+
+    let mut frame = if version >= 700 {
+        // Script, tempo, transition, sound 2, sound 1 and palette
+        let channel = |index: usize| index * MAIN_CHANNEL_D7;
+        Frame {
+            frame: number,
+            script: member_ref(channel(0)),
+            tempo: byte(channel(1) + 1),
+            transition: member_ref(channel(2)),
+            sound2: member_ref(channel(3)),
+            sound1: member_ref(channel(4)),
+            palette: member_ref(channel(5)),
+            sprites: Vec::new(),
+        }
+    } else {
+        Frame {
+            frame: number,
+            script: member_ref(0),
+            sound1: member_ref(4),
+            sound2: member_ref(8),
+            transition: member_ref(12),
+            tempo: byte(21),
+            palette: member_ref(24),
+            sprites: Vec::new(),
+        }
+    };
+
+    let sprite_count = channels.len().saturating_sub(main_size) / sprite_size;
+    for index in 0..sprite_count {
+        let at = main_size + index * sprite_size;
+        let (member, script, fore_color, back_color) = if version >= 700 {
+            (
+                member_at(at + 4),
+                member_ref(at + 8),
+                byte(at + 2),
+                byte(at + 3),
+            )
+        } else {
+            (
+                member_at(at + 2),
+                member_ref(at + 6),
+                byte(at + 10),
+                byte(at + 11),
+            )
+        };
+        let sprite_type = byte(at);
+        if member.member == 0 && sprite_type == 0 {
+            continue;
+        }
+        frame.sprites.push(Sprite {
+            channel: index + 1,
+            member,
+            name: String::new(),
+            cast: String::new(),
+            sprite_type,
+            ink: byte(at + 1) & 0x3f,
+            blend: byte(at + 21),
+            loc_v: short(at + 12),
+            loc_h: short(at + 14),
+            height: short(at + 16),
+            width: short(at + 18),
+            fore_color,
+            back_color,
+            script,
+        });
+    }
+    frame
+}
+
+/// Reads a `VWLB` chunk: a count, then count + 1 (frame, text offset) pairs
+/// and the label text. Director keeps a comment after the first carriage
+/// return, which is dropped. The chunk is big-endian, but the text is in
+/// the movie's own encoding.
+pub fn read_labels(data: &[u8], endian: Endian) -> Result<Vec<(String, u32)>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    let count = reader.read_u16()? as usize;
+    let text_start = 2 + (count + 1) * 4;
+    let mut entries = Vec::with_capacity(count + 1);
+    for _ in 0..=count {
+        let frame = reader.read_u16()? as u32;
+        let offset = reader.read_u16()? as usize;
+        entries.push((frame, text_start + offset));
+    }
+    let mut labels = Vec::with_capacity(count);
+    for pair in entries.windows(2) {
+        let ((frame, start), (_, end)) = (pair[0], pair[1]);
+        reader.seek(start)?;
+        let text = reader.read_bytes(end.saturating_sub(start))?;
+        let name = text.split(|&b| b == b'\r').next().unwrap_or_default();
+        labels.push((decode_string(name, endian), frame));
+    }
+    Ok(labels)
+}
+
+/// Spans each label to the frame before the next one and collects the
+/// sprite members shown in that range.
+pub fn label_ranges(mut labels: Vec<(String, u32)>, frames: &[Frame]) -> Vec<Label> {
+    labels.sort_by_key(|&(_, frame)| frame);
+    let last_frame = frames.len() as u32;
+    let starts: Vec<u32> = labels.iter().map(|&(_, frame)| frame).collect();
+    labels
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, frame))| {
+            let end = starts
+                .get(index + 1)
+                .map_or(last_frame, |next| next.saturating_sub(1))
+                .max(frame);
+            let mut members: Vec<LabelMember> = Vec::new();
+            let range = frames
+                .iter()
+                .filter(|score_frame| (frame..=end).contains(&score_frame.frame));
+            for sprite in range.flat_map(|score_frame| &score_frame.sprites) {
+                if sprite.member.member != 0
+                    && !members.iter().any(|seen| seen.member == sprite.member)
+                {
+                    members.push(LabelMember {
+                        member: sprite.member,
+                        name: sprite.name.clone(),
+                        cast: sprite.cast.clone(),
+                    });
+                }
+            }
+            Label {
+                name,
+                frame,
+                last_frame: end,
+                members,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame's (offset, bytes) patches to the channel buffer.
+    type Patches<'a> = &'a [(u16, &'a [u8])];
+
+    fn member(cast_lib: i16, member: i16) -> MemberRef {
+        MemberRef { cast_lib, member }
+    }
+
+    /// Builds the frame data of a score or film loop.
+    fn frame_data(
+        frame_count: u32,
+        sprite_size: u16,
+        channels: u16,
+        frames: &[Patches],
+    ) -> Vec<u8> {
+        let mut body = Vec::new();
+        for patches in frames {
+            let mut frame = Vec::new();
+            for (offset, bytes) in *patches {
+                frame.extend((bytes.len() as u16).to_be_bytes());
+                frame.extend(offset.to_be_bytes());
+                frame.extend(*bytes);
+            }
+            body.extend((frame.len() as u16 + 2).to_be_bytes());
+            body.extend(frame);
+        }
+        let mut data = ((20 + body.len()) as u32).to_be_bytes().to_vec();
+        data.extend(20u32.to_be_bytes());
+        data.extend(frame_count.to_be_bytes());
+        data.extend(13u16.to_be_bytes());
+        data.extend(sprite_size.to_be_bytes());
+        data.extend(channels.to_be_bytes());
+        data.extend([0; 2]);
+        data.extend(body);
+        data
+    }
+
+    /// Wraps frame data in the entry list of a `VWSC` chunk.
+    fn vwsc(frames: &[u8]) -> Vec<u8> {
+        let mut data = 0u32.to_be_bytes().to_vec();
+        data.extend(ENTRY_LIST_MARKER.to_be_bytes());
+        data.extend([0; 4]);
+        data.extend(1u32.to_be_bytes());
+        data.extend([0; 8]);
+        data.extend(0u32.to_be_bytes());
+        data.extend((frames.len() as u32).to_be_bytes());
+        data.extend(frames);
+        data
+    }
+
+    #[test]
+    fn applies_frame_deltas() {
+        // Sprite 1: a bitmap of member 3 with ink 8, at (100, 50) and
+        // 20 by 10 pixels
+        let mut sprite = vec![1, 0x48, 0, 1, 0, 3, 0, 0, 0, 0, 4, 5];
+        for value in [50i16, 100, 10, 20] {
+            sprite.extend(value.to_be_bytes());
+        }
+        sprite.extend([0, 128]);
+        let frames = frame_data(
+            2,
+            24,
+            2,
+            &[
+                &[(0, &[0, 1, 0, 5]), (21, &[30]), (48, &sprite)],
+                &[(62, &120i16.to_be_bytes())],
+            ],
+        );
+        let score = read_score(&vwsc(&frames), 500).unwrap();
+        assert_eq!(score.len(), 2);
+
+        let first = &score[0];
+        assert_eq!(first.frame, 1);
+        assert!(first.script == Some(member(1, 5)));
+        assert_eq!(first.tempo, 30);
+        assert!(first.sound1.is_none());
+        assert_eq!(first.sprites.len(), 1);
+        let sprite = &first.sprites[0];
+        assert_eq!(sprite.channel, 1);
+        assert!(sprite.member == member(1, 3));
+        assert_eq!((sprite.sprite_type, sprite.ink, sprite.blend), (1, 8, 128));
+        assert_eq!((sprite.loc_h, sprite.loc_v), (100, 50));
+        assert_eq!((sprite.width, sprite.height), (20, 10));
+        assert_eq!((sprite.fore_color, sprite.back_color), (4, 5));
+
+        // The second frame only moves the sprite; the rest carries over
+        let second = &score[1];
+        assert_eq!(second.tempo, 30);
+        assert_eq!(second.sprites[0].loc_h, 120);
+        assert_eq!(second.sprites[0].loc_v, 50);
+        assert!(second.sprites[0].member == member(1, 3));
+    }
+
+    #[test]
+    fn reads_director_7_channels() {
+        let tempo = MAIN_CHANNEL_D7 as u16 + 1;
+        let sound1 = 4 * MAIN_CHANNEL_D7 as u16;
+        let sprite = MAIN_CHANNELS_D7 as u16;
+        let frames = frame_data(
+            1,
+            0,
+            1,
+            &[&[
+                (tempo, &[15]),
+                (sound1, &[0, 2, 0, 9]),
+                (sprite, &[8, 0, 6, 7, 0, 1, 0, 4, 0, 1, 0, 2]),
+            ]],
+        );
+        let score = read_score(&vwsc(&frames), 700).unwrap();
+        let frame = &score[0];
+        assert_eq!(frame.tempo, 15);
+        assert!(frame.sound1 == Some(member(2, 9)));
+        let sprite = &frame.sprites[0];
+        assert!(sprite.member == member(1, 4));
+        assert!(sprite.script == Some(member(1, 2)));
+        assert_eq!((sprite.fore_color, sprite.back_color), (6, 7));
+    }
+
+    #[test]
+    fn reads_film_loops_to_the_end() {
+        let frames = frame_data(1, 24, 1, &[&[(48, &[1, 0, 0, 1, 0, 1])], &[], &[]]);
+        // The score stops at the frame count, a film loop reads every frame
+        assert_eq!(read_score(&vwsc(&frames), 500).unwrap().len(), 1);
+        let film_loop = read_film_loop(&frames, 500).unwrap();
+        assert_eq!(film_loop.len(), 3);
+        assert_eq!(film_loop[2].sprites.len(), 1);
+        assert!(read_film_loop(&frames, 400).is_err());
+    }
+
+    #[test]
+    fn rejects_scores_without_frame_data() {
+        let mut data = vwsc(&[]);
+        // An entry list with no entries
+        data[12..16].copy_from_slice(&0u32.to_be_bytes());
+        data.truncate(28);
+        assert!(read_score(&data, 500).is_err());
+    }
+
+    fn frame(number: u32, members: &[i16]) -> Frame {
+        let sprites = members
+            .iter()
+            .enumerate()
+            .map(|(index, &number)| Sprite {
+                channel: index + 1,
+                member: member(1, number),
+                name: format!("member {}", number),
+                cast: "Internal".to_string(),
+                sprite_type: 1,
+                ink: 0,
+                blend: 255,
+                loc_h: 0,
+                loc_v: 0,
+                width: 0,
+                height: 0,
+                fore_color: 255,
+                back_color: 0,
+                script: None,
+            })
+            .collect();
+        Frame {
+            frame: number,
+            script: None,
+            tempo: 0,
+            transition: None,
+            palette: None,
+            sound1: None,
+            sound2: None,
+            sprites,
+        }
+    }
+
+    #[test]
+    fn reads_labels_without_their_comments() {
+        let mut data = 2u16.to_be_bytes().to_vec();
+        for (frame, offset) in [(1u16, 0u16), (10, 5), (0, 18)] {
+            data.extend(frame.to_be_bytes());
+            data.extend(offset.to_be_bytes());
+        }
+        data.extend(b"intro");
+        data.extend(b"v\x8ag\rkommentar");
+        assert_eq!(
+            read_labels(&data, Endian::Big).unwrap(),
+            [("intro".to_string(), 1), ("v\u{e4}g".to_string(), 10)]
+        );
+        assert!(read_labels(&data[..10], Endian::Big).is_err());
+    }
+
+    #[test]
+    fn spans_labels_to_the_next_one() {
+        let frames = [
+            frame(1, &[3, 4]),
+            frame(2, &[4, 3]),
+            frame(3, &[5]),
+            frame(4, &[6]),
+            frame(5, &[7]),
+        ];
+        let labels = vec![("slut".to_string(), 4), ("start".to_string(), 1)];
+        let ranges = label_ranges(labels, &frames);
+        let members =
+            |label: &Label| -> Vec<i16> { label.members.iter().map(|m| m.member.member).collect() };
+        assert_eq!(ranges[0].name, "start");
+        assert_eq!((ranges[0].frame, ranges[0].last_frame), (1, 3));
+        assert_eq!(members(&ranges[0]), [3, 4, 5]);
+        assert_eq!(ranges[0].members[0].name, "member 3");
+        assert_eq!(ranges[1].name, "slut");
+        assert_eq!((ranges[1].frame, ranges[1].last_frame), (4, 5));
+        assert_eq!(members(&ranges[1]), [6, 7]);
+    }
+}
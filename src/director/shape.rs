@@ -0,0 +1,316 @@
+//! Shape members: rectangles, rounded rectangles, ovals and lines drawn by
+//! Director itself. Everything about them is in the `CASt` data.
+//!
+//! Director draws the border inside the shape's rect, so both the SVG and
+//! the raster version keep the stroke within those bounds.
+
+use super::palette::Palette;
+use super::{Endian, Reader};
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+/// Corner radius of rounded rectangles, in pixels.
+const CORNER_RADIUS: f32 = 12.0;
+/// Line direction for lines running from bottom left to top right.
+const LINE_RISING: u8 = 6;
+/// Samples per pixel along each axis when rasterising.
+const SUPERSAMPLING: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShapeType {
+    Rect,
+    RoundRect,
+    Oval,
+    Line,
+}
+
+pub struct Shape {
+    pub shape_type: ShapeType,
+    pub width: u32,
+    pub height: u32,
+    /// Director's pattern number. Patterns aren't reproduced; patterned
+    /// fills are drawn solid.
+    pub pattern: u16,
+    pub fore_color: u8,
+    pub filled: bool,
+    pub line_size: u8,
+    pub line_direction: u8,
+}
+
+impl Shape {
+    pub fn parse(data: &[u8]) -> Result<Shape> {
+        let mut reader = Reader::new(data, Endian::Big);
+        let shape_type = match reader.read_u16()? {
+            2 => ShapeType::RoundRect,
+            3 => ShapeType::Oval,
+            4 => ShapeType::Line,
+            _ => ShapeType::Rect,
+        };
+        let top = reader.read_u16()? as i16;
+        let left = reader.read_u16()? as i16;
+        let bottom = reader.read_u16()? as i16;
+        let right = reader.read_u16()? as i16;
+        let pattern = reader.read_u16()?;
+        let fore_color = reader.read_u8()?;
+        reader.skip(1)?; // back colour, only seen through patterns
+        let fill_type = reader.read_u8()?;
+        let line_size = reader.read_u8()?;
+        let line_direction = reader.read_u8()?;
+        Ok(Shape {
+            shape_type,
+            width: (i32::from(right) - i32::from(left)).max(0) as u32,
+            height: (i32::from(bottom) - i32::from(top)).max(0) as u32,
+            pattern,
+            fore_color,
+            filled: fill_type != 0,
+            line_size,
+            line_direction,
+        })
+    }
+
+    /// Size of the drawing. Lines can have a rect with no width or height,
+    /// but still take up their line size.
+    fn canvas_size(&self) -> (u32, u32) {
+        let min = self.line_size.max(1) as u32;
+        (self.width.max(min), self.height.max(min))
+    }
+
+    pub fn to_svg(&self, palette: &Palette) -> String {
+        let (width, height) = self.canvas_size();
+        let colour = hex_colour(palette, self.fore_color);
+        let line = self.line_size as f32;
+        let (w, h) = (width as f32, height as f32);
+        let fill = if self.filled && self.shape_type != ShapeType::Line {
+            colour.as_str()
+        } else {
+            "none"
+        };
+        let stroke = if self.line_size > 0 {
+            format!(r#" stroke="{}" stroke-width="{}""#, colour, line)
+        } else {
+            String::new()
+        };
+        let element = match self.shape_type {
+            ShapeType::Rect | ShapeType::RoundRect => {
+                let radius = if self.shape_type == ShapeType::RoundRect {
+                    format!(r#" rx="{}""#, (CORNER_RADIUS - line / 2.0).max(0.0))
+                } else {
+                    String::new()
+                };
+                format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}"{} fill="{}"{}/>"#,
+                    line / 2.0,
+                    line / 2.0,
+                    (w - line).max(0.0),
+                    (h - line).max(0.0),
+                    radius,
+                    fill,
+                    stroke
+                )
+            }
+            ShapeType::Oval => format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" fill="{}"{}/>"#,
+                w / 2.0,
+                h / 2.0,
+                ((w - line) / 2.0).max(0.0),
+                ((h - line) / 2.0).max(0.0),
+                fill,
+                stroke
+            ),
+            ShapeType::Line => {
+                let (y1, y2) = if self.line_direction == LINE_RISING {
+                    (h, 0.0)
+                } else {
+                    (0.0, h)
+                };
+                format!(
+                    r#"<line x1="0" y1="{}" x2="{}" y2="{}"{}/>"#,
+                    y1, w, y2, stroke
+                )
+            }
+        };
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" "#,
+                r#"viewBox="0 0 {w} {h}" data-pattern="{pattern}">"#,
+                "\n  {element}\n</svg>\n"
+            ),
+            w = width,
+            h = height,
+            pattern = self.pattern,
+            element = element
+        )
+    }
+
+    /// Rasterises the shape at `scale` times its size, antialiased, with a
+    /// transparent background.
+    pub fn render(&self, palette: &Palette, scale: u32) -> RgbaImage {
+        let (width, height) = self.canvas_size();
+        let [r, g, b] = colour(palette, self.fore_color);
+        let mut image = RgbaImage::new(width * scale, height * scale);
+        let samples = SUPERSAMPLING * SUPERSAMPLING;
+        let step = 1.0 / (scale * SUPERSAMPLING) as f32;
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let mut covered = 0;
+            for sample in 0..samples {
+                let sx = (x * SUPERSAMPLING + sample % SUPERSAMPLING) as f32 + 0.5;
+                let sy = (y * SUPERSAMPLING + sample / SUPERSAMPLING) as f32 + 0.5;
+                if self.covers(sx * step, sy * step) {
+                    covered += 1;
+                }
+            }
+            if covered > 0 {
+                let alpha = (covered * 255 / samples) as u8;
+                *pixel = Rgba([r, g, b, alpha]);
+            }
+        }
+        image
+    }
+
+    /// Whether a point, in unscaled pixels, is drawn.
+    fn covers(&self, x: f32, y: f32) -> bool {
+        let (width, height) = self.canvas_size();
+        let (w, h) = (width as f32, height as f32);
+        let line = self.line_size as f32;
+        if self.shape_type == ShapeType::Line {
+            let (y1, y2) = if self.line_direction == LINE_RISING {
+                (h, 0.0)
+            } else {
+                (0.0, h)
+            };
+            return segment_distance(x, y, (0.0, y1), (w, y2)) <= line.max(1.0) / 2.0;
+        }
+        // Signed distance to the outline, negative inside
+        let distance = match self.shape_type {
+            ShapeType::Oval => {
+                let (rx, ry) = (w / 2.0, h / 2.0);
+                let (nx, ny) = ((x - rx) / rx, (y - ry) / ry);
+                ((nx * nx + ny * ny).sqrt() - 1.0) * rx.min(ry)
+            }
+            _ => {
+                let radius = if self.shape_type == ShapeType::RoundRect {
+                    CORNER_RADIUS.min(w / 2.0).min(h / 2.0)
+                } else {
+                    0.0
+                };
+                let qx = (x - w / 2.0).abs() - (w / 2.0 - radius);
+                let qy = (y - h / 2.0).abs() - (h / 2.0 - radius);
+                let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+                outside + qx.max(qy).min(0.0) - radius
+            }
+        };
+        distance <= 0.0 && (self.filled || distance > -line)
+    }
+}
+
+fn segment_distance(x: f32, y: f32, (x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> f32 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((x - x1) * dx + (y - y1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (px, py) = (x1 + t * dx - x, y1 + t * dy - y);
+    (px * px + py * py).sqrt()
+}
+
+fn colour(palette: &Palette, index: u8) -> [u8; 3] {
+    palette.get(index as usize).copied().unwrap_or([0, 0, 0])
+}
+
+fn hex_colour(palette: &Palette, index: u8) -> String {
+    let [r, g, b] = colour(palette, index);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A palette where index 1 is red and everything else black.
+    fn palette() -> Palette {
+        let mut palette = vec![[0, 0, 0]; 256];
+        palette[1] = [255, 0, 0];
+        palette
+    }
+
+    /// `CASt` data of a shape with the given type, rect and fill.
+    fn shape_data(shape_type: u16, rect: [i16; 4], filled: bool, line_size: u8) -> Vec<u8> {
+        let mut data = shape_type.to_be_bytes().to_vec();
+        for value in rect {
+            data.extend(value.to_be_bytes());
+        }
+        data.extend(7u16.to_be_bytes());
+        data.extend([1, 0, filled as u8, line_size, 5]);
+        data
+    }
+
+    #[test]
+    fn parses_shapes() {
+        let shape = Shape::parse(&shape_data(3, [10, 20, 40, 80], true, 2)).unwrap();
+        assert_eq!(shape.shape_type, ShapeType::Oval);
+        assert_eq!((shape.width, shape.height), (60, 30));
+        assert_eq!((shape.pattern, shape.fore_color), (7, 1));
+        assert!(shape.filled);
+        assert_eq!((shape.line_size, shape.line_direction), (2, 5));
+        assert!(Shape::parse(&shape_data(1, [0; 4], false, 1)[..10]).is_err());
+        // Rects whose size doesn't fit in an i16
+        let wide = Shape::parse(&shape_data(
+            1,
+            [i16::MIN, i16::MIN, i16::MAX, i16::MAX],
+            false,
+            1,
+        ))
+        .unwrap();
+        assert_eq!((wide.width, wide.height), (0xffff, 0xffff));
+        let flipped = Shape::parse(&shape_data(
+            1,
+            [i16::MAX, i16::MAX, i16::MIN, i16::MIN],
+            false,
+            1,
+        ))
+        .unwrap();
+        assert_eq!((flipped.width, flipped.height), (0, 0));
+    }
+
+    #[test]
+    fn writes_svg() {
+        let rect = Shape::parse(&shape_data(1, [0, 0, 10, 20], false, 2)).unwrap();
+        assert_eq!(
+            rect.to_svg(&palette()),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"10\" \
+             viewBox=\"0 0 20 10\" data-pattern=\"7\">\n  \
+             <rect x=\"1\" y=\"1\" width=\"18\" height=\"8\" fill=\"none\" \
+             stroke=\"#ff0000\" stroke-width=\"2\"/>\n</svg>\n"
+        );
+        let oval = Shape::parse(&shape_data(3, [0, 0, 10, 20], true, 0)).unwrap();
+        assert!(oval
+            .to_svg(&palette())
+            .contains(r##"<ellipse cx="10" cy="5" rx="10" ry="5" fill="#ff0000"/>"##));
+        // A rising line with an empty rect still takes up its line size
+        let mut line = Shape::parse(&shape_data(4, [0, 0, 0, 20], true, 3)).unwrap();
+        line.line_direction = LINE_RISING;
+        let svg = line.to_svg(&palette());
+        assert!(svg.contains(r#"width="20" height="3""#));
+        assert!(svg.contains(r##"<line x1="0" y1="3" x2="20" y2="0" stroke="#ff0000""##));
+    }
+
+    #[test]
+    fn rasterises_within_the_rect() {
+        let rect = Shape::parse(&shape_data(1, [0, 0, 4, 4], false, 1)).unwrap();
+        let image = rect.render(&palette(), 2);
+        assert_eq!(image.dimensions(), (8, 8));
+        // The border is inside the rect and the middle is left empty
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(7, 7), Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(4, 4).0[3], 0);
+
+        let oval = Shape::parse(&shape_data(3, [0, 0, 8, 8], true, 0)).unwrap();
+        let image = oval.render(&palette(), 1);
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+        assert_eq!(image.get_pixel(4, 4).0[3], 255);
+        // Edge pixels are partly covered
+        assert!((1..255).contains(&image.get_pixel(1, 1).0[3]));
+    }
+}
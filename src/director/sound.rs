@@ -0,0 +1,461 @@
+//! Sound cast members.
+//!
+//! Up to Director 6 the samples live in a Mac `snd ` resource owned by the
+//! member. Later versions split it into a `sndH` chunk holding the sound
+//! header and a `sndS` chunk holding the samples. Sounds imported as files
+//! in Director 8 and up are kept as is in an `ediM` media chunk.
+//!
+//! Sound resources are always big-endian, even in `XFIR` movies.
+
+use super::{swa, Endian, Reader};
+use anyhow::{bail, Result};
+
+/// Sound Manager command that points at a sound header.
+const SOUND_CMD: u16 = 0x50;
+const BUFFER_CMD: u16 = 0x51;
+/// Set on commands whose second parameter is an offset into the resource.
+const DATA_OFFSET_FLAG: u16 = 0x8000;
+
+/// `encode` values of a sound header.
+const STANDARD_HEADER: u8 = 0x00;
+const COMPRESSED_HEADER: u8 = 0xfe;
+const EXTENDED_HEADER: u8 = 0xff;
+
+/// More channels than any sound Director plays; only damaged headers have
+/// them.
+const MAX_CHANNELS: u16 = 8;
+
+const IMA4: [u8; 4] = *b"ima4";
+/// Bytes in one IMA4 packet: a 2-byte preamble and 64 samples of 4 bits.
+const IMA4_PACKET_LEN: usize = 34;
+const IMA4_PACKET_SAMPLES: usize = 64;
+
+const IMA_STEPS: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+const IMA_INDEX_ADJUST: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+/// Decoded PCM, laid out the way WAV expects it: interleaved channels,
+/// unsigned 8-bit or signed little-endian 16-bit samples.
+pub struct Sound {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub data: Vec<u8>,
+}
+
+impl Sound {
+    pub fn to_wav(&self) -> Vec<u8> {
+        let block_align = u32::from(self.channels) * u32::from(self.bits_per_sample) / 8;
+        let mut wav = Vec::with_capacity(44 + self.data.len() + 1);
+        wav.extend_from_slice(b"RIFF");
+        let padded_len = self.data.len() + self.data.len() % 2;
+        wav.extend_from_slice(&(36 + padded_len as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&self.channels.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.saturating_mul(block_align).to_le_bytes());
+        wav.extend_from_slice(&(block_align as u16).to_le_bytes());
+        wav.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&self.data);
+        if self.data.len() % 2 == 1 {
+            wav.push(0);
+        }
+        wav
+    }
+}
+
+/// What an `ediM` chunk holds.
+pub enum Media {
+    /// A WAV file that can be written out unchanged.
+    Wav(Vec<u8>),
+    /// PCM decoded from another container.
+    Pcm(Sound),
+    /// The MPEG Layer III stream of Shockwave Audio, see [`swa`].
+    Mp3(Vec<u8>),
+}
+
+/// Decodes a Mac `snd ` resource of format 1 or 2.
+pub fn decode_snd(data: &[u8]) -> Result<Sound> {
+    let mut reader = Reader::new(data, Endian::Big);
+    match reader.read_u16()? {
+        1 => {
+            let data_formats = reader.read_u16()? as usize;
+            reader.skip(data_formats * 6)?;
+        }
+        2 => reader.skip(2)?,
+        format => bail!("Unsupported snd resource format {}", format),
+    }
+    let command_count = reader.read_u16()?;
+    for _ in 0..command_count {
+        let command = reader.read_u16()?;
+        reader.skip(2)?;
+        let param = reader.read_u32()? as usize;
+        let kind = command & !DATA_OFFSET_FLAG;
+        if command & DATA_OFFSET_FLAG != 0 && (kind == SOUND_CMD || kind == BUFFER_CMD) {
+            let (header, data_offset) = read_sound_header(data, param)?;
+            let samples = data.get(data_offset..).unwrap_or_default();
+            return header.decode(samples);
+        }
+    }
+    bail!("snd resource has no sound or buffer command")
+}
+
+/// Decodes a `sndH` header together with the samples from its `sndS` chunk.
+pub fn decode_split(header: &[u8], samples: &[u8]) -> Result<Sound> {
+    let (header, _) = read_sound_header(header, 0)?;
+    header.decode(samples)
+}
+
+/// Recognises the media files Director keeps in `ediM` chunks. Returns
+/// `None` for formats that are neither PCM nor Shockwave Audio.
+pub fn decode_media(data: &[u8]) -> Result<Option<Media>> {
+    match (data.get(..4), data.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WAVE")) => Ok(Some(Media::Wav(data.to_vec()))),
+        (Some(b"FORM"), Some(b"AIFF" | b"AIFC")) => {
+            decode_aiff(data).map(|sound| sound.map(Media::Pcm))
+        }
+        _ => Ok(swa::find_mp3_stream(data).map(|mp3| Media::Mp3(mp3.to_vec()))),
+    }
+}
+
+struct SoundHeader {
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    frames: usize,
+    ima4: bool,
+}
+
+impl SoundHeader {
+    fn decode(&self, samples: &[u8]) -> Result<Sound> {
+        let data = if self.ima4 {
+            decode_ima4(samples, self.channels as usize, self.frames)
+        } else {
+            let frame_len = self.channels as usize * self.bits_per_sample as usize / 8;
+            let len = (self.frames * frame_len).min(samples.len() / frame_len.max(1) * frame_len);
+            to_wav_order(&samples[..len], self.bits_per_sample)
+        };
+        Ok(Sound {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            bits_per_sample: self.bits_per_sample,
+            data,
+        })
+    }
+}
+
+/// Parses the standard, extended or compressed sound header at `offset`
+/// and returns it with the offset of its first sample.
+fn read_sound_header(data: &[u8], offset: usize) -> Result<(SoundHeader, usize)> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.seek(offset)?;
+    reader.skip(4)?; // sample pointer, always 0 in a resource
+    let length_or_channels = reader.read_u32()? as usize;
+    let sample_rate = reader.read_u32()? >> 16;
+    reader.skip(8)?; // loop start and end
+    let encode = reader.read_u8()?;
+    reader.skip(1)?; // base frequency
+
+    match encode {
+        STANDARD_HEADER => Ok((
+            SoundHeader {
+                channels: 1,
+                sample_rate,
+                bits_per_sample: 8,
+                frames: length_or_channels,
+                ima4: false,
+            },
+            reader.pos(),
+        )),
+        EXTENDED_HEADER => {
+            let frames = reader.read_u32()? as usize;
+            reader.skip(22)?; // AIFF sample rate, marker, instrument and AES chunks
+            let bits_per_sample = reader.read_u16()?;
+            reader.skip(14)?;
+            if bits_per_sample != 8 && bits_per_sample != 16 {
+                bail!("Unsupported sample size: {} bits", bits_per_sample);
+            }
+            Ok((
+                SoundHeader {
+                    channels: channel_count(length_or_channels)?,
+                    sample_rate,
+                    bits_per_sample,
+                    frames,
+                    ima4: false,
+                },
+                reader.pos(),
+            ))
+        }
+        COMPRESSED_HEADER => {
+            // Counted in packets rather than sample frames
+            let packets = reader.read_u32()? as usize;
+            reader.skip(14)?; // AIFF sample rate and marker chunk
+            let format = reader.read_fourcc()?;
+            reader.skip(12)?;
+            let compression_id = reader.read_u16()? as i16;
+            reader.skip(6)?; // packet size, synthesizer and sample size
+            if format.0 != IMA4 {
+                bail!(
+                    "Unsupported sound compression {} (id {})",
+                    format,
+                    compression_id
+                );
+            }
+            Ok((
+                SoundHeader {
+                    channels: channel_count(length_or_channels)?,
+                    sample_rate,
+                    bits_per_sample: 16,
+                    frames: packets * IMA4_PACKET_SAMPLES,
+                    ima4: true,
+                },
+                reader.pos(),
+            ))
+        }
+        other => bail!("Unknown sound header encoding 0x{:02x}", other),
+    }
+}
+
+/// Checks the channel count of a sound header, taking 0 for mono.
+fn channel_count(channels: usize) -> Result<u16> {
+    match channels {
+        0 => Ok(1),
+        channels if channels <= MAX_CHANNELS as usize => Ok(channels as u16),
+        channels => bail!("Unsupported channel count: {}", channels),
+    }
+}
+
+/// Mac samples are big-endian; WAV wants 16-bit samples little-endian.
+fn to_wav_order(samples: &[u8], bits_per_sample: u16) -> Vec<u8> {
+    if bits_per_sample == 16 {
+        samples
+            .chunks_exact(2)
+            .flat_map(|pair| [pair[1], pair[0]])
+            .collect()
+    } else {
+        samples.to_vec()
+    }
+}
+
+/// Apple IMA4: each channel is coded in 34-byte packets, and the packets
+/// of a multichannel sound alternate between channels.
+fn decode_ima4(data: &[u8], channels: usize, frames: usize) -> Vec<u8> {
+    let channels = channels.max(1);
+    let packet_frames = data.len() / (IMA4_PACKET_LEN * channels);
+    let frames = frames.min(packet_frames * IMA4_PACKET_SAMPLES);
+    let mut out = vec![0u8; frames * channels * 2];
+
+    for (packet_index, packet) in data.chunks_exact(IMA4_PACKET_LEN).enumerate() {
+        let channel = packet_index % channels;
+        let first_frame = packet_index / channels * IMA4_PACKET_SAMPLES;
+        let preamble = u16::from_be_bytes([packet[0], packet[1]]);
+        let mut predictor = (preamble & 0xff80) as i16 as i32;
+        let mut index = ((preamble & 0x7f) as i32).min(88);
+
+        for (i, &byte) in packet[2..].iter().enumerate() {
+            for (j, nibble) in [byte & 0x0f, byte >> 4].into_iter().enumerate() {
+                let step = IMA_STEPS[index as usize];
+                let mut diff = step >> 3;
+                if nibble & 4 != 0 {
+                    diff += step;
+                }
+                if nibble & 2 != 0 {
+                    diff += step >> 1;
+                }
+                if nibble & 1 != 0 {
+                    diff += step >> 2;
+                }
+                predictor = if nibble & 8 != 0 {
+                    predictor - diff
+                } else {
+                    predictor + diff
+                }
+                .clamp(i16::MIN as i32, i16::MAX as i32);
+                index = (index + IMA_INDEX_ADJUST[nibble as usize]).clamp(0, 88);
+
+                let frame = first_frame + i * 2 + j;
+                if frame < frames {
+                    let at = (frame * channels + channel) * 2;
+                    out[at..at + 2].copy_from_slice(&(predictor as i16).to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Reads uncompressed AIFF/AIFC sound data. Compressed AIFC is left alone.
+fn decode_aiff(data: &[u8]) -> Result<Option<Sound>> {
+    let mut reader = Reader::new(data, Endian::Big);
+    reader.skip(8)?;
+    let aifc = reader.read_fourcc()?.0 == *b"AIFC";
+    let mut format = None;
+    let mut samples = None;
+    while reader.len() - reader.pos() >= 8 {
+        let id = reader.read_fourcc()?;
+        let len = reader.read_u32()? as usize;
+        let body = reader.read_bytes(len.min(reader.len() - reader.pos()))?;
+        if len % 2 == 1 && !reader.eof() {
+            reader.skip(1)?;
+        }
+        match &id.0 {
+            b"COMM" => {
+                let mut comm = Reader::new(body, Endian::Big);
+                let channels = comm.read_u16()?;
+                comm.skip(4)?; // frame count, implied by the SSND size
+                let bits_per_sample = comm.read_u16()?;
+                let sample_rate = read_extended(comm.read_bytes(10)?);
+                if aifc && comm.len() - comm.pos() >= 4 {
+                    let compression = comm.read_fourcc()?;
+                    if !matches!(&compression.0, b"NONE" | b"twos") {
+                        return Ok(None);
+                    }
+                }
+                format = Some((
+                    channel_count(channels.into())?,
+                    bits_per_sample,
+                    sample_rate,
+                ));
+            }
+            b"SSND" => {
+                let mut ssnd = Reader::new(body, Endian::Big);
+                let offset = ssnd.read_u32()? as usize;
+                ssnd.skip(4 + offset)?;
+                samples = Some(&body[ssnd.pos()..]);
+            }
+            _ => {}
+        }
+    }
+    let (Some((channels, bits_per_sample, sample_rate)), Some(samples)) = (format, samples) else {
+        bail!("AIFF file is missing its COMM or SSND chunk");
+    };
+    if bits_per_sample != 8 && bits_per_sample != 16 {
+        bail!("Unsupported AIFF sample size: {} bits", bits_per_sample);
+    }
+    // AIFF 8-bit samples are signed, WAV ones unsigned
+    let data = if bits_per_sample == 8 {
+        samples.iter().map(|&b| b ^ 0x80).collect()
+    } else {
+        to_wav_order(samples, 16)
+    };
+    Ok(Some(Sound {
+        sample_rate,
+        channels,
+        bits_per_sample,
+        data,
+    }))
+}
+
+/// Converts an 80-bit IEEE extended float, as used for AIFF sample rates.
+fn read_extended(bytes: &[u8]) -> u32 {
+    let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7fff) as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap_or_default());
+    let shift = 16383 + 63 - exponent;
+    if !(0..64).contains(&shift) {
+        return 0;
+    }
+    (mantissa >> shift) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(data: &[u8]) -> Vec<i16> {
+        data.chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_ima4_packets() {
+        let mut packet = vec![0; IMA4_PACKET_LEN];
+        packet[2] = 0x07;
+        let decoded = samples(&decode_ima4(&packet, 1, 64));
+        assert_eq!(decoded.len(), 64);
+        assert_eq!(decoded[..3], [11, 13, 14]);
+        // Frames beyond the header's count are dropped
+        assert_eq!(decode_ima4(&packet, 1, 10).len(), 20);
+    }
+
+    #[test]
+    fn interleaves_ima4_channels() {
+        let mut data = vec![0; IMA4_PACKET_LEN * 2];
+        data[2] = 0x07;
+        // The right channel's packet starts from a predictor of 256
+        data[IMA4_PACKET_LEN] = 0x01;
+        let decoded = samples(&decode_ima4(&data, 2, 64));
+        assert_eq!(decoded.len(), 128);
+        assert_eq!(decoded[..4], [11, 256, 13, 256]);
+    }
+
+    #[test]
+    fn decodes_snd_with_a_standard_header() {
+        let mut snd = vec![0, 1, 0, 1, 0, 5, 0, 0, 0, 0, 0, 1];
+        snd.extend([0x80, 0x51, 0, 0, 0, 0, 0, 20]);
+        snd.extend([0, 0, 0, 0, 0, 0, 0, 3]);
+        snd.extend(0x5622_0000u32.to_be_bytes());
+        snd.extend([0; 8]);
+        snd.extend([STANDARD_HEADER, 0x3c]);
+        snd.extend([0x80, 0xff, 0x00, 0x7f]);
+        let sound = decode_snd(&snd).unwrap();
+        assert_eq!((sound.sample_rate, sound.channels), (22050, 1));
+        assert_eq!(sound.bits_per_sample, 8);
+        assert_eq!(sound.data, [0x80, 0xff, 0x00]);
+
+        let wav = sound.to_wav();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        // The odd-length data is padded, and the RIFF size counts the pad
+        assert_eq!(wav.len(), 44 + 3 + 1);
+        assert_eq!(wav[4..8], (wav.len() as u32 - 8).to_le_bytes());
+        assert_eq!(wav[40..44], 3u32.to_le_bytes());
+    }
+
+    #[test]
+    fn swaps_16_bit_samples() {
+        assert_eq!(
+            to_wav_order(&[0x12, 0x34, 0x56, 0x78], 16),
+            [0x34, 0x12, 0x78, 0x56]
+        );
+        assert_eq!(to_wav_order(&[0x12, 0x34], 8), [0x12, 0x34]);
+    }
+
+    #[test]
+    fn refuses_damaged_channel_counts() {
+        let header = |channels: u32| {
+            let mut header = vec![0; 4];
+            header.extend(channels.to_be_bytes());
+            header.extend(0x5622_0000u32.to_be_bytes());
+            header.extend([0; 8]);
+            header.extend([EXTENDED_HEADER, 0x3c]);
+            header.extend(1u32.to_be_bytes());
+            header.extend([0; 22]);
+            header.extend(16u16.to_be_bytes());
+            header.extend([0; 14]);
+            header
+        };
+        let sound = decode_split(&header(0), &[0x12, 0x34]).unwrap();
+        assert_eq!((sound.channels, sound.data.len()), (1, 2));
+        assert!(decode_split(&header(2), &[0; 4]).is_ok());
+        assert!(decode_split(&header(4096), &[0; 4]).is_err());
+
+        // A sound made some other way still writes a header
+        let sound = Sound {
+            sample_rate: u32::MAX,
+            channels: u16::MAX,
+            bits_per_sample: 16,
+            data: Vec::new(),
+        };
+        assert_eq!(sound.to_wav().len(), 44);
+    }
+}
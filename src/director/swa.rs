@@ -0,0 +1,186 @@
+//! Shockwave Audio (SWA): MPEG-1/2 Layer III frames behind a Macromedia
+//! header. The header is not needed to play the stream, so it is skipped by
+//! looking for the first run of valid MPEG frames.
+
+use super::sound::Sound;
+use anyhow::{bail, Context, Result};
+use std::io::{Cursor, ErrorKind};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Frames that must follow each other before a sync word is trusted.
+const MIN_FRAMES: usize = 3;
+
+const MPEG1_BITRATES: [u32; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_BITRATES: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+const MPEG1_SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Returns the Layer III stream inside SWA data, or `None` if it contains
+/// no MPEG audio.
+pub fn find_mp3_stream(data: &[u8]) -> Option<&[u8]> {
+    let mut start = 0;
+    while start + 4 <= data.len() {
+        if data[start] == 0xff {
+            let (frames, end) = frames_end(data, start);
+            if frames >= MIN_FRAMES || (frames > 0 && end == data.len()) {
+                return Some(&data[start..end]);
+            }
+        }
+        start += 1;
+    }
+    None
+}
+
+/// Decodes a Layer III stream to 16-bit PCM.
+pub fn decode_mp3(mp3: &[u8]) -> Result<Sound> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(mp3.to_vec())), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension("mp3");
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .context("Failed to read MPEG stream")?
+        .format;
+    let track = format
+        .default_track()
+        .context("MPEG stream has no audio track")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("Failed to create MP3 decoder")?;
+
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = 0;
+    let mut data = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).context("Failed to read MP3 frame"),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A damaged frame only loses its own samples
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(e).context("Failed to decode MP3 frame"),
+        };
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        channels = spec.channels.count() as u16;
+        let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        data.extend(buffer.samples().iter().flat_map(|s| s.to_le_bytes()));
+    }
+    if channels == 0 {
+        bail!("MPEG stream contains no decodable frames");
+    }
+    Ok(Sound {
+        sample_rate,
+        channels,
+        bits_per_sample: 16,
+        data,
+    })
+}
+
+/// Follows Layer III frame headers from `start` for as long as they are
+/// valid. Returns the number of frames and the offset after the last one.
+fn frames_end(data: &[u8], start: usize) -> (usize, usize) {
+    let mut offset = start;
+    let mut frames = 0;
+    while let Some(len) = data.get(offset..offset + 4).and_then(frame_len) {
+        if offset + len > data.len() {
+            break;
+        }
+        offset += len;
+        frames += 1;
+    }
+    (frames, offset)
+}
+
+/// Length in bytes of the Layer III frame starting with `header`.
+fn frame_len(header: &[u8]) -> Option<usize> {
+    if header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+    let version = (header[1] >> 3) & 3;
+    let layer = (header[1] >> 1) & 3;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let rate_index = ((header[2] >> 2) & 3) as usize;
+    let padding = ((header[2] >> 1) & 1) as usize;
+    // Version 1 is reserved, layer 1 is Layer III
+    if version == 1 || layer != 1 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return None;
+    }
+    let (bitrate, sample_rate, factor) = match version {
+        3 => (
+            MPEG1_BITRATES[bitrate_index],
+            MPEG1_SAMPLE_RATES[rate_index],
+            144,
+        ),
+        2 => (
+            MPEG2_BITRATES[bitrate_index],
+            MPEG1_SAMPLE_RATES[rate_index] / 2,
+            72,
+        ),
+        _ => (
+            MPEG2_BITRATES[bitrate_index],
+            MPEG1_SAMPLE_RATES[rate_index] / 4,
+            72,
+        ),
+    };
+    Some((factor * bitrate * 1000 / sample_rate) as usize + padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A silent MPEG-1 Layer III frame at 128 kbit/s and 44.1 kHz.
+    fn frame() -> Vec<u8> {
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        frame
+    }
+
+    #[test]
+    fn computes_frame_lengths() {
+        assert_eq!(frame_len(&[0xff, 0xfb, 0x90, 0x00]), Some(417));
+        // With a padding byte
+        assert_eq!(frame_len(&[0xff, 0xfb, 0x92, 0x00]), Some(418));
+        // MPEG-2 at 64 kbit/s and 22.05 kHz
+        assert_eq!(frame_len(&[0xff, 0xf3, 0x80, 0x00]), Some(208));
+        // A free or bad bitrate, Layer II, and no sync word
+        assert_eq!(frame_len(&[0xff, 0xfb, 0x00, 0x00]), None);
+        assert_eq!(frame_len(&[0xff, 0xfb, 0xf0, 0x00]), None);
+        assert_eq!(frame_len(&[0xff, 0xfd, 0x90, 0x00]), None);
+        assert_eq!(frame_len(&[0x00, 0xfb, 0x90, 0x00]), None);
+    }
+
+    #[test]
+    fn skips_the_swa_header() {
+        // The header has a stray 0xff that isn't followed by frames
+        let mut data = vec![0, 0, 0x01, 0x40, 0xff, 0xfb, 0x90, 0, 0, 0];
+        let header_len = data.len();
+        for _ in 0..MIN_FRAMES {
+            data.extend(frame());
+        }
+        let stream = find_mp3_stream(&data).unwrap();
+        assert_eq!(stream.len(), 417 * MIN_FRAMES);
+        assert_eq!(stream.as_ptr(), data[header_len..].as_ptr());
+        assert!(find_mp3_stream(&data[..header_len]).is_none());
+    }
+}
@@ -0,0 +1,433 @@
+//! Styled text. Fields keep theirs in `STXT`: the characters followed by a
+//! table of style runs, with the alignment in the member's `CASt` data.
+//! Text members from Director 7 on are owned by the Text Asset Xtra, which
+//! stores them as `XMED`; Director 6 text members carry a plain copy of
+//! their text in `RTE1`.
+//!
+//! The `XMED` layout isn't documented and only its text is recovered here:
+//! the text section is stored as a hexadecimal length, a comma and the
+//! characters. Its style and paragraph sections aren't decoded yet, so text
+//! members come out as plain text, marked as such, rather than with empty
+//! style runs standing in for their formatting.
+
+use super::{decode_string, Endian, Reader};
+use anyhow::{bail, Result};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+
+const STYLE_BOLD: u8 = 1;
+const STYLE_ITALIC: u8 = 2;
+const STYLE_UNDERLINE: u8 = 4;
+/// Style bits in the order Lingo lists them in `fontStyle`.
+const STYLE_NAMES: [(u8, &str); 7] = [
+    (STYLE_BOLD, "bold"),
+    (STYLE_ITALIC, "italic"),
+    (STYLE_UNDERLINE, "underline"),
+    (8, "outline"),
+    (16, "shadow"),
+    (32, "condense"),
+    (64, "extend"),
+];
+
+const XMED_MAGIC: &[u8] = b"FFFF";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    /// Reads a field's alignment from its `CASt` data, where it follows the
+    /// border, margin, shadow and box type bytes.
+    pub fn from_field_data(data: &[u8]) -> Alignment {
+        match data.get(4..6).map(|b| i16::from_be_bytes([b[0], b[1]])) {
+            Some(1) => Alignment::Center,
+            Some(-1) => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn css(self) -> &'static str {
+        match self {
+            Alignment::Left => "left",
+            Alignment::Center => "center",
+            Alignment::Right => "right",
+        }
+    }
+}
+
+/// Formatting from one offset in the text up to the next run.
+#[derive(Clone, Serialize)]
+pub struct StyleRun {
+    /// Offset of the first character, in characters.
+    pub start: usize,
+    /// Font name from the movie's font map.
+    pub font: String,
+    pub font_id: u16,
+    pub size: u16,
+    #[serde(serialize_with = "style_names")]
+    pub style: u8,
+    pub colour: [u8; 3],
+    pub line_height: u16,
+    pub ascent: u16,
+}
+
+impl StyleRun {
+    fn has(&self, style: u8) -> bool {
+        self.style & style != 0
+    }
+}
+
+#[derive(Serialize)]
+pub struct StyledText {
+    /// The text with Director's carriage returns turned into newlines.
+    pub text: String,
+    pub alignment: Alignment,
+    pub runs: Vec<StyleRun>,
+    /// Set when the text comes from a format whose styles aren't decoded,
+    /// so the runs and alignment say nothing about its formatting.
+    #[serde(skip)]
+    pub plain: bool,
+}
+
+/// Reads a `STXT` chunk, naming the runs' fonts from `fonts`.
+pub fn read_stxt(data: &[u8], endian: Endian, fonts: &HashMap<u16, String>) -> Result<StyledText> {
+    let mut reader = Reader::new(data, Endian::Big);
+    let offset = reader.read_u32()? as usize;
+    let text_len = reader.read_u32()? as usize;
+    let style_len = reader.read_u32()? as usize;
+    reader.seek(offset)?;
+    let text = decode_string(reader.read_bytes(text_len)?, endian).replace('\r', "\n");
+
+    // A damaged style table shouldn't cost the text
+    let runs = if style_len >= 2 {
+        read_runs(&mut reader, fonts).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    Ok(StyledText {
+        text,
+        alignment: Alignment::Left,
+        runs,
+        plain: false,
+    })
+}
+
+fn read_runs(reader: &mut Reader, fonts: &HashMap<u16, String>) -> Result<Vec<StyleRun>> {
+    let count = reader.read_u16()?;
+    let mut runs = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let start = reader.read_u32()? as usize;
+        let line_height = reader.read_u16()?;
+        let ascent = reader.read_u16()?;
+        let font_id = reader.read_u16()?;
+        let style = reader.read_u8()?;
+        reader.skip(1)?;
+        let size = reader.read_u16()?;
+        // Colours are QuickDraw's 16 bits per component
+        let mut colour = [0; 3];
+        for component in &mut colour {
+            *component = (reader.read_u16()? >> 8) as u8;
+        }
+        runs.push(StyleRun {
+            start,
+            font: font_name(fonts, font_id),
+            font_id,
+            size,
+            style,
+            colour,
+            line_height,
+            ascent,
+        });
+    }
+    Ok(runs)
+}
+
+/// The font a run uses, by id when the font map doesn't have it.
+fn font_name(fonts: &HashMap<u16, String>, id: u16) -> String {
+    fonts
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| format!("font {}", id))
+}
+
+/// Recovers the text of an `XMED` chunk, without its styles.
+pub fn read_xmed(data: &[u8], endian: Endian) -> Result<StyledText> {
+    if !data.starts_with(XMED_MAGIC) {
+        bail!("Not an XMED chunk");
+    }
+    // The text is the first length-prefixed section whose length fits,
+    // with up to eight digits since the sections before it run together
+    let text = (XMED_MAGIC.len()..data.len())
+        .filter(|&comma| data[comma] == b',')
+        .find_map(|comma| {
+            let digits = data[..comma]
+                .iter()
+                .rev()
+                .take_while(|b| b.is_ascii_hexdigit())
+                .take(8)
+                .count();
+            let len = std::str::from_utf8(&data[comma - digits..comma]).ok()?;
+            let len = usize::from_str_radix(len, 16).ok().filter(|&len| len > 0)?;
+            let text = data.get(comma + 1..comma + 1 + len)?;
+            text.iter()
+                .all(|&b| b >= 0x20 || b == b'\r' || b == b'\t')
+                .then_some(text)
+        });
+    match text {
+        Some(text) => Ok(plain_text(decode_string(text, endian))),
+        None => bail!("No text found in XMED chunk"),
+    }
+}
+
+/// Reads the plain copy of a Director 6 text member's text from `RTE1`.
+pub fn read_rte1(data: &[u8], endian: Endian) -> StyledText {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    plain_text(decode_string(&data[..end], endian))
+}
+
+fn plain_text(text: String) -> StyledText {
+    StyledText {
+        text: text.replace("\r\n", "\n").replace('\r', "\n"),
+        alignment: Alignment::Left,
+        runs: Vec::new(),
+        plain: true,
+    }
+}
+
+fn style_names<S: Serializer>(style: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        STYLE_NAMES
+            .iter()
+            .filter(|(bit, _)| style & bit != 0)
+            .map(|(_, name)| name),
+    )
+}
+
+impl StyledText {
+    /// Splits the text into the stretches each run covers. Text before the
+    /// first run, or all of it without runs, has no run.
+    fn spans(&self) -> Vec<(&str, Option<&StyleRun>)> {
+        let byte_offset = |chars: usize| {
+            self.text
+                .char_indices()
+                .nth(chars)
+                .map_or(self.text.len(), |(offset, _)| offset)
+        };
+        let mut runs: Vec<&StyleRun> = self.runs.iter().collect();
+        runs.sort_by_key(|run| run.start);
+        let mut spans = Vec::new();
+        let first = runs
+            .first()
+            .map_or(self.text.len(), |run| byte_offset(run.start));
+        if first > 0 {
+            spans.push((&self.text[..first], None));
+        }
+        for (index, run) in runs.iter().enumerate() {
+            let start = byte_offset(run.start);
+            let end = runs
+                .get(index + 1)
+                .map_or(self.text.len(), |next| byte_offset(next.start));
+            if start < end {
+                spans.push((&self.text[start..end], Some(*run)));
+            }
+        }
+        spans
+    }
+
+    /// Markdown with bold, italic and underline kept and every line break
+    /// a hard break. Fonts, sizes and colours have no Markdown equivalent;
+    /// the HTML and JSON versions keep them.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![String::new()];
+        for (text, run) in self.spans() {
+            for (index, piece) in text.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(String::new());
+                }
+                let line = lines.last_mut().unwrap();
+                match run {
+                    Some(run) => line.push_str(&emphasise(piece, run)),
+                    None => line.push_str(&escape_markdown(piece)),
+                }
+            }
+        }
+        let mut markdown = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            markdown.push_str(line);
+            let next_has_text = lines.get(index + 1).is_some_and(|next| !next.is_empty());
+            if !line.is_empty() && next_has_text {
+                markdown.push_str("  ");
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    /// A standalone HTML page with every run as a styled `span`. Line breaks
+    /// and runs of spaces are kept with `pre-wrap`.
+    pub fn to_html(&self, title: &str) -> String {
+        let mut body = String::new();
+        for (text, run) in self.spans() {
+            let text = escape_html(text);
+            match run {
+                Some(run) => body.push_str(&format!(
+                    r#"<span style="{}">{}</span>"#,
+                    escape_html(&css(run)),
+                    text
+                )),
+                None => body.push_str(&text),
+            }
+        }
+        format!(
+            concat!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+                "<title>{title}</title>\n</head>\n<body>\n",
+                "<div style=\"text-align: {alignment}; white-space: pre-wrap\">",
+                "{body}</div>\n</body>\n</html>\n"
+            ),
+            title = escape_html(title),
+            alignment = self.alignment.css(),
+            body = body
+        )
+    }
+}
+
+/// Wraps a stretch of text in the emphasis its run calls for, keeping the
+/// surrounding whitespace outside the markers so they still apply.
+fn emphasise(text: &str, run: &StyleRun) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    let mut inner = escape_markdown(trimmed);
+    let marker = match (run.has(STYLE_BOLD), run.has(STYLE_ITALIC)) {
+        (true, true) => "***",
+        (true, false) => "**",
+        (false, true) => "*",
+        (false, false) => "",
+    };
+    inner = format!("{marker}{inner}{marker}");
+    if run.has(STYLE_UNDERLINE) {
+        inner = format!("<u>{}</u>", inner);
+    }
+    format!("{}{}{}", leading, inner, trailing)
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn css(run: &StyleRun) -> String {
+    let [r, g, b] = run.colour;
+    let mut css = format!(
+        "font-family: '{}'; font-size: {}px; color: #{:02x}{:02x}{:02x}",
+        run.font.replace('\'', "\\'"),
+        run.size,
+        r,
+        g,
+        b
+    );
+    if run.has(STYLE_BOLD) {
+        css.push_str("; font-weight: bold");
+    }
+    if run.has(STYLE_ITALIC) {
+        css.push_str("; font-style: italic");
+    }
+    if run.has(STYLE_UNDERLINE) {
+        css.push_str("; text-decoration: underline");
+    }
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stxt(text: &[u8], runs: &[(u32, u16, u8, u16)]) -> Vec<u8> {
+        let mut style = (runs.len() as u16).to_be_bytes().to_vec();
+        for &(start, font_id, style_bits, size) in runs {
+            style.extend(start.to_be_bytes());
+            style.extend([0, 12, 0, 10]);
+            style.extend(font_id.to_be_bytes());
+            style.extend([style_bits, 0]);
+            style.extend(size.to_be_bytes());
+            style.extend([0xff, 0xff, 0, 0, 0, 0]);
+        }
+        let mut data = 12u32.to_be_bytes().to_vec();
+        data.extend((text.len() as u32).to_be_bytes());
+        data.extend((style.len() as u32).to_be_bytes());
+        data.extend(text);
+        data.extend(style);
+        data
+    }
+
+    #[test]
+    fn reads_stxt_runs() {
+        let fonts = HashMap::from([(3, "Geneva".to_string())]);
+        let data = stxt(b"Extra!\rPolisen", &[(0, 3, STYLE_BOLD, 18), (7, 4, 0, 10)]);
+        let styled = read_stxt(&data, Endian::Big, &fonts).unwrap();
+        assert_eq!(styled.text, "Extra!\nPolisen");
+        assert!(!styled.plain);
+        assert_eq!(styled.runs.len(), 2);
+        assert_eq!(styled.runs[0].font, "Geneva");
+        assert_eq!(styled.runs[0].colour, [0xff, 0, 0]);
+        assert_eq!(styled.runs[1].font, "font 4");
+        assert_eq!(styled.to_markdown(), "**Extra!**  \nPolisen\n");
+        let html = styled.to_html("news");
+        assert!(html.contains("font-family: 'Geneva'; font-size: 18px; color: #ff0000"));
+    }
+
+    #[test]
+    fn keeps_the_text_of_a_damaged_style_table() {
+        let mut data = stxt(b"Hej", &[(0, 3, 0, 12)]);
+        data.truncate(data.len() - 4);
+        let styled = read_stxt(&data, Endian::Big, &HashMap::new()).unwrap();
+        assert_eq!(styled.text, "Hej");
+        assert!(styled.runs.is_empty());
+    }
+
+    #[test]
+    fn escapes_markdown() {
+        let styled = StyledText {
+            text: "*not* bold\n".to_string(),
+            alignment: Alignment::Left,
+            runs: Vec::new(),
+            plain: false,
+        };
+        assert_eq!(styled.to_markdown(), "\\*not\\* bold\n\n");
+    }
+
+    #[test]
+    fn reads_xmed_text_without_styles() {
+        let data = b"FFFF0000000600040000000B,Hej\rp\x8c dig!\x00\x01,";
+        let styled = read_xmed(data, Endian::Big).unwrap();
+        assert_eq!(styled.text, "Hej\npå dig!");
+        assert!(styled.plain);
+        assert!(read_xmed(b"RIFX", Endian::Big).is_err());
+        assert_eq!(
+            read_rte1(b"Gammal\rtext\x00junk", Endian::Big).text,
+            "Gammal\ntext"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Fingerprints of known releases: the SHA-256 of every movie of a game's
+//! edition, kept in the game's profile. A disc whose movies match an
+//! edition's is that edition whatever the files are called, which tells
+//! apart games sharing file names like the two Mulle Meck titles. Movies
+//! that differ from the release they were matched to are flagged.
+//!
+//! `cgex fingerprint` prints the fingerprints of a disc in profile format.
+
+use crate::game_extractor::GameExtractor;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// One release of a game.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Edition {
+    pub name: String,
+    /// Language code, e.g. `sv`.
+    pub language: String,
+    pub year: u16,
+    /// SHA-256 of each movie in upper case hex, by file name. Names are
+    /// matched ignoring case, like `expected_files`.
+    pub files: BTreeMap<String, String>,
+}
+
+/// A movie on the disc being detected.
+pub struct MovieFile {
+    /// Lower case file name.
+    pub name: String,
+    pub sha256: String,
+}
+
+/// The game a disc was detected as, and how sure that is.
+pub struct Detection {
+    pub game: Box<dyn GameExtractor>,
+    /// The release the movies were matched to by fingerprint.
+    pub edition: Option<Edition>,
+    /// From 0 to 1. For a fingerprint match, the edition's movies found
+    /// unchanged, over its movies plus the disc's other ones. A match by
+    /// file names alone can't be sure of much, so it gets half the share
+    /// of expected files found, and half that again when another game
+    /// expects as many of them.
+    pub confidence: f32,
+    /// Movies named like one of the edition's, with other content.
+    pub modified_files: Vec<String>,
+    /// Movies that aren't part of the matched edition, or for a match by
+    /// file names, not expected by the game at all.
+    pub unknown_files: Vec<String>,
+}
+
+/// An edition that some of the movies matched.
+pub struct EditionMatch {
+    /// Index of the edition's game.
+    pub game: usize,
+    pub edition: Edition,
+    pub confidence: f32,
+    pub modified_files: Vec<String>,
+    pub unknown_files: Vec<String>,
+}
+
+/// Finds the edition the most movies belong to by content. Ties go to the
+/// edition with the higher confidence, then to the first one.
+pub fn match_edition(
+    games: &[Box<dyn GameExtractor>],
+    movies: &[MovieFile],
+) -> Option<EditionMatch> {
+    let mut best: Option<(usize, f32, usize, &Edition)> = None;
+    for (index, game) in games.iter().enumerate() {
+        for edition in game.get_editions() {
+            let hashes: HashSet<String> = edition
+                .files
+                .values()
+                .map(|hash| hash.to_uppercase())
+                .collect();
+            let found: HashSet<&str> = movies
+                .iter()
+                .map(|movie| movie.sha256.as_str())
+                .filter(|hash| hashes.contains(*hash))
+                .collect();
+            if found.is_empty() {
+                continue;
+            }
+            let strays = movies
+                .iter()
+                .filter(|movie| !hashes.contains(&movie.sha256))
+                .count();
+            let confidence = found.len() as f32 / (hashes.len() + strays) as f32;
+            let better = best.is_none_or(|(_, best_confidence, best_found, _)| {
+                found.len() > best_found
+                    || (found.len() == best_found && confidence > best_confidence)
+            });
+            if better {
+                best = Some((index, confidence, found.len(), edition));
+            }
+        }
+    }
+
+    let (game, confidence, _, edition) = best?;
+    let mut modified_files = Vec::new();
+    let mut unknown_files = Vec::new();
+    for movie in movies {
+        let known = edition
+            .files
+            .values()
+            .any(|hash| hash.eq_ignore_ascii_case(&movie.sha256));
+        if known {
+            continue;
+        }
+        let named = edition
+            .files
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(&movie.name));
+        if named {
+            modified_files.push(movie.name.clone());
+        } else {
+            unknown_files.push(movie.name.clone());
+        }
+    }
+    Some(EditionMatch {
+        game,
+        edition: edition.clone(),
+        confidence,
+        modified_files,
+        unknown_files,
+    })
+}
+
+/// The fingerprints of a disc's movies as an `[[editions]]` entry to paste
+/// into a game profile, with the name, language and year to fill in.
+pub fn edition_template(movies: &[MovieFile]) -> String {
+    #[derive(Serialize)]
+    struct Editions {
+        editions: [Edition; 1],
+    }
+
+    let edition = Edition {
+        name: "Unknown release".to_string(),
+        language: String::new(),
+        year: 0,
+        files: movies
+            .iter()
+            .map(|movie| (movie.name.clone(), movie.sha256.clone()))
+            .collect(),
+    };
+    toml::to_string(&Editions {
+        editions: [edition],
+    })
+    .expect("Editions always serialise")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::GameProfile;
+
+    fn game(profile: &str) -> Box<dyn GameExtractor> {
+        Box::new(toml::from_str::<GameProfile>(profile).unwrap())
+    }
+
+    fn movie(name: &str, sha256: &str) -> MovieFile {
+        MovieFile {
+            name: name.to_string(),
+            sha256: sha256.to_string(),
+        }
+    }
+
+    /// Two games sharing movie names, the first with two releases.
+    fn games() -> Vec<Box<dyn GameExtractor>> {
+        vec![
+            game(concat!(
+                "name = \"Bygg bilar\"\n",
+                "transparent_color = [255, 255, 255]\n",
+                "expected_files = [\"02.dxr\", \"03.dxr\"]\n",
+                "[[editions]]\nname = \"Original\"\nlanguage = \"sv\"\nyear = 1997\n",
+                "files = { \"02.dxr\" = \"aa\", \"03.dxr\" = \"bb\" }\n",
+                "[[editions]]\nname = \"Nyutgåva\"\nlanguage = \"sv\"\nyear = 2000\n",
+                "files = { \"02.dxr\" = \"aa\", \"03.dxr\" = \"cc\" }\n",
+            )),
+            game(concat!(
+                "name = \"Bygg båtar\"\n",
+                "transparent_color = [255, 255, 255]\n",
+                "expected_files = [\"02.dxr\", \"03.dxr\"]\n",
+                "[[editions]]\nname = \"Original\"\nlanguage = \"sv\"\nyear = 2000\n",
+                "files = { \"02.dxr\" = \"DD\", \"03.dxr\" = \"EE\" }\n",
+            )),
+        ]
+    }
+
+    #[test]
+    fn matches_the_edition_with_the_most_movies() {
+        let games = games();
+        let movies = [movie("02.dxr", "AA"), movie("03.dxr", "CC")];
+        let found = match_edition(&games, &movies).unwrap();
+        assert_eq!((found.game, found.edition.year), (0, 2000));
+        assert_eq!(found.confidence, 1.0);
+        assert!(found.modified_files.is_empty() && found.unknown_files.is_empty());
+
+        // Same names, other content: the other game, whose hashes are upper case
+        let movies = [movie("02.dxr", "DD"), movie("03.dxr", "EE")];
+        assert_eq!(match_edition(&games, &movies).unwrap().game, 1);
+    }
+
+    #[test]
+    fn flags_modified_and_unknown_movies() {
+        let games = games();
+        let movies = [
+            movie("02.dxr", "AA"),
+            movie("03.dxr", "FF"),
+            movie("extra.dxr", "00"),
+        ];
+        let found = match_edition(&games, &movies).unwrap();
+        assert_eq!(found.game, 0);
+        assert_eq!(found.modified_files, ["03.dxr"]);
+        assert_eq!(found.unknown_files, ["extra.dxr"]);
+        // One of the two movies found, with two strays
+        assert_eq!(found.confidence, 0.25);
+
+        assert!(match_edition(&games, &[movie("02.dxr", "FF")]).is_none());
+    }
+
+    #[test]
+    fn matches_file_names_ignoring_case() {
+        let games = vec![game(concat!(
+            "name = \"Bygg bilar\"\n",
+            "transparent_color = [255, 255, 255]\n",
+            "expected_files = [\"02.DXR\", \"03.DXR\"]\n",
+            "[[editions]]\nname = \"Original\"\nlanguage = \"sv\"\nyear = 1997\n",
+            "files = { \"02.DXR\" = \"AA\", \"03.DXR\" = \"BB\" }\n",
+        ))];
+        // Disc names are lower case
+        let movies = [movie("02.dxr", "AA"), movie("03.dxr", "FF")];
+        let found = match_edition(&games, &movies).unwrap();
+        assert_eq!(found.modified_files, ["03.dxr"]);
+        assert!(found.unknown_files.is_empty());
+    }
+
+    #[test]
+    fn prints_editions_in_profile_format() {
+        let template = edition_template(&[movie("02.dxr", "AA")]);
+        let edition: toml::Value = toml::from_str(&template).unwrap();
+        assert_eq!(
+            edition["editions"][0]["files"]["02.dxr"].as_str(),
+            Some("AA")
+        );
+    }
+}
@@ -0,0 +1,772 @@
+//! Reads the HFS or HFS+ volume of a Mac or hybrid CD image, with the
+//! files' resource forks and Finder type and creator codes.
+//!
+//! Hybrid discs put an Apple partition map (or the volume itself) at the
+//! start of the disc, where ISO 9660 leaves its first sixteen sectors
+//! unused. Files are found by walking the leaves of the catalog B-tree;
+//! forks fragmented past the extents kept in the catalog continue in the
+//! extents overflow B-tree. HFS+ volumes can be wrapped in an HFS volume
+//! for older Macs, in which case the embedded one is read.
+
+use crate::director::{decode_string, Endian, Reader};
+use crate::iso::DiscImage;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// Where a volume's header sits, from the start of the volume.
+const HEADER_OFFSET: u64 = 1024;
+const HFS_SIGNATURE: &[u8] = b"BD";
+const HFS_PLUS_SIGNATURES: [&[u8]; 2] = [b"H+", b"HX"];
+const PARTITION_MAP_BLOCK: u64 = 512;
+const HFS_PARTITION: &[u8] = b"Apple_HFS";
+
+const ROOT_PARENT_ID: u32 = 1;
+const CATALOG_FILE_ID: u32 = 4;
+
+const LEAF_NODE: i8 = -1;
+const HEADER_NODE: i8 = 1;
+/// Length of the descriptor at the start of every B-tree node.
+const NODE_DESCRIPTOR_LEN: usize = 14;
+const FOLDER_RECORD: i16 = 1;
+const FILE_RECORD: i16 = 2;
+const DATA_FORK: u8 = 0x00;
+const RESOURCE_FORK: u8 = 0xff;
+
+/// Finder type codes of Director movies and casts, with the extension a
+/// Windows copy of the file would have. Mac files rarely have one.
+const DIRECTOR_TYPES: [(&[u8; 4], &str); 6] = [
+    (b"MV93", "dir"),
+    (b"MV95", "dir"),
+    (b"M!93", "dxr"),
+    (b"M!95", "dxr"),
+    (b"MC95", "cst"),
+    (b"MC*5", "cxt"),
+];
+
+/// Extension the resource fork of a file is written with, next to its
+/// data fork.
+pub const RESOURCE_FORK_EXTENSION: &str = "rsrc";
+
+/// Whether a disc starts the way a Mac volume or partitioned Mac disc
+/// does. `start` is the first 1026 bytes of the disc.
+pub fn is_hfs_start(start: &[u8]) -> bool {
+    let signature = &start[HEADER_OFFSET as usize..HEADER_OFFSET as usize + 2];
+    start.starts_with(b"ER")
+        || signature == HFS_SIGNATURE
+        || HFS_PLUS_SIGNATURES.contains(&signature)
+}
+
+/// A contiguous run of allocation blocks.
+#[derive(Clone, Copy)]
+struct Extent {
+    start: u32,
+    count: u32,
+}
+
+#[derive(Clone, Default)]
+struct Fork {
+    len: u64,
+    extents: Vec<Extent>,
+}
+
+/// A file on the volume, with its path from the root folder.
+pub struct MacFile {
+    pub path: PathBuf,
+    pub file_type: [u8; 4],
+    pub creator: [u8; 4],
+    data: Fork,
+    resource: Fork,
+}
+
+/// A file's Finder info, for `finder_info.json`.
+#[derive(Serialize)]
+struct FinderInfo {
+    path: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    creator: String,
+    data_len: u64,
+    resource_len: u64,
+}
+
+impl MacFile {
+    /// The path to write the data fork to. Director files get the extension
+    /// their type code calls for unless they already have a Director one,
+    /// so they are picked up like the Windows copies.
+    pub fn export_path(&self) -> PathBuf {
+        let has_director_extension = self.path.extension().is_some_and(|extension| {
+            DIRECTOR_TYPES
+                .iter()
+                .any(|(_, director)| extension.eq_ignore_ascii_case(director))
+        });
+        match DIRECTOR_TYPES
+            .iter()
+            .find(|(file_type, _)| **file_type == self.file_type)
+        {
+            Some((_, extension)) if !has_director_extension => {
+                let mut path = self.path.clone().into_os_string();
+                path.push(".");
+                path.push(extension);
+                path.into()
+            }
+            _ => self.path.clone(),
+        }
+    }
+
+    fn finder_info(&self) -> FinderInfo {
+        FinderInfo {
+            path: self.export_path().to_string_lossy().replace('\\', "/"),
+            file_type: decode_string(&self.file_type, Endian::Big),
+            creator: decode_string(&self.creator, Endian::Big),
+            data_len: self.data.len,
+            resource_len: self.resource.len,
+        }
+    }
+}
+
+pub struct HfsVolume<'a> {
+    image: &'a mut DiscImage,
+    /// Byte offset of allocation block 0 in the data track.
+    blocks_start: u64,
+    block_size: u64,
+    plus: bool,
+    catalog: Fork,
+    /// Extents of forks past the ones kept in the catalog, by file id and
+    /// fork type, in order.
+    overflow: HashMap<(u32, u8), Vec<Extent>>,
+}
+
+impl<'a> HfsVolume<'a> {
+    /// Opens the Mac volume of a disc image.
+    pub fn open(image: &'a mut DiscImage) -> Result<HfsVolume<'a>> {
+        let mut start = find_volume(image)?.context("Disc image has no HFS or HFS+ volume")?;
+        let mut header = image.read_at(start + HEADER_OFFSET, 512)?;
+        let mut reader = Reader::new(&header, Endian::Big);
+        let mut plus = HFS_PLUS_SIGNATURES.contains(&reader.read_bytes(2)?);
+        if !plus {
+            reader.seek(124)?;
+            if reader.read_bytes(2)? == b"H+" {
+                // A wrapper; the real volume lives in its allocation blocks
+                let embedded_start = reader.read_u16()? as u64;
+                let (blocks_start, block_size) = hfs_blocks(&header, start)?;
+                start = blocks_start + embedded_start * block_size;
+                header = image.read_at(start + HEADER_OFFSET, 512)?;
+                plus = true;
+            }
+        }
+
+        let mut reader = Reader::new(&header, Endian::Big);
+        let (blocks_start, block_size, extents_file, catalog) = if plus {
+            reader.seek(40)?;
+            let block_size = reader.read_u32()? as u64;
+            reader.seek(192)?;
+            let extents_file = read_plus_fork(&mut reader)?;
+            reader.seek(272)?;
+            let catalog = read_plus_fork(&mut reader)?;
+            (start, block_size, extents_file, catalog)
+        } else {
+            let (blocks_start, block_size) = hfs_blocks(&header, start)?;
+            reader.seek(130)?;
+            let extents_file = read_hfs_fork(&mut reader)?;
+            reader.seek(146)?;
+            let catalog = read_hfs_fork(&mut reader)?;
+            (blocks_start, block_size, extents_file, catalog)
+        };
+        if block_size == 0 {
+            bail!("Mac volume has no allocation block size");
+        }
+        let mut volume = HfsVolume {
+            image,
+            blocks_start,
+            block_size,
+            plus,
+            catalog,
+            overflow: HashMap::new(),
+        };
+        volume.read_overflow(&extents_file)?;
+        // The catalog can be fragmented too
+        if let Some(extents) = volume.overflow.get(&(CATALOG_FILE_ID, DATA_FORK)) {
+            volume.catalog.extents.extend(extents.iter().copied());
+        }
+        Ok(volume)
+    }
+
+    /// Reads the extents overflow file, which the catalog itself may need.
+    fn read_overflow(&mut self, extents_file: &Fork) -> Result<()> {
+        for record in self.leaf_records(extents_file)? {
+            let mut reader = Reader::new(&record, Endian::Big);
+            let (fork_type, file_id, data_start) = if self.plus {
+                reader.skip(2)?;
+                let fork_type = reader.read_u8()?;
+                reader.skip(1)?;
+                (fork_type, reader.read_u32()?, 12)
+            } else {
+                reader.skip(1)?;
+                let fork_type = reader.read_u8()?;
+                (fork_type, reader.read_u32()?, 8)
+            };
+            reader.seek(data_start)?;
+            let extents = if self.plus {
+                read_plus_extents(&mut reader)?
+            } else {
+                read_hfs_extents(&mut reader)?
+            };
+            // Keys sort by start block, so the records come in fork order
+            self.overflow
+                .entry((file_id, fork_type))
+                .or_default()
+                .extend(extents);
+        }
+        Ok(())
+    }
+
+    /// Lists every file on the volume.
+    pub fn files(&mut self) -> Result<Vec<MacFile>> {
+        let catalog = self.catalog.clone();
+        let mut folders: HashMap<u32, (u32, String)> = HashMap::new();
+        let mut found = Vec::new();
+        for record in self.leaf_records(&catalog)? {
+            let Some((parent, name, data)) = self.split_catalog_record(&record) else {
+                continue;
+            };
+            let mut reader = Reader::new(data, Endian::Big);
+            let record_type = if self.plus {
+                reader.read_u16()? as i16
+            } else {
+                reader.read_u8()? as i16
+            };
+            match record_type {
+                FOLDER_RECORD => {
+                    reader.seek(if self.plus { 8 } else { 6 })?;
+                    folders.insert(reader.read_u32()?, (parent, name));
+                }
+                FILE_RECORD => {
+                    let (id, file) = self.read_file_record(data, &name)?;
+                    found.push((parent, id, file));
+                }
+                _ => {}
+            }
+        }
+
+        let mut files = Vec::new();
+        for (parent, id, mut file) in found {
+            // Files in the hidden folders HFS+ keeps hard links in have no
+            // path from the root
+            let Some(dir) = folder_path(&folders, parent) else {
+                continue;
+            };
+            file.path = dir.join(&file.path);
+            for (fork_type, fork) in [
+                (DATA_FORK, &mut file.data),
+                (RESOURCE_FORK, &mut file.resource),
+            ] {
+                if let Some(extents) = self.overflow.get(&(id, fork_type)) {
+                    fork.extents.extend(extents.iter().copied());
+                }
+            }
+            files.push(file);
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Splits a catalog leaf record into its key's parent id and name, and
+    /// its data. Returns `None` for records without a usable name.
+    fn split_catalog_record<'r>(&self, record: &'r [u8]) -> Option<(u32, String, &'r [u8])> {
+        let mut reader = Reader::new(record, Endian::Big);
+        let (parent, name, data_start) = if self.plus {
+            let key_len = reader.read_u16().ok()? as usize;
+            let parent = reader.read_u32().ok()?;
+            let name_len = reader.read_u16().ok()? as usize;
+            let units: Vec<u16> = reader
+                .read_bytes(name_len * 2)
+                .ok()?
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            // HFS+ stores names decomposed
+            let name: String = String::from_utf16_lossy(&units).nfc().collect();
+            (parent, name, 2 + key_len)
+        } else {
+            let key_len = reader.read_u8().ok()? as usize;
+            reader.skip(1).ok()?;
+            let parent = reader.read_u32().ok()?;
+            let name_len = reader.read_u8().ok()? as usize;
+            let name = decode_string(reader.read_bytes(name_len).ok()?, Endian::Big);
+            // Data starts on a word boundary
+            (parent, name, (1 + key_len + 1) & !1)
+        };
+        // Thread records have no name; the private folders HFS+ hides
+        // hard links in start with NULs
+        if name.is_empty() || name.starts_with('\0') {
+            return None;
+        }
+        // A `/` is fine in a Mac name but not in a path
+        let name = name.replace(['/', '\\'], "_");
+        Some((parent, name, record.get(data_start..)?))
+    }
+
+    /// Reads a file record's id, Finder info and forks. The path is just
+    /// the name until the folders are known.
+    fn read_file_record(&self, data: &[u8], name: &str) -> Result<(u32, MacFile)> {
+        let mut reader = Reader::new(data, Endian::Big);
+        let (finder_info, id_offset, data_offset, resource_offset) = if self.plus {
+            (48, 8, 88, 168)
+        } else {
+            (4, 20, 26, 36)
+        };
+        reader.seek(finder_info)?;
+        let file_type: [u8; 4] = reader.read_bytes(4)?.try_into()?;
+        let creator: [u8; 4] = reader.read_bytes(4)?.try_into()?;
+        reader.seek(id_offset)?;
+        let id = reader.read_u32()?;
+        let (data, resource) = if self.plus {
+            reader.seek(data_offset)?;
+            let data = read_plus_fork(&mut reader)?;
+            reader.seek(resource_offset)?;
+            (data, read_plus_fork(&mut reader)?)
+        } else {
+            // Logical lengths, then the first extents of each fork further on
+            reader.seek(data_offset)?;
+            let data_len = reader.read_u32()? as u64;
+            reader.seek(resource_offset)?;
+            let resource_len = reader.read_u32()? as u64;
+            reader.seek(74)?;
+            let data_extents = read_hfs_extents(&mut reader)?;
+            let resource_extents = read_hfs_extents(&mut reader)?;
+            (
+                Fork {
+                    len: data_len,
+                    extents: data_extents,
+                },
+                Fork {
+                    len: resource_len,
+                    extents: resource_extents,
+                },
+            )
+        };
+        let file = MacFile {
+            path: PathBuf::from(name),
+            file_type,
+            creator,
+            data,
+            resource,
+        };
+        Ok((id, file))
+    }
+
+    fn read_fork(&mut self, fork: &Fork) -> Result<Vec<u8>> {
+        // The length comes from the catalog, so check it against the extents
+        // before reading instead of trusting it for the allocation
+        let extents_len: u64 = fork
+            .extents
+            .iter()
+            .map(|extent| extent.count as u64 * self.block_size)
+            .sum();
+        if fork.len > extents_len {
+            bail!("Fork is missing extents");
+        }
+        let mut data = Vec::new();
+        for extent in &fork.extents {
+            if data.len() as u64 >= fork.len {
+                break;
+            }
+            let len = (extent.count as u64 * self.block_size).min(fork.len - data.len() as u64);
+            let offset = self.blocks_start + extent.start as u64 * self.block_size;
+            data.extend(self.image.read_at(offset, len as usize)?);
+        }
+        Ok(data)
+    }
+
+    /// Reads every record in the leaf nodes of a B-tree file, in key order.
+    fn leaf_records(&mut self, tree: &Fork) -> Result<Vec<Vec<u8>>> {
+        if tree.len == 0 {
+            return Ok(Vec::new());
+        }
+        let data = self.read_fork(tree)?;
+        // The header record follows the header node's descriptor
+        let mut reader = Reader::new(&data, Endian::Big);
+        reader.seek(8)?;
+        if reader.read_u8()? as i8 != HEADER_NODE {
+            bail!("B-tree doesn't start with a header node");
+        }
+        reader.seek(NODE_DESCRIPTOR_LEN + 10)?;
+        let mut node = reader.read_u32()?;
+        reader.seek(NODE_DESCRIPTOR_LEN + 18)?;
+        let node_size = reader.read_u16()? as usize;
+        if node_size == 0 {
+            bail!("B-tree has no node size");
+        }
+
+        let mut records = Vec::new();
+        let mut visited = 0;
+        while node != 0 {
+            visited += 1;
+            if visited > data.len() / node_size {
+                bail!("B-tree leaf chain loops");
+            }
+            let start = node as usize * node_size;
+            let bytes = data
+                .get(start..start + node_size)
+                .context("B-tree node is out of range")?;
+            let mut reader = Reader::new(bytes, Endian::Big);
+            node = reader.read_u32()?;
+            reader.skip(4)?;
+            if reader.read_u8()? as i8 != LEAF_NODE {
+                bail!("B-tree leaf chain leads to a non-leaf node");
+            }
+            reader.skip(1)?;
+            let count = reader.read_u16()? as usize;
+            let records_end = node_size
+                .checked_sub(2 * (count + 1))
+                .filter(|end| *end >= NODE_DESCRIPTOR_LEN)
+                .context("B-tree node has more records than fit in it")?;
+            // Record offsets are stacked backwards from the end of the node,
+            // with one more giving the free space after the last record
+            let offsets = (0..=count)
+                .map(|index| {
+                    reader.seek(node_size - 2 * (index + 1))?;
+                    Ok(reader.read_u16()? as usize)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            for pair in offsets.windows(2) {
+                if pair[0] < NODE_DESCRIPTOR_LEN || pair[0] > pair[1] || pair[1] > records_end {
+                    bail!("B-tree record is outside its node");
+                }
+                records.push(bytes[pair[0]..pair[1]].to_vec());
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn read_data_fork(&mut self, file: &MacFile) -> Result<Vec<u8>> {
+        self.read_fork(&file.data)
+    }
+
+    /// Writes every file on the volume under `dest`: the data fork at the
+    /// file's path, the resource fork next to it with a `.rsrc` extension
+    /// when it has one, and everyone's type and creator codes to
+    /// `finder_info.json`.
+    pub fn extract(&mut self, dest: &Path) -> Result<()> {
+        let files = self.files()?;
+        for file in &files {
+            let path = dest.join(file.export_path());
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let data = self.read_fork(&file.data)?;
+            File::create(&path)
+                .and_then(|mut out| out.write_all(&data))
+                .with_context(|| format!("Failed to extract {:?} from Mac volume", file.path))?;
+            if file.resource.len > 0 {
+                let resource = self.read_fork(&file.resource)?;
+                let mut resource_path = path.into_os_string();
+                resource_path.push(".");
+                resource_path.push(RESOURCE_FORK_EXTENSION);
+                fs::write(&resource_path, resource).with_context(|| {
+                    format!("Failed to extract resource fork of {:?}", file.path)
+                })?;
+            }
+        }
+        let finder_info: Vec<FinderInfo> = files.iter().map(MacFile::finder_info).collect();
+        fs::write(
+            dest.join("finder_info.json"),
+            serde_json::to_string_pretty(&finder_info)?,
+        )
+        .context("Failed to write finder_info.json")
+    }
+}
+
+/// Finds where the Mac volume starts: after the Apple partition map's
+/// `Apple_HFS` entry, or at the start of the disc when it has no map.
+fn find_volume(image: &mut DiscImage) -> Result<Option<u64>> {
+    let block = image.read_at(0, PARTITION_MAP_BLOCK as usize)?;
+    if block.starts_with(b"ER") {
+        // Every entry holds the number of entries in the map, which can't
+        // be more than the blocks after the first that the image has
+        let blocks = (image.data_len()? / PARTITION_MAP_BLOCK).saturating_sub(1);
+        let mut count = 1;
+        let mut index = 1;
+        while index <= count {
+            let entry = image.read_at(index * PARTITION_MAP_BLOCK, PARTITION_MAP_BLOCK as usize)?;
+            let mut reader = Reader::new(&entry, Endian::Big);
+            if reader.read_bytes(2)? != b"PM" {
+                break;
+            }
+            reader.skip(2)?;
+            count = (reader.read_u32()? as u64).min(blocks);
+            let start = reader.read_u32()? as u64;
+            reader.seek(48)?;
+            if reader.read_bytes(32)?.starts_with(HFS_PARTITION) {
+                return Ok(Some(start * PARTITION_MAP_BLOCK));
+            }
+            index += 1;
+        }
+        return Ok(None);
+    }
+    let header = image.read_at(HEADER_OFFSET, 2)?;
+    let found = header == HFS_SIGNATURE || HFS_PLUS_SIGNATURES.contains(&header.as_slice());
+    Ok(found.then_some(0))
+}
+
+/// Where an HFS volume's allocation blocks start and how big they are,
+/// from its master directory block.
+fn hfs_blocks(header: &[u8], start: u64) -> Result<(u64, u64)> {
+    let mut reader = Reader::new(header, Endian::Big);
+    reader.seek(20)?;
+    let block_size = reader.read_u32()? as u64;
+    reader.seek(28)?;
+    let first_sector = reader.read_u16()? as u64;
+    Ok((start + first_sector * 512, block_size))
+}
+
+/// Reads an HFS fork given as its length and first three extents, as the
+/// master directory block has them.
+fn read_hfs_fork(reader: &mut Reader) -> Result<Fork> {
+    let len = reader.read_u32()? as u64;
+    let extents = read_hfs_extents(reader)?;
+    Ok(Fork { len, extents })
+}
+
+fn read_hfs_extents(reader: &mut Reader) -> Result<Vec<Extent>> {
+    let mut extents = Vec::new();
+    for _ in 0..3 {
+        let start = reader.read_u16()? as u32;
+        let count = reader.read_u16()? as u32;
+        if count > 0 {
+            extents.push(Extent { start, count });
+        }
+    }
+    Ok(extents)
+}
+
+/// Reads an HFS+ fork data record: its length, clump size, block count and
+/// first eight extents.
+fn read_plus_fork(reader: &mut Reader) -> Result<Fork> {
+    let len = (reader.read_u32()? as u64) << 32 | reader.read_u32()? as u64;
+    reader.skip(8)?;
+    let extents = read_plus_extents(reader)?;
+    Ok(Fork { len, extents })
+}
+
+fn read_plus_extents(reader: &mut Reader) -> Result<Vec<Extent>> {
+    let mut extents = Vec::new();
+    for _ in 0..8 {
+        let start = reader.read_u32()?;
+        let count = reader.read_u32()?;
+        if count > 0 {
+            extents.push(Extent { start, count });
+        }
+    }
+    Ok(extents)
+}
+
+/// The path of a folder from the root folder, which is the volume itself
+/// and so left out.
+fn folder_path(folders: &HashMap<u32, (u32, String)>, mut id: u32) -> Option<PathBuf> {
+    let mut names = Vec::new();
+    loop {
+        let (parent, name) = folders.get(&id)?;
+        if *parent == ROOT_PARENT_ID {
+            break;
+        }
+        names.push(name.as_str());
+        // A damaged catalog could link folders in a loop
+        if names.len() > folders.len() {
+            return None;
+        }
+        id = *parent;
+    }
+    Some(names.iter().rev().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const BLOCK: usize = 512;
+    /// Allocation blocks of the catalog and of the one file's data.
+    const CATALOG_BLOCK: usize = 8;
+    const DATA_BLOCK: usize = 16;
+
+    /// A directory for one test's files, named after it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cgex_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A B-tree node of `kind` holding `records`, linked to `next`.
+    fn node(kind: i8, next: u32, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut node = next.to_be_bytes().to_vec();
+        node.extend([0; 4]);
+        node.extend([kind as u8, 1]);
+        node.extend((records.len() as u16).to_be_bytes());
+        node.extend([0; 2]);
+        let mut offsets = Vec::new();
+        for record in records {
+            offsets.push(node.len() as u16);
+            node.extend(record);
+        }
+        offsets.push(node.len() as u16);
+        node.resize(BLOCK, 0);
+        for (index, offset) in offsets.iter().enumerate() {
+            let position = BLOCK - 2 * (index + 1);
+            node[position..position + 2].copy_from_slice(&offset.to_be_bytes());
+        }
+        node
+    }
+
+    /// The header node of a B-tree whose first leaf is node 1.
+    fn header_node() -> Vec<u8> {
+        let mut record = vec![0; 106];
+        record[10..14].copy_from_slice(&1u32.to_be_bytes());
+        record[18..20].copy_from_slice(&(BLOCK as u16).to_be_bytes());
+        node(HEADER_NODE, 0, &[record])
+    }
+
+    /// An HFS catalog record: the key, padded to a word, then the data.
+    fn catalog_record(parent: u32, name: &[u8], mut data: Vec<u8>) -> Vec<u8> {
+        let mut record = vec![6 + name.len() as u8, 0];
+        record.extend(parent.to_be_bytes());
+        record.push(name.len() as u8);
+        record.extend(name);
+        if record.len() % 2 == 1 {
+            record.push(0);
+        }
+        record.append(&mut data);
+        record
+    }
+
+    /// The catalog of a volume named `Disk` holding one 5-byte text file.
+    fn catalog_leaf() -> Vec<Vec<u8>> {
+        let mut folder = vec![0; 70];
+        folder[0] = FOLDER_RECORD as u8;
+        folder[6..10].copy_from_slice(&2u32.to_be_bytes());
+        let mut file = vec![0; 102];
+        file[0] = FILE_RECORD as u8;
+        file[4..8].copy_from_slice(b"TEXT");
+        file[8..12].copy_from_slice(b"ttxt");
+        file[20..24].copy_from_slice(&16u32.to_be_bytes());
+        file[26..30].copy_from_slice(&5u32.to_be_bytes());
+        file[74..76].copy_from_slice(&(DATA_BLOCK as u16).to_be_bytes());
+        file[76..78].copy_from_slice(&1u16.to_be_bytes());
+        vec![
+            catalog_record(ROOT_PARENT_ID, b"Disk", folder),
+            catalog_record(2, b"Read Me", file),
+        ]
+    }
+
+    /// A cooked image of an HFS volume with `catalog` as its catalog file,
+    /// allocation blocks the size of a sector from the start of the disc.
+    fn hfs_image(catalog: &[Vec<u8>]) -> Vec<u8> {
+        let mut image = vec![0; 2048 * 18];
+        let header = &mut image[HEADER_OFFSET as usize..];
+        header[..2].copy_from_slice(HFS_SIGNATURE);
+        header[20..24].copy_from_slice(&(BLOCK as u32).to_be_bytes());
+        header[146..150].copy_from_slice(&((catalog.len() * BLOCK) as u32).to_be_bytes());
+        header[150..152].copy_from_slice(&(CATALOG_BLOCK as u16).to_be_bytes());
+        header[152..154].copy_from_slice(&(catalog.len() as u16).to_be_bytes());
+        for (index, node) in catalog.iter().enumerate() {
+            let start = (CATALOG_BLOCK + index) * BLOCK;
+            image[start..start + BLOCK].copy_from_slice(node);
+        }
+        image[DATA_BLOCK * BLOCK..DATA_BLOCK * BLOCK + 5].copy_from_slice(b"hello");
+        image
+    }
+
+    fn open_image(dir: &Path, image: &[u8]) -> DiscImage {
+        let path = dir.join("disc.iso");
+        fs::write(&path, image).unwrap();
+        DiscImage::open(&path).unwrap()
+    }
+
+    /// Lists the files of the volume in `image`.
+    fn list_files(dir: &Path, image: &[u8]) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let mut image = open_image(dir, image);
+        let mut volume = HfsVolume::open(&mut image)?;
+        let files = volume.files()?;
+        files
+            .iter()
+            .map(|file| Ok((file.export_path(), volume.read_data_fork(file)?)))
+            .collect()
+    }
+
+    #[test]
+    fn reads_files_from_the_catalog() {
+        let dir = test_dir("hfs_catalog");
+        let image = hfs_image(&[header_node(), node(LEAF_NODE, 0, &catalog_leaf())]);
+        let files = list_files(&dir, &image).unwrap();
+        assert_eq!(files, [(PathBuf::from("Read Me"), b"hello".to_vec())]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_catalogs() {
+        let dir = test_dir("hfs_malformed");
+        let leaf = node(LEAF_NODE, 0, &catalog_leaf());
+        // A leaf where the header node should be
+        let image = hfs_image(&[leaf.clone(), leaf.clone()]);
+        let error = list_files(&dir, &image).unwrap_err();
+        assert!(error.to_string().contains("header node"));
+
+        // A record running past the offset table
+        let mut bad_offset = leaf.clone();
+        bad_offset[BLOCK - 6..BLOCK - 4].copy_from_slice(&(BLOCK as u16).to_be_bytes());
+        let image = hfs_image(&[header_node(), bad_offset]);
+        let error = list_files(&dir, &image).unwrap_err();
+        assert!(error.to_string().contains("outside its node"));
+
+        // More records than the node has room for offsets
+        let mut bad_count = leaf.clone();
+        bad_count[10..12].copy_from_slice(&u16::MAX.to_be_bytes());
+        let image = hfs_image(&[header_node(), bad_count]);
+        let error = list_files(&dir, &image).unwrap_err();
+        assert!(error.to_string().contains("more records"));
+
+        // A leaf linking back to itself
+        let looping = node(LEAF_NODE, 1, &catalog_leaf());
+        let image = hfs_image(&[header_node(), looping]);
+        let error = list_files(&dir, &image).unwrap_err();
+        assert!(error.to_string().contains("loops"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn finds_volumes_in_partition_maps() {
+        let dir = test_dir("hfs_partitions");
+        let entry = |count: u32, start: u32, partition_type: &[u8]| {
+            let mut entry = vec![0; PARTITION_MAP_BLOCK as usize];
+            entry[..2].copy_from_slice(b"PM");
+            entry[4..8].copy_from_slice(&count.to_be_bytes());
+            entry[8..12].copy_from_slice(&start.to_be_bytes());
+            entry[48..48 + partition_type.len()].copy_from_slice(partition_type);
+            entry
+        };
+        let mut image = b"ER".to_vec();
+        image.resize(PARTITION_MAP_BLOCK as usize, 0);
+        image.extend(entry(2, 1, b"Apple_partition_map"));
+        image.extend(entry(2, 64, HFS_PARTITION));
+        image.resize(2048 * 18, 0);
+        image[16 * 2048 + 1024..16 * 2048 + 1026].copy_from_slice(HFS_SIGNATURE);
+        let mut disc = open_image(&dir, &image);
+        assert_eq!(find_volume(&mut disc).unwrap(), Some(64 * 512));
+
+        // A map claiming more entries than the image has blocks, all of
+        // them for other partitions
+        let mut image = b"ER".to_vec();
+        image.resize(2048 * 18, 0);
+        for block in image.chunks_mut(PARTITION_MAP_BLOCK as usize).skip(1) {
+            block.copy_from_slice(&entry(u32::MAX, 0, b"Apple_Free"));
+        }
+        let mut disc = open_image(&dir, &image);
+        assert_eq!(find_volume(&mut disc).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
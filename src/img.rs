@@ -12,19 +12,18 @@ use image::imageops::{resize, FilterType};
 use image::{DynamicImage, GenericImageView, ImageBuffer, ImageFormat, Rgba, RgbaImage};
 use std::path::Path;
 
-const IMAGENET_PARAMS: &'static [u8] = include_bytes!("imagenet.rsr");
+const IMAGENET_PARAMS: &[u8] = include_bytes!("imagenet.rsr");
+/// How much images are enlarged when upscaling is enabled.
+pub const UPSCALE_FACTOR: u32 = 3;
 
 pub fn process_image(
-    input: &Path,
+    img: DynamicImage,
     output: &Path,
     compress: bool,
     upscale: bool,
     transparent_color: [u8; 3],
     handle_transparency: bool,
 ) -> Result<ImageFormat> {
-    let img =
-        image::open(input).with_context(|| format!("Failed to open input image: {:?}", input))?;
-
     // Case 1: No upscale, no compression (original BMP)
     if !upscale && !compress {
         img.save_with_format(output, ImageFormat::Bmp)
@@ -40,7 +39,7 @@ pub fn process_image(
     }
 
     // For cases 3 and 4, we need to upscale
-    let factor = 3; // Hardcoded factor
+    let factor = UPSCALE_FACTOR;
     if handle_transparency {
         // Existing processing that detects the transparent background:
         let img2 = img.clone();
@@ -0,0 +1,477 @@
+//! Reads CD images without mounting them: plain `.iso` files and `.bin`
+//! images described by a `.cue` sheet.
+//!
+//! The Joliet tree is used when the disc has one, since it keeps the long
+//! and non-ASCII names (`Jönssonligan`) that the plain ISO 9660 tree
+//! shortens to `JONSSONL`. Hybrid discs also carry an HFS volume for the
+//! Mac, which [`hfs`] reads through the same image.
+
+use crate::hfs;
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const SECTOR_SIZE: u64 = 2048;
+/// Sector holding the first volume descriptor.
+const FIRST_DESCRIPTOR: u64 = 16;
+/// How many volume descriptors to look through for the terminator. Real
+/// discs have a handful; a damaged image may have none.
+const MAX_DESCRIPTORS: u64 = 64;
+const PRIMARY_DESCRIPTOR: u8 = 1;
+const SUPPLEMENTARY_DESCRIPTOR: u8 = 2;
+const DESCRIPTOR_END: u8 = 255;
+/// Escape sequences marking a supplementary descriptor as Joliet, for its
+/// three UCS-2 levels.
+const JOLIET_ESCAPES: [&[u8]; 3] = [b"%/@", b"%/C", b"%/E"];
+const FLAG_DIRECTORY: u8 = 0x02;
+/// Length of a directory record before its name.
+const RECORD_HEADER_LEN: usize = 33;
+
+pub fn is_disc_image(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|extension| {
+            ["iso", "bin", "cue"]
+                .iter()
+                .any(|image| extension.eq_ignore_ascii_case(image))
+        })
+}
+
+/// A file in the image, with its path from the root of the disc.
+pub struct DiscFile {
+    pub path: PathBuf,
+    sector: u32,
+    len: u32,
+}
+
+/// Where the data track's sectors are in the image file.
+#[derive(Clone, Copy)]
+struct Layout {
+    /// Bytes per sector in the image file: 2048 for cooked images, 2352
+    /// for raw ones.
+    sector_size: u64,
+    /// Where the 2048 bytes of user data start within a raw sector.
+    data_offset: u64,
+    /// Offset of the data track's first sector in the image file.
+    track_start: u64,
+}
+
+pub struct DiscImage {
+    file: File,
+    layout: Layout,
+    joliet: bool,
+    /// Root directory of the ISO 9660 tree, if the image has one; Mac-only
+    /// discs just have an HFS volume.
+    root: Option<(u32, u32)>,
+}
+
+impl DiscImage {
+    pub fn open(path: &Path) -> Result<DiscImage> {
+        let is_cue = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("cue"));
+        let (image_path, layout) = if is_cue {
+            let (image_path, layout) = read_cue(path)?;
+            (image_path, Some(layout))
+        } else {
+            (path.to_path_buf(), None)
+        };
+        let mut file = File::open(&image_path)
+            .with_context(|| format!("Failed to open disc image: {:?}", image_path))?;
+        let layout = match layout {
+            Some(layout) => layout,
+            None => detect_layout(&mut file)?,
+        };
+        let mut image = DiscImage {
+            file,
+            layout,
+            joliet: false,
+            root: None,
+        };
+        image.read_descriptors()?;
+        Ok(image)
+    }
+
+    fn read_sectors(&mut self, sector: u32, len: u32) -> Result<Vec<u8>> {
+        self.read_at(sector as u64 * SECTOR_SIZE, len as usize)
+    }
+
+    /// Reads `len` bytes of the data track, starting `offset` bytes into
+    /// its user data.
+    pub fn read_at(&mut self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        // Lengths come from the image, so check them before allocating
+        let Layout {
+            sector_size,
+            data_offset,
+            track_start,
+        } = self.layout;
+        let sectors = (offset + len as u64).div_ceil(SECTOR_SIZE);
+        let end = track_start + sectors.saturating_sub(1) * sector_size + data_offset + SECTOR_SIZE;
+        if len > 0 && end > self.file.metadata()?.len() {
+            bail!("Disc image is truncated");
+        }
+        let mut data = Vec::with_capacity(len);
+        let mut buffer = [0; SECTOR_SIZE as usize];
+        let mut sector = offset / SECTOR_SIZE;
+        let mut skip = (offset % SECTOR_SIZE) as usize;
+        while data.len() < len {
+            let offset = track_start + sector * sector_size + data_offset;
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file
+                .read_exact(&mut buffer)
+                .context("Disc image is truncated")?;
+            let wanted = (len - data.len()).min(buffer.len() - skip);
+            data.extend_from_slice(&buffer[skip..skip + wanted]);
+            skip = 0;
+            sector += 1;
+        }
+        Ok(data)
+    }
+
+    /// The number of bytes of user data in the data track.
+    pub fn data_len(&self) -> Result<u64> {
+        let Layout {
+            sector_size,
+            track_start,
+            ..
+        } = self.layout;
+        let sectors = self.file.metadata()?.len().saturating_sub(track_start) / sector_size;
+        Ok(sectors * SECTOR_SIZE)
+    }
+
+    /// Finds the root directory, preferring the Joliet tree.
+    fn read_descriptors(&mut self) -> Result<()> {
+        let mut primary = None;
+        for sector in FIRST_DESCRIPTOR..FIRST_DESCRIPTOR + MAX_DESCRIPTORS {
+            let descriptor = self.read_sectors(sector as u32, SECTOR_SIZE as u32)?;
+            if &descriptor[1..6] != b"CD001" {
+                break;
+            }
+            // The root directory record sits at 156 in both descriptors
+            let root = (le_u32(&descriptor[158..]), le_u32(&descriptor[166..]));
+            match descriptor[0] {
+                PRIMARY_DESCRIPTOR => primary = Some(root),
+                SUPPLEMENTARY_DESCRIPTOR if JOLIET_ESCAPES.contains(&&descriptor[88..91]) => {
+                    self.joliet = true;
+                    self.root = Some(root);
+                    return Ok(());
+                }
+                DESCRIPTOR_END => break,
+                _ => {}
+            }
+        }
+        self.root = primary;
+        Ok(())
+    }
+
+    /// Lists every file in the ISO 9660 tree.
+    pub fn files(&mut self) -> Result<Vec<DiscFile>> {
+        let root = self
+            .root
+            .context("Disc image has no ISO 9660 file system, only a Mac one")?;
+        let mut files = Vec::new();
+        let mut pending = vec![(PathBuf::new(), root)];
+        // A damaged directory pointing back up the tree is only read once
+        let mut seen = HashSet::new();
+        while let Some((dir, (sector, len))) = pending.pop() {
+            if !seen.insert(sector) {
+                continue;
+            }
+            let data = self.read_sectors(sector, len)?;
+            let mut position = 0;
+            while position < data.len() {
+                let record_len = data[position] as usize;
+                if record_len == 0 {
+                    // Records don't cross sectors; the rest of this one is
+                    // padding
+                    position = (position / SECTOR_SIZE as usize + 1) * SECTOR_SIZE as usize;
+                    continue;
+                }
+                let Some(record) = data.get(position..position + record_len) else {
+                    break;
+                };
+                position += record_len;
+                // A damaged record too short for its name is skipped
+                let Some(&name_len) = record.get(RECORD_HEADER_LEN - 1) else {
+                    continue;
+                };
+                let Some(raw_name) =
+                    record.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + name_len as usize)
+                else {
+                    continue;
+                };
+                // The first two records are the directory itself and its
+                // parent
+                if raw_name == [0] || raw_name == [1] {
+                    continue;
+                }
+                let name = self.decode_name(raw_name);
+                // Never let a damaged or crafted name escape the tree
+                if name.is_empty() || name == ".." || name.contains(['/', '\\']) {
+                    continue;
+                }
+                let path = dir.join(name);
+                let extent = (le_u32(&record[2..]), le_u32(&record[10..]));
+                if record[25] & FLAG_DIRECTORY != 0 {
+                    pending.push((path, extent));
+                } else {
+                    files.push(DiscFile {
+                        path,
+                        sector: extent.0,
+                        len: extent.1,
+                    });
+                }
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    fn decode_name(&self, raw: &[u8]) -> String {
+        let name = if self.joliet {
+            let units: Vec<u16> = raw
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        } else {
+            String::from_utf8_lossy(raw).into_owned()
+        };
+        // Drop the version number, and the dot of names without extension
+        let name = name.split(';').next().unwrap_or_default();
+        name.strip_suffix('.').unwrap_or(name).to_string()
+    }
+
+    pub fn read(&mut self, file: &DiscFile) -> Result<Vec<u8>> {
+        self.read_sectors(file.sector, file.len)
+    }
+
+    /// Writes every file in the ISO 9660 tree under `dest`, keeping the
+    /// tree.
+    pub fn extract(&mut self, dest: &Path) -> Result<()> {
+        for file in self.files()? {
+            let path = dest.join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let data = self.read(&file)?;
+            File::create(&path)
+                .and_then(|mut out| out.write_all(&data))
+                .with_context(|| format!("Failed to extract {:?} from disc image", file.path))?;
+        }
+        Ok(())
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Works out the sector layout of an image without a cue sheet by looking
+/// for the first volume descriptor where each layout would put it. Images
+/// of Mac-only discs have none, but are always cooked.
+fn detect_layout(file: &mut File) -> Result<Layout> {
+    // Cooked, raw mode 1 and raw mode 2 form 1
+    for (sector_size, data_offset) in [(SECTOR_SIZE, 0), (2352, 16), (2352, 24)] {
+        let mut magic = [0; 6];
+        file.seek(SeekFrom::Start(
+            FIRST_DESCRIPTOR * sector_size + data_offset,
+        ))?;
+        if file.read_exact(&mut magic).is_ok() && &magic[1..] == b"CD001" {
+            return Ok(Layout {
+                sector_size,
+                data_offset,
+                track_start: 0,
+            });
+        }
+    }
+    let mut start = [0; 1026];
+    file.seek(SeekFrom::Start(0))?;
+    if file.read_exact(&mut start).is_ok() && hfs::is_hfs_start(&start) {
+        return Ok(Layout {
+            sector_size: SECTOR_SIZE,
+            data_offset: 0,
+            track_start: 0,
+        });
+    }
+    bail!("Not an ISO 9660 or HFS image")
+}
+
+/// Reads a cue sheet, returning the image file of its first data track
+/// and that track's layout.
+fn read_cue(path: &Path) -> Result<(PathBuf, Layout)> {
+    let cue = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read cue sheet: {:?}", path))?;
+    let mut image = None;
+    let mut layout = None;
+    for line in cue.lines().map(str::trim) {
+        let mut words = line.split_whitespace();
+        match words.next().map(str::to_ascii_uppercase).as_deref() {
+            Some("FILE") if layout.is_none() => {
+                // The name is quoted and may contain spaces
+                let name = match line.split('"').nth(1) {
+                    Some(name) => name,
+                    None => words.next().unwrap_or_default(),
+                };
+                image = Some(path.with_file_name(name));
+            }
+            Some("TRACK") if layout.is_none() => {
+                layout = match words.nth(1).map(str::to_ascii_uppercase).as_deref() {
+                    Some("MODE1/2048") => Some((SECTOR_SIZE, 0)),
+                    Some("MODE1/2352") => Some((2352, 16)),
+                    Some("MODE2/2352") => Some((2352, 24)),
+                    Some("MODE2/2336") => Some((2336, 8)),
+                    // Audio tracks come after the data track on mixed discs
+                    _ => None,
+                };
+            }
+            Some("INDEX") if layout.is_some() && words.next() == Some("01") => {
+                let (sector_size, data_offset) = layout.unwrap();
+                let start = words.next().map(cue_frames).transpose()?.unwrap_or(0);
+                let image = image.context("Cue sheet has no FILE before its TRACK")?;
+                let layout = Layout {
+                    sector_size,
+                    data_offset,
+                    track_start: start * sector_size,
+                };
+                return Ok((image, layout));
+            }
+            _ => {}
+        }
+    }
+    bail!("No data track found in cue sheet {:?}", path)
+}
+
+/// Converts an `mm:ss:ff` cue time to sectors, at 75 per second.
+fn cue_frames(time: &str) -> Result<u64> {
+    let parts: Vec<u64> = time
+        .split(':')
+        .map(|part| part.parse().context("Invalid cue sheet time"))
+        .collect::<Result<_>>()?;
+    match parts[..] {
+        [minutes, seconds, frames] => Ok((minutes * 60 + seconds) * 75 + frames),
+        _ => bail!("Invalid cue sheet time: {}", time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A directory for one test's files, named after it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cgex_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(name: &[u8], sector: u32, len: u32, flags: u8) -> Vec<u8> {
+        let mut record = vec![0; RECORD_HEADER_LEN];
+        record[0] = (RECORD_HEADER_LEN + name.len()) as u8;
+        record[2..6].copy_from_slice(&sector.to_le_bytes());
+        record[10..14].copy_from_slice(&len.to_le_bytes());
+        record[25] = flags;
+        record[32] = name.len() as u8;
+        record.extend(name);
+        record
+    }
+
+    /// A cooked image with one file, `README.TXT`, and a directory that
+    /// points back at the root.
+    fn image() -> Vec<u8> {
+        let sector = SECTOR_SIZE as usize;
+        let mut image = vec![0; sector * 21];
+        let descriptor = &mut image[sector * 16..sector * 17];
+        descriptor[0] = PRIMARY_DESCRIPTOR;
+        descriptor[1..6].copy_from_slice(b"CD001");
+        descriptor[156..156 + 34].copy_from_slice(&record(&[0], 18, 2048, FLAG_DIRECTORY));
+        image[sector * 17] = DESCRIPTOR_END;
+        image[sector * 17 + 1..sector * 17 + 6].copy_from_slice(b"CD001");
+
+        // Too short for the name it claims, and for a name at all
+        let mut damaged = record(b"X", 20, 5, 0);
+        damaged[32] = 40;
+        let mut truncated = vec![0; 20];
+        truncated[0] = 20;
+        let records = [
+            record(&[0], 18, 2048, FLAG_DIRECTORY),
+            record(&[1], 18, 2048, FLAG_DIRECTORY),
+            record(b"README.TXT;1", 20, 5, 0),
+            record(b"LOOP", 18, 2048, FLAG_DIRECTORY),
+            damaged,
+            truncated,
+        ];
+        let directory = records.concat();
+        image[sector * 18..sector * 18 + directory.len()].copy_from_slice(&directory);
+        image[sector * 20..sector * 20 + 5].copy_from_slice(b"hello");
+        image
+    }
+
+    #[test]
+    fn lists_and_reads_files() {
+        let dir = test_dir("iso");
+        let path = dir.join("disc.iso");
+        fs::write(&path, image()).unwrap();
+        let mut disc = DiscImage::open(&path).unwrap();
+        let files = disc.files().unwrap();
+        // LOOP leads back to the root, which isn't listed again
+        let paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("README.TXT")]);
+        assert_eq!(disc.read(&files[0]).unwrap(), b"hello");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_reads_past_the_end() {
+        let dir = test_dir("iso_truncated");
+        let path = dir.join("disc.iso");
+        let mut image = image();
+        image.truncate(SECTOR_SIZE as usize * 20);
+        fs::write(&path, image).unwrap();
+        let mut disc = DiscImage::open(&path).unwrap();
+        let files = disc.files().unwrap();
+        assert!(disc.read(&files[0]).is_err());
+        assert!(disc.read_at(0, usize::MAX / 2).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn converts_cue_times() {
+        assert_eq!(cue_frames("00:00:00").unwrap(), 0);
+        assert_eq!(cue_frames("01:02:03").unwrap(), (60 + 2) * 75 + 3);
+        assert!(cue_frames("01:02").is_err());
+        assert!(cue_frames("aa:00:00").is_err());
+    }
+
+    #[test]
+    fn reads_the_data_track_of_a_cue_sheet() {
+        let dir = test_dir("cue");
+        let cue = dir.join("disc.cue");
+        fs::write(
+            &cue,
+            concat!(
+                "FILE \"Mitt spel.bin\" BINARY\r\n",
+                "  TRACK 01 MODE1/2352\r\n",
+                "    INDEX 00 00:00:00\r\n",
+                "    INDEX 01 00:02:00\r\n",
+                "  TRACK 02 AUDIO\r\n",
+                "    INDEX 01 10:00:00\r\n",
+            ),
+        )
+        .unwrap();
+        let (image, layout) = read_cue(&cue).unwrap();
+        assert_eq!(image, dir.join("Mitt spel.bin"));
+        assert_eq!((layout.sector_size, layout.data_offset), (2352, 16));
+        assert_eq!(layout.track_start, 150 * 2352);
+
+        fs::write(
+            &cue,
+            "FILE \"a.bin\" BINARY\n  TRACK 01 AUDIO\n    INDEX 01 00:00:00\n",
+        )
+        .unwrap();
+        assert!(read_cue(&cue).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,35 @@
+//! Extracts the assets of Director games (Jönssonligan, Mulle Meck) from
+//! their discs: bitmaps upscaled and with transparent backgrounds, sounds,
+//! text, scripts, scores and more, in a folder per movie and cast.
+//!
+//! [`ExtractionPipeline`] runs a whole extraction, the way the `cgex`
+//! command line tool does, exporting the movies through one of the
+//! [`backend`]s. The pieces it is made of are public for tools
+//! that only need some of them: [`detect_game`] with the release
+//! [`fingerprint`]s and the [`GameProfile`]s games are described by,
+//! [`process_image`], the disc image readers in [`iso`] and [`hfs`], and
+//! the Director file parser in [`director`].
+
+mod anim;
+pub mod backend;
+pub mod director;
+pub mod fingerprint;
+pub mod game_extractor;
+pub mod hfs;
+pub mod img;
+pub mod iso;
+pub mod manifest;
+pub mod native;
+mod network;
+pub mod pipeline;
+pub mod profile;
+pub mod report;
+pub mod resource_fork;
+pub mod watchdog;
+
+pub use game_extractor::GameExtractor;
+pub use img::process_image;
+pub use pipeline::{
+    detect_game, known_games, ExtractionOptions, ExtractionPipeline, Platform, Progress,
+};
+pub use profile::GameProfile;
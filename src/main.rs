@@ -1,32 +1,31 @@
-use anyhow::{bail, Context, Result};
-use clap::Parser;
-use data_encoding::HEXUPPER;
-use image::ImageFormat;
-use rayon::prelude::*;
-use ring::digest::{Context as DigestContext, SHA256};
-use std::collections::HashSet;
-use std::env;
-use std::fs::{self, File};
-use std::io::Read;
+use anyhow::{Context, Result};
+use cgex::backend::{
+    self, ExtractorBackend, NativeBackend, ReplayBackend, WindowsBackend, WineBackend,
+};
+use cgex::{fingerprint, pipeline, profile};
+use cgex::{ExtractionOptions, ExtractionPipeline, Platform};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::Path;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
-mod game_extractor;
-mod img;
-mod network;
-
-use game_extractor::{GameExtractor, JonssonDjupet, JonssonMjolner, MulleBat, MulleBil};
-use img::process_image;
+/// Where dir_extractor.exe and its Xtras are.
+const EXTRACTOR_TOOLS: &str = "extractor_tools";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Input directory containing the disc contents
-    #[arg(short, long, default_value = "disc_contents")]
+    /// Input directory containing the disc contents, or a disc image (.iso, or .bin/.cue)
+    #[arg(short, long, visible_alias = "input", default_value = "disc_contents")]
     input_dir: String,
 
+    /// Extra game profile (.toml) to detect the game from, can be given more than once
+    #[arg(long = "profile")]
+    profiles: Vec<String>,
+
+    /// Which file system of a hybrid disc image to read the movies from
+    #[arg(long, value_enum, default_value_t = Platform::Windows)]
+    platform: Platform,
+
     /// Output directory for processed files
     #[arg(short, long, default_value = "output")]
     output_dir: String,
@@ -42,353 +41,158 @@ struct Args {
     /// Do not handle transparent background; leave background colors intact
     #[arg(long)]
     no_transparent_background: bool,
-}
-
-pub fn detect_game(input_dir: &Path) -> Result<Box<dyn GameExtractor>> {
-    let dir_files = find_dir_files(input_dir)?;
-    let found_files: HashSet<String> = dir_files
-        .iter()
-        .filter_map(|path| path.file_name())
-        .filter_map(|name| name.to_str())
-        .map(|s| s.to_lowercase())
-        .collect();
 
-    let games: Vec<Box<dyn GameExtractor>> = vec![
-        Box::new(JonssonMjolner),
-        Box::new(JonssonDjupet),
-        Box::new(MulleBil),
-        Box::new(MulleBat),
-    ];
+    /// How to extract the movies (default: wine, or windows on Windows)
+    #[arg(long, value_enum, conflicts_with = "native")]
+    backend: Option<Backend>,
 
-    let mut best_match: Option<(usize, Box<dyn GameExtractor>)> = None;
-
-    for game in games {
-        let expected_files = game.get_expected_files();
-        let match_count = expected_files.intersection(&found_files).count();
-
-        if match_count > 0 {
-            if let Some((best_count, _)) = best_match {
-                if match_count > best_count {
-                    best_match = Some((match_count, game));
-                }
-            } else {
-                best_match = Some((match_count, game));
-            }
-        }
-    }
+    /// Same as --backend native: parse the movies without dir_extractor.exe (no Wine needed)
+    #[arg(long)]
+    native: bool,
 
-    if let Some((match_count, game)) = best_match {
-        let expected_count = game.get_expected_files().len();
-        if match_count < expected_count {
-            println!(
-                "Warning: Only found {} out of {} expected files for {}. Proceeding anyway.",
-                match_count,
-                expected_count,
-                game.get_name()
-            );
-        }
-        Ok(game)
-    } else {
-        bail!("Unable to detect game type. No matching .dir files found.")
-    }
-}
+    /// How many movies to extract at once with the wine backend, each in its own Wine prefix and Xvfb display
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
 
-fn find_dir_files(dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut dir_files = Vec::new();
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                dir_files.extend(find_dir_files(&path)?);
-            } else if path.extension().map_or(false, |ext| {
-                ext.eq_ignore_ascii_case("dir") || ext.eq_ignore_ascii_case("dxr")
-            }) {
-                dir_files.push(path);
-            }
-        }
-    }
-    Ok(dir_files)
-}
+    /// Seconds a movie may take under Wine before the extractor is killed
+    #[arg(long, default_value_t = backend::DEFAULT_MOVIE_TIMEOUT.as_secs())]
+    movie_timeout: u64,
 
-#[cfg(not(target_os = "windows"))]
-fn check_wine_installation() -> Result<()> {
-    let output = Command::new("wine")
-        .arg("--version")
-        .output()
-        .context("Failed to execute 'wine --version'. Is Wine installed and in your PATH?")?;
+    /// Save the temp directory here once the movies are extracted, or with --backend replay, read it back
+    #[arg(long)]
+    snapshot: Option<String>,
 
-    if !output.status.success() {
-        bail!("Wine is not properly installed or configured. Please install Wine and ensure it's in your PATH.");
-    }
+    /// With --native, write Shockwave Audio as the raw .mp3 stream instead of decoding it to WAV
+    #[arg(long)]
+    raw_mp3: bool,
 
-    Ok(())
+    #[command(subcommand)]
+    mode: Option<Mode>,
 }
 
-fn extract_files(temp_dir: &Path, game: &Box<dyn GameExtractor>) -> Result<()> {
-    let files = find_files(temp_dir, &[".dir", ".dxr"])
-        .context("Failed to find .dir or .dxr files. Make sure the input directory is correct and contains these files.")?;
-
-    if files.is_empty() {
-        bail!("No .dir or .dxr files found in the input directory. Please check your input path.");
-    }
-
-    let total = files.len();
-    for (i, file) in files.iter().enumerate() {
-        let file_name = file.file_name().to_string_lossy().into_owned();
-        println!(
-            "Extracting assets from: {:?} ({}/{})",
-            file_name,
-            i + 1,
-            total
-        );
-        game.run_extractor(temp_dir, &file_name)
-            .context(format!("Failed to extract assets from: {:?}", file_name))?;
-    }
-    Ok(())
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Backend {
+    /// dir_extractor.exe under Wine
+    Wine,
+    /// dir_extractor.exe run directly
+    Windows,
+    /// cgex's own Director parser
+    Native,
+    /// The exported files of an earlier run's --snapshot
+    Replay,
 }
 
-fn hash_file(path: &Path) -> Result<String> {
-    let mut file = File::open(path).context("Failed to open file for hashing")?;
-    let mut context = DigestContext::new(&SHA256);
-    let mut buffer = [0; 8192];
-
-    loop {
-        let count = file.read(&mut buffer).context("Failed to read file")?;
-        if count == 0 {
-            break;
-        }
-        context.update(&buffer[..count]);
-    }
-
-    let digest = context.finish();
-    Ok(HEXUPPER.encode(digest.as_ref()))
+#[derive(Subcommand, Debug)]
+enum Mode {
+    /// Write out the movies and Xtras bundled in a Windows projector (.exe)
+    UnpackProjector {
+        /// The projector executable
+        projector: String,
+
+        /// Directory to write the movies to, to use as --input-dir afterwards
+        #[arg(short, long, default_value = "disc_contents")]
+        output_dir: String,
+    },
+    /// Print the SHA-256 of the input's movies as an edition to add to a game profile
+    Fingerprint,
 }
 
-fn remove_duplicates(path: &Path) -> Result<()> {
-    let mut set = HashSet::new();
-    let mut files: Vec<PathBuf> = fs::read_dir(path)
-        .context("Failed to read output directory")?
-        .filter_map(|entry| entry.ok())
-        .map(|entry| entry.path())
-        .filter(|path| path.is_file())
-        .collect();
-
-    files.sort();
-
-    for path in files {
-        let hash = hash_file(&path)?;
-        if set.contains(&hash) {
-            fs::remove_file(&path).context("Failed to remove duplicate file")?;
-        } else {
-            set.insert(hash);
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if let Some(Mode::UnpackProjector {
+        projector,
+        output_dir,
+    }) = &args.mode
+    {
+        return pipeline::unpack_projector(Path::new(projector), Path::new(output_dir));
+    }
+    if let Some(Mode::Fingerprint) = args.mode {
+        let movies = pipeline::fingerprint_movies(Path::new(&args.input_dir), args.platform)?;
+        print!("{}", fingerprint::edition_template(&movies));
+        return Ok(());
+    }
+    let mut pipeline = ExtractionPipeline::new(&args.input_dir, &args.output_dir);
+    let (backend, extractor_backend) = select_backend(&args)?;
+    pipeline = pipeline.backend(extractor_backend);
+    if let Some(snapshot) = &args.snapshot {
+        if !matches!(backend, Backend::Replay) {
+            pipeline = pipeline.snapshot(snapshot);
         }
     }
-    Ok(())
-}
-
-fn find_files(dir: &Path, extensions: &[&str]) -> Result<Vec<fs::DirEntry>> {
-    let mut files: Vec<fs::DirEntry> = fs::read_dir(dir)
-        .context("Failed to read directory")?
-        .filter_map(|res| res.ok())
-        .filter(|entry| {
-            entry
-                .path()
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .map_or(false, |ext| {
-                    extensions.iter().any(|&valid_ext| {
-                        ext.eq_ignore_ascii_case(valid_ext.trim_start_matches('.'))
-                    })
-                })
+    for path in &args.profiles {
+        pipeline = pipeline.profile(profile::load_profile(Path::new(path))?);
+    }
+    pipeline
+        .options(ExtractionOptions {
+            compression: args.compression,
+            upscale: !args.no_upscale,
+            transparent_background: !args.no_transparent_background,
+            raw_mp3: args.raw_mp3,
+            platform: args.platform,
+            jobs: args.jobs,
         })
-        .collect();
-
-    files.sort_by_key(|dir| dir.path());
-    Ok(files)
+        .on_progress(|progress| println!("{}", progress))
+        .run()
 }
 
-fn move_file_to_output(src_path: &Path, output_dir: &Path, extension: Option<&str>) -> Result<()> {
-    let file_name = src_path
-        .file_name()
-        .and_then(|os_str| os_str.to_str())
-        .context("Invalid file name")?;
-
-    let parts: Vec<&str> = file_name.split("--").collect();
-    let mut dst_path = output_dir.to_path_buf();
-
-    if parts.len() > 1 {
-        dst_path.extend(&parts[..parts.len() - 1]);
-
-        let file_parts: Vec<&str> = parts.last().unwrap().split("__").collect();
-        if file_parts.len() > 1 {
-            dst_path.push(&file_parts[0]);
-            let mut final_name = file_parts[1..].join("__");
-            if final_name.starts_with('-') {
-                final_name = final_name[1..].to_string();
-            }
-            dst_path.push(final_name);
-        } else {
-            dst_path.push(parts.last().unwrap());
+/// The backend the arguments ask for: `--native`, then `--backend`, then
+/// the platform's default.
+fn select_backend(args: &Args) -> Result<(Backend, Box<dyn ExtractorBackend>)> {
+    let backend = match args.backend {
+        _ if args.native => Backend::Native,
+        Some(backend) => backend,
+        None if cfg!(target_os = "windows") => Backend::Windows,
+        None => Backend::Wine,
+    };
+    let extractor_backend: Box<dyn ExtractorBackend> = match backend {
+        Backend::Wine => Box::new(
+            WineBackend::new(EXTRACTOR_TOOLS)
+                .movie_timeout(Duration::from_secs(args.movie_timeout)),
+        ),
+        Backend::Windows => Box::new(WindowsBackend::new(EXTRACTOR_TOOLS)),
+        Backend::Native => Box::new(NativeBackend),
+        Backend::Replay => {
+            let snapshot = args
+                .snapshot
+                .as_deref()
+                .context("--backend replay needs a --snapshot to read")?;
+            Box::new(ReplayBackend::new(snapshot))
         }
-    } else {
-        dst_path.push(file_name);
-    }
-
-    if let Some(ext) = extension {
-        dst_path.set_extension(ext);
-    }
-
-    fs::create_dir_all(dst_path.parent().unwrap())?;
-    fs::rename(src_path, &dst_path)
-        .or_else(|_| fs::copy(src_path, &dst_path).map(|_| ()))
-        .with_context(|| format!("Failed to move file: {:?}", src_path))?;
-
-    Ok(())
+    };
+    Ok((backend, extractor_backend))
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let input_dir = Path::new(&args.input_dir);
-    let output_dir = Path::new(&args.output_dir);
-    let extractor_tools_dir = Path::new("extractor_tools");
-
-    let game = detect_game(&input_dir)?;
-
-    println!("Found {} assets. Starting extraction.", game.get_name());
-
-    if !input_dir.exists() {
-        bail!(
-            "Input directory '{}' does not exist. Please check your input path.",
-            input_dir.display()
-        );
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    check_wine_installation()?;
-
-    let temp_dir = env::temp_dir().join(format!("cgex_{}", std::process::id()));
-    fs::create_dir_all(&temp_dir).context("Failed to create temporary directory")?;
-
-    // Copy the entire input directory to temp
-    game_extractor::copy_directory(&input_dir, &temp_dir)
-        .context("Failed to copy input directory to temp")?;
-
-    fs::copy(
-        extractor_tools_dir.join("dir_extractor.exe"),
-        temp_dir.join("dir_extractor.exe"),
-    )
-    .context("Failed to copy dir_extractor.exe")?;
-
-    let xtras_src = extractor_tools_dir.join("Xtras");
-    let xtras_dst = temp_dir.join("Xtras");
-    game_extractor::copy_directory(&xtras_src, &xtras_dst)
-        .context("Failed to copy Xtras folder")?;
-
-    // Prepare the temp directory based on the specific game requirements
-    game.prepare_temp_directory(&temp_dir)?;
-
-    extract_files(&temp_dir, &game).context("Failed to extract files")?;
-
-    println!("Removing duplicates. This might take a while...");
-    if let Err(e) = remove_duplicates(&temp_dir) {
-        println!("Warning: Failed to remove duplicate files: {}", e);
-        println!("Continuing with processing...");
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let broken_images = game.get_broken_images();
-    for file in &broken_images {
-        let path = temp_dir.join(file);
-        if let Err(e) = fs::remove_file(&path) {
-            println!("Warning: Failed to remove file {:?}: {}", path, e);
-        }
+    fn backend_name(args: &[&str]) -> Result<String> {
+        let args = Args::try_parse_from([&["cgex"], args].concat())?;
+        let (_, backend) = select_backend(&args)?;
+        Ok(backend.name().to_string())
     }
 
-    println!(
-        "Processing images{}{}. This might take a while...",
-        if args.no_upscale {
-            ""
-        } else {
-            " with AI-upscaling"
-        },
-        if args.compression {
-            " and compression"
+    #[test]
+    fn selects_backends() {
+        let default = if cfg!(target_os = "windows") {
+            "windows"
         } else {
-            ""
-        }
-    );
-
-    let bmp_files =
-        find_files(&temp_dir, &[".bmp"]).context("Failed to find BMP files for processing")?;
-    let total = bmp_files.len();
-    let counter = AtomicUsize::new(1);
-
-    let processed_files: Vec<Result<(PathBuf, ImageFormat)>> = bmp_files
-        .into_par_iter()
-        .map(|entry| -> Result<(PathBuf, ImageFormat)> {
-            let current = counter.fetch_add(1, Ordering::SeqCst);
-            let file_n = entry.file_name();
-            let file_name = file_n.to_string_lossy();
-            println!("Processing: {:?} ({}/{})", file_name, current, total);
-
-            let input_path = entry.path();
-            let output_path = temp_dir.join(input_path.file_name().unwrap());
-            process_image(
-                &input_path,
-                &output_path,
-                args.compression,
-                !args.no_upscale,
-                game.get_transparent_color(),
-                !args.no_transparent_background,
-            )
-            .map(|format| (output_path, format))
-            .with_context(|| format!("Failed to process image: {:?}", input_path))
-        })
-        .collect();
-
-    // Handle successful and failed image processing
-    let (successful, failed): (Vec<_>, Vec<_>) =
-        processed_files.into_iter().partition(Result::is_ok);
-
-    let successful: Vec<(PathBuf, ImageFormat)> =
-        successful.into_iter().map(Result::unwrap).collect();
-
-    // Report failed images
-    for error in failed {
-        if let Err(e) = error {
-            eprintln!("Error processing image: {}", e);
-        }
-    }
-
-    game.post_extraction_setup(&temp_dir, &successful)?;
-
-    println!("Moving files into final directory structure");
-    fs::create_dir_all(output_dir).context("Failed to create output directory")?;
-
-    for (temp_path, format) in successful {
-        let extension = format.extensions_str()[0];
-        move_file_to_output(&temp_path, output_dir, Some(extension))
-            .with_context(|| format!("Failed to move processed file: {:?}", temp_path))?;
-    }
-
-    let wav_files =
-        find_files(&temp_dir, &[".wav"]).context("Failed to find WAV files for moving")?;
-    for file in wav_files {
-        let src_path = file.path();
-        move_file_to_output(&src_path, output_dir, None)
-            .context(format!("Failed to move WAV file: {:?}", src_path))?;
+            "wine"
+        };
+        assert_eq!(backend_name(&[]).unwrap(), default);
+        assert_eq!(backend_name(&["--native"]).unwrap(), "native");
+        assert_eq!(backend_name(&["--backend", "native"]).unwrap(), "native");
+        assert_eq!(backend_name(&["--backend", "windows"]).unwrap(), "windows");
+        assert_eq!(
+            backend_name(&["--backend", "replay", "--snapshot", "snap"]).unwrap(),
+            "replay"
+        );
     }
 
-    let txt_files =
-        find_files(&temp_dir, &[".txt"]).context("Failed to find WAV files for moving")?;
-    for file in txt_files {
-        let src_path = file.path();
-        move_file_to_output(&src_path, output_dir, None)
-            .context(format!("Failed to move txt file: {:?}", src_path))?;
+    #[test]
+    fn rejects_conflicting_backend_options() {
+        assert!(backend_name(&["--backend", "replay"]).is_err());
+        assert!(backend_name(&["--native", "--backend", "wine"]).is_err());
+        assert!(backend_name(&["--backend", "dosbox"]).is_err());
     }
-    println!("Cleaning up temporary directory");
-    fs::remove_dir_all(&temp_dir).context("Failed to remove temporary directory")?;
-
-    println!("Processing complete!");
-    Ok(())
 }
@@ -0,0 +1,259 @@
+use crate::director::bitmap::PaletteRef;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One exported cast member. Bitmap fields are only set for bitmaps and are
+/// in the member's original pixels, before any upscaling. Film loops and
+/// shapes get a width and height too.
+#[derive(Serialize)]
+pub struct ManifestEntry {
+    pub movie: String,
+    pub cast: String,
+    pub member: u32,
+    pub name: String,
+    /// Lingo's type name, or the Xtra's for Xtra members.
+    #[serde(rename = "type")]
+    pub member_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PaletteRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_point: Option<[i16; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Family name of an embedded font.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// Files written to the temp directory, resolved to `outputs` once
+    /// they have been moved.
+    #[serde(skip)]
+    pub files: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// A text member using a font.
+#[derive(Serialize)]
+pub struct FontUse {
+    #[serde(skip)]
+    pub font: String,
+    pub movie: String,
+    pub cast: String,
+    pub member: u32,
+    pub name: String,
+}
+
+/// An external cast library linked by a movie.
+#[derive(Serialize)]
+pub struct CastLink {
+    pub movie: String,
+    pub cast: String,
+    /// The path stored in the movie, from the author's machine.
+    pub file_path: String,
+    /// The cast file that was found, relative to the directory the movies
+    /// are read from.
+    pub file: Option<String>,
+    /// Where its members were extracted, relative to the output directory.
+    pub folder: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FontIndexEntry<'a> {
+    font: &'a str,
+    /// Files of the font members embedding this font, if any.
+    outputs: Vec<&'a str>,
+    members: Vec<&'a FontUse>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    game: &'a str,
+    /// Factor the images were upscaled by, to scale registration points.
+    image_scale: u32,
+    members: &'a [ManifestEntry],
+    fonts: Vec<FontIndexEntry<'a>>,
+    external_casts: &'a [CastLink],
+}
+
+/// Fills in each entry's `outputs` from the temp files it wrote. `moved`
+/// maps temp file names to where they ended up; `duplicates` maps files
+/// removed as duplicates to the file that was kept instead.
+pub fn resolve_outputs(
+    entries: &mut [ManifestEntry],
+    output_dir: &Path,
+    moved: &HashMap<String, PathBuf>,
+    duplicates: &HashMap<String, String>,
+) {
+    for entry in entries {
+        entry.outputs = entry
+            .files
+            .iter()
+            .map(|file| duplicates.get(file).unwrap_or(file))
+            .filter_map(|file| moved.get(file))
+            .filter_map(|path| path.strip_prefix(output_dir).ok())
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect();
+    }
+}
+
+/// Writes `manifest.json` to the output directory, once `resolve_outputs`
+/// has filled in the entries.
+pub fn write_manifest(
+    output_dir: &Path,
+    game: &str,
+    image_scale: u32,
+    entries: &[ManifestEntry],
+    font_uses: &[FontUse],
+    external_casts: &[CastLink],
+) -> Result<()> {
+    let manifest = Manifest {
+        game,
+        image_scale,
+        members: entries,
+        fonts: font_index(entries, font_uses),
+        external_casts,
+    };
+    fs::write(
+        output_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .context("Failed to write manifest.json")
+}
+
+/// Groups font users and embedded fonts by font name, ignoring case since
+/// Director does.
+fn font_index<'a>(
+    entries: &'a [ManifestEntry],
+    font_uses: &'a [FontUse],
+) -> Vec<FontIndexEntry<'a>> {
+    fn entry<'a, 'b>(
+        index: &'b mut BTreeMap<String, FontIndexEntry<'a>>,
+        font: &'a str,
+    ) -> &'b mut FontIndexEntry<'a> {
+        index
+            .entry(font.to_lowercase())
+            .or_insert_with(|| FontIndexEntry {
+                font,
+                outputs: Vec::new(),
+                members: Vec::new(),
+            })
+    }
+
+    let mut index = BTreeMap::new();
+    for member in entries {
+        if let Some(font) = &member.font {
+            let outputs = member.outputs.iter().map(String::as_str);
+            entry(&mut index, font).outputs.extend(outputs);
+        }
+    }
+    for font_use in font_uses {
+        entry(&mut index, &font_use.font).members.push(font_use);
+    }
+    index.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn entry(member: u32, name: &str, files: &[&str]) -> ManifestEntry {
+        ManifestEntry {
+            movie: "intro".to_string(),
+            cast: "Internal".to_string(),
+            member,
+            name: name.to_string(),
+            member_type: "bitmap".to_string(),
+            bit_depth: Some(8),
+            palette: Some(PaletteRef::Member {
+                cast_lib: 1,
+                member: 2,
+            }),
+            registration_point: Some([10, -4]),
+            width: Some(32),
+            height: Some(16),
+            font: None,
+            files: files.iter().map(|file| file.to_string()).collect(),
+            outputs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn resolves_moved_and_duplicate_files() {
+        let output_dir = Path::new("/out");
+        let mut entries = [
+            entry(1, "bil", &["a.png", "b.png", "gone.png"]),
+            entry(2, "bil kopia", &["c.png"]),
+        ];
+        let moved = HashMap::from([
+            ("a.png".to_string(), output_dir.join("intro/bil.png")),
+            ("b.png".to_string(), output_dir.join("intro/bil_2x.png")),
+        ]);
+        let duplicates = HashMap::from([("c.png".to_string(), "a.png".to_string())]);
+        resolve_outputs(&mut entries, output_dir, &moved, &duplicates);
+        assert_eq!(entries[0].outputs, ["intro/bil.png", "intro/bil_2x.png"]);
+        assert_eq!(entries[1].outputs, ["intro/bil.png"]);
+    }
+
+    #[test]
+    fn writes_members_and_a_font_index() {
+        let dir = env::temp_dir().join(format!("cgex_test_manifest_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut bitmap = entry(1, "bil", &[]);
+        bitmap.outputs = vec!["intro/bil.png".to_string()];
+        let mut font = entry(2, "Mulle", &[]);
+        font.member_type = "font".to_string();
+        font.bit_depth = None;
+        font.palette = None;
+        font.registration_point = None;
+        font.font = Some("Mulle".to_string());
+        font.outputs = vec!["intro/Mulle.bdf".to_string()];
+        let uses = [FontUse {
+            font: "MULLE".to_string(),
+            movie: "intro".to_string(),
+            cast: "Internal".to_string(),
+            member: 3,
+            name: "rubrik".to_string(),
+        }];
+        let links = [CastLink {
+            movie: "intro".to_string(),
+            cast: "delad".to_string(),
+            file_path: "C:\\mulle\\delad.cst".to_string(),
+            file: Some("delad.cst".to_string()),
+            folder: None,
+        }];
+        write_manifest(&dir, "mulle_bil", 2, &[bitmap, font], &uses, &links).unwrap();
+
+        let text = fs::read_to_string(dir.join("manifest.json")).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&text).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(manifest["game"], "mulle_bil");
+        assert_eq!(manifest["image_scale"], 2);
+        let bitmap = &manifest["members"][0];
+        assert_eq!(bitmap["type"], "bitmap");
+        assert_eq!(bitmap["registration_point"], serde_json::json!([10, -4]));
+        assert_eq!(
+            bitmap["palette"],
+            serde_json::json!({ "member": { "cast_lib": 1, "member": 2 } })
+        );
+        assert!(bitmap.get("files").is_none());
+        assert!(bitmap.get("font").is_none());
+        assert!(manifest["members"][1].get("palette").is_none());
+
+        // Font names are grouped regardless of case
+        let fonts = manifest["fonts"].as_array().unwrap();
+        assert_eq!(fonts.len(), 1);
+        assert_eq!(fonts[0]["font"], "Mulle");
+        assert_eq!(fonts[0]["outputs"], serde_json::json!(["intro/Mulle.bdf"]));
+        assert_eq!(fonts[0]["members"][0]["name"], "rubrik");
+        assert_eq!(
+            manifest["external_casts"][0]["folder"],
+            serde_json::Value::Null
+        );
+    }
+}
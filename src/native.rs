@@ -0,0 +1,774 @@
+use crate::anim;
+use crate::director::bitmap::{BitmapInfo, PaletteRef};
+use crate::director::film_loop::{self, FilmLoop};
+use crate::director::font::{self, EmbeddedFont};
+use crate::director::lingo::CastScripts;
+use crate::director::shape::Shape;
+use crate::director::sound::{self, Media};
+use crate::director::text::StyledText;
+use crate::director::{palette, swa, CastLib, CastMember, MemberType, Movie, CLUT};
+use crate::manifest::{CastLink, FontUse, ManifestEntry};
+use crate::report::{self, MovieReport};
+use crate::resource_fork::{self, Resource};
+use anyhow::{bail, Context, Result};
+use image::{ImageFormat, RgbaImage};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A bitmap decoded in memory, named like the BMP file `dir_extractor.exe`
+/// would have written for it.
+pub struct DecodedBitmap {
+    pub file_name: String,
+    pub image: RgbaImage,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// Write Shockwave Audio as `.mp3` instead of decoding it to WAV.
+    pub raw_mp3: bool,
+    /// Write film loops as animated WebP instead of APNG.
+    pub webp: bool,
+    /// Scale shapes are rasterised at, to match the upscaled bitmaps.
+    pub image_scale: u32,
+}
+
+/// Folder external casts are extracted to, shared by all movies linking
+/// them. It takes the place of the movie name in file names.
+pub const SHARED_FOLDER: &str = "shared";
+
+/// Stands in for the cast name in the file names of resources from a Mac
+/// file's resource fork, so they end up in a folder of their own.
+const RESOURCES_FOLDER: &str = "resources";
+
+/// Extensions of external cast files: plain, protected and Shockwave.
+const CAST_EXTENSIONS: [&str; 3] = ["cst", "cxt", "cct"];
+
+/// Everything `extract_movie` produced besides the files in the temp
+/// directory.
+#[derive(Default)]
+pub struct MovieAssets {
+    pub bitmaps: Vec<DecodedBitmap>,
+    pub manifest: Vec<ManifestEntry>,
+    pub font_uses: Vec<FontUse>,
+    /// The movie's external cast libraries, to be extracted once each.
+    pub external_casts: Vec<CastLink>,
+    /// What `dir_extractor.exe` reported, for backends that run it.
+    pub reports: Vec<MovieReport>,
+}
+
+impl MovieAssets {
+    pub fn extend(&mut self, other: MovieAssets) {
+        self.bitmaps.extend(other.bitmaps);
+        self.manifest.extend(other.manifest);
+        self.font_uses.extend(other.font_uses);
+        self.external_casts.extend(other.external_casts);
+        self.reports.extend(other.reports);
+    }
+}
+
+/// Extracts the assets of one movie without Wine, writing them to
+/// `temp_dir` with the same `movie--cast__member` names that
+/// `dir_extractor.lingo` uses so the rest of the pipeline is unchanged,
+/// except that sounds keep their member number like everything else.
+/// Bitmaps are returned instead of written, so they can go straight to
+/// `process_image`, along with a manifest entry for every member that
+/// produced a file. The score is written as `movie--timeline.json`, which
+/// ends up as `timeline.json` in the movie's folder, and the frame labels
+/// go to `labels.json` next to it. External casts are only listed, for the
+/// caller to extract once with `extract_shared_cast`.
+pub fn extract_movie(
+    temp_dir: &Path,
+    dir_file: &Path,
+    options: ExportOptions,
+) -> Result<MovieAssets> {
+    let movie = Movie::open(dir_file)?;
+    let mut assets = extract_casts(&movie, temp_dir, options)?;
+    for cast in &movie.casts {
+        if !cast.file_path.is_empty() && cast.members.is_empty() {
+            assets.external_casts.push(CastLink {
+                movie: movie.name.clone(),
+                cast: cast.name.clone(),
+                file_path: cast.file_path.clone(),
+                file: None,
+                folder: None,
+            });
+        }
+    }
+    // The score of older movies isn't decoded; their members are still
+    // worth having
+    let frames = movie.score().unwrap_or_else(|e| {
+        println!(
+            "Warning: Skipping the timeline of {:?}: {:#}",
+            movie.name, e
+        );
+        None
+    });
+    if let Some(frames) = frames {
+        let file_name = format!("{}--timeline.json", movie.name);
+        fs::write(
+            temp_dir.join(file_name),
+            serde_json::to_string_pretty(&frames)?,
+        )?;
+        match movie.labels(&frames) {
+            Ok(Some(labels)) => {
+                let file_name = format!("{}--labels.json", movie.name);
+                fs::write(
+                    temp_dir.join(file_name),
+                    serde_json::to_string_pretty(&labels)?,
+                )?;
+            }
+            Ok(None) => {}
+            Err(e) => println!(
+                "Warning: Skipping the frame labels of {:?}: {:#}",
+                movie.name, e
+            ),
+        }
+    }
+    Ok(assets)
+}
+
+/// Extracts an external cast file into `shared/<name>`, so every movie
+/// linking it finds it in the same place. `name` comes from
+/// [`shared_cast_name`]. Returns the folder, relative to the output
+/// directory.
+pub fn extract_shared_cast(
+    temp_dir: &Path,
+    cast_file: &Path,
+    name: &str,
+    options: ExportOptions,
+) -> Result<(String, MovieAssets)> {
+    let mut movie = Movie::open(cast_file)?;
+    movie.name = SHARED_FOLDER.to_string();
+    match movie.casts.len() {
+        0 => bail!("Cast file {:?} holds no casts", cast_file),
+        1 => {}
+        count => println!(
+            "Warning: Cast file {:?} holds {} casts, extracting them all into {:?}",
+            cast_file, count, name
+        ),
+    }
+    for cast in &mut movie.casts {
+        cast.name = name.to_string();
+    }
+    let assets = extract_casts(&movie, temp_dir, options)?;
+    let folder = format!("{}/{}", SHARED_FOLDER, sanitize(name));
+    Ok((folder, assets))
+}
+
+/// Names the shared folder of an external cast after its file, like a
+/// movie's folder. Casts whose names are already in `taken`, which holds
+/// lowercase names since the folders can end up on a case-insensitive file
+/// system, get a number appended so they don't overwrite each other. The
+/// name returned is added to `taken`.
+pub fn shared_cast_name(cast_file: &Path, taken: &mut HashSet<String>) -> String {
+    let stem = cast_file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let stem = sanitize(stem.split('.').next().unwrap_or_default());
+    let mut name = stem.clone();
+    let mut number = 1;
+    while !taken.insert(name.to_lowercase()) {
+        number += 1;
+        name = format!("{}_{}", stem, number);
+    }
+    if number > 1 {
+        println!(
+            "Warning: Another external cast is already named {:?}, extracting {:?} as {:?}",
+            stem, cast_file, name
+        );
+    }
+    name
+}
+
+/// Exports the members of all the casts stored in a movie or cast file.
+fn extract_casts(movie: &Movie, temp_dir: &Path, options: ExportOptions) -> Result<MovieAssets> {
+    let mut assets = MovieAssets::default();
+    let mut plain_texts = 0;
+    for (cast_index, cast) in movie.casts.iter().enumerate() {
+        // A script context that can't be read only costs the decompiled scripts
+        let scripts = movie.scripts(cast_index).unwrap_or_else(|e| {
+            println!(
+                "Warning: Failed to read the scripts of cast {:?}: {:#}",
+                cast.name, e
+            );
+            None
+        });
+        for member in &cast.members {
+            // One member that can't be decoded shouldn't cost the rest
+            let files = match export_member(
+                movie,
+                cast_index,
+                member,
+                scripts.as_ref(),
+                temp_dir,
+                options,
+                &mut assets.bitmaps,
+            ) {
+                Ok(files) => files,
+                Err(e) => {
+                    println!(
+                        "Warning: Failed to export member {} ({:?}) of cast {:?}: {:#}",
+                        member.number, member.name, cast.name, e
+                    );
+                    continue;
+                }
+            };
+            if !files.is_empty() {
+                assets
+                    .manifest
+                    .push(manifest_entry(movie, cast, member, files));
+            }
+            // Unreadable text has already been warned about when exporting it
+            if let Some(styled) = is_text(member)
+                .then(|| movie.styled_text(member).ok().flatten())
+                .flatten()
+            {
+                if styled.plain {
+                    plain_texts += 1;
+                }
+                assets
+                    .font_uses
+                    .extend(text_fonts(movie, cast, member, &styled));
+            }
+        }
+    }
+    if plain_texts > 0 {
+        println!(
+            "Warning: {} text members of {:?} were written as plain text only, \
+             their XMED/RTE1 styles aren't decoded yet",
+            plain_texts, movie.name
+        );
+    }
+    Ok(assets)
+}
+
+/// Finds the file an external cast reference points to under `dir`. The
+/// stored path is from the author's machine, in Windows or Mac syntax, and
+/// the extension may differ from the file on the disc (`.cst` becomes
+/// `.cxt` when protected), so only the file's stem is matched, ignoring
+/// case like the file systems Director ran on.
+pub fn find_cast_file(dir: &Path, file_path: &str) -> Result<Option<PathBuf>> {
+    let file_name = file_path
+        .rsplit(['\\', '/', ':'])
+        .next()
+        .unwrap_or_default();
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, extension))
+            if CAST_EXTENSIONS
+                .iter()
+                .any(|cast| extension.eq_ignore_ascii_case(cast)) =>
+        {
+            stem
+        }
+        _ => file_name,
+    };
+    if stem.is_empty() {
+        return Ok(None);
+    }
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in &entries {
+        let path = entry.path();
+        let matches = path
+            .file_stem()
+            .is_some_and(|s| s.to_string_lossy().eq_ignore_ascii_case(stem))
+            && path.extension().is_some_and(|extension| {
+                CAST_EXTENSIONS
+                    .iter()
+                    .any(|cast| extension.eq_ignore_ascii_case(cast))
+            });
+        if matches && path.is_file() {
+            return Ok(Some(path));
+        }
+    }
+    for entry in entries {
+        if entry.path().is_dir() {
+            if let Some(path) = find_cast_file(&entry.path(), file_path)? {
+                return Ok(Some(path));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Exports the sounds and bitmap fonts in a resource fork written by
+/// [`crate::hfs`], as WAV and BDF. They are named after the file the fork
+/// belongs to like a movie's members are, in a `resources` folder, and
+/// unnamed ones after their type. Returns the names of the files written.
+pub fn extract_resource_fork(temp_dir: &Path, fork_path: &Path) -> Result<Vec<String>> {
+    let fork = fs::read(fork_path)?;
+    let resources = resource_fork::read_resources(&fork)?;
+    let owner = fork_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Invalid resource fork file name")?
+        .split('.')
+        .next()
+        .unwrap_or_default();
+    let families = resource_fork::font_families(&resources);
+    let mut files = Vec::new();
+    for resource in &resources {
+        let mut label = match &resource.name {
+            Some(name) if !name.is_empty() => sanitize(name),
+            _ => sanitize(resource_fork::type_name(resource).trim()),
+        };
+        let exported = match &resource.resource_type {
+            b"snd " => sound::decode_snd(resource.data).map(|sound| Some((sound.to_wav(), "wav"))),
+            b"FONT" | b"NFNT" => {
+                bitmap_font(resource, &resources, &families).map(|font| {
+                    font.map(|(family, bdf)| {
+                        // Font resources are rarely named themselves
+                        label = sanitize(&family);
+                        (bdf.into_bytes(), "bdf")
+                    })
+                })
+            }
+            _ => Ok(None),
+        };
+        match exported {
+            Ok(Some((data, extension))) => {
+                let file_name = format!(
+                    "{}--{}__{}-{}.{}",
+                    owner, RESOURCES_FOLDER, label, resource.id, extension
+                );
+                fs::write(temp_dir.join(&file_name), data)?;
+                files.push(file_name);
+            }
+            Ok(None) => {}
+            Err(e) => println!(
+                "Warning: Failed to export resource {} {:?} of {:?}: {}",
+                resource.id, label, owner, e
+            ),
+        }
+    }
+    Ok(files)
+}
+
+/// Converts a `FONT` or `NFNT` resource to BDF, returning its family name
+/// with it. `FONT` resources of size 0 only name their family and are
+/// skipped.
+fn bitmap_font(
+    resource: &Resource,
+    resources: &[Resource],
+    families: &[(i16, String, u16)],
+) -> Result<Option<(String, String)>> {
+    if resource.data.is_empty() {
+        return Ok(None);
+    }
+    let (family, size) = if &resource.resource_type == b"FONT" {
+        let family_id = resource.id & !0x7f;
+        let family = resources
+            .iter()
+            .find(|other| &other.resource_type == b"FONT" && other.id == family_id)
+            .and_then(|other| other.name.clone());
+        (family, Some((resource.id & 0x7f) as u16))
+    } else {
+        match families.iter().find(|(id, _, _)| *id == resource.id) {
+            Some((_, family, size)) => (Some(family.clone()), Some(*size)),
+            None => (None, None),
+        }
+    };
+    let family = family
+        .or_else(|| resource.name.clone())
+        .unwrap_or_else(|| format!("font {}", resource.id));
+    let bdf = font::nfnt_to_bdf(resource.data, &family, size.filter(|&size| size > 0))?;
+    Ok(Some((family, bdf)))
+}
+
+/// Manifest entries for the files `dir_extractor.exe` wrote to `temp_dir`
+/// for `movie`, which only the movie itself describes.
+pub fn describe_extracted(temp_dir: &Path, movie: &Movie) -> Result<Vec<ManifestEntry>> {
+    // The extractor's file names can differ in case from the member names
+    let mut written = HashMap::new();
+    for entry in fs::read_dir(temp_dir)? {
+        let file_name = entry?.file_name().to_string_lossy().into_owned();
+        written.insert(file_name.to_lowercase(), file_name);
+    }
+    let prefix = format!("{}--", movie.name);
+    let mut entries = Vec::new();
+    for cast in &movie.casts {
+        for member in &cast.members {
+            let file = report::expected_file(&prefix, cast, member)
+                .and_then(|file| written.get(&file.to_lowercase()));
+            if let Some(file) = file {
+                entries.push(manifest_entry(movie, cast, member, vec![file.clone()]));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn manifest_entry(
+    movie: &Movie,
+    cast: &CastLib,
+    member: &CastMember,
+    files: Vec<String>,
+) -> ManifestEntry {
+    let bitmap = (member.member_type == MemberType::Bitmap)
+        .then(|| {
+            BitmapInfo::parse(&member.specific_data, movie.version, movie.system_palette()).ok()
+        })
+        .flatten();
+    let size = match member.member_type {
+        MemberType::FilmLoop => FilmLoop::parse(&member.specific_data, Vec::new())
+            .ok()
+            .map(|film_loop| (film_loop.width(), film_loop.height())),
+        MemberType::Shape => Shape::parse(&member.specific_data)
+            .ok()
+            .map(|shape| (shape.width, shape.height)),
+        _ => bitmap.as_ref().map(|info| (info.width, info.height)),
+    };
+    ManifestEntry {
+        movie: movie.name.clone(),
+        cast: cast.name.clone(),
+        member: member.number,
+        name: member.name.clone(),
+        member_type: member
+            .xtra_type()
+            .filter(|_| member.member_type == MemberType::Xtra)
+            .unwrap_or_else(|| member.member_type.name().to_string()),
+        bit_depth: bitmap.as_ref().map(|info| info.bit_depth),
+        palette: bitmap.as_ref().map(|info| info.palette),
+        registration_point: bitmap.as_ref().map(|info| [info.reg_x, info.reg_y]),
+        width: size.map(|(width, _)| width),
+        height: size.map(|(_, height)| height),
+        font: is_font(member).then(|| font_family(member)),
+        files,
+        outputs: Vec::new(),
+    }
+}
+
+fn export_member(
+    movie: &Movie,
+    cast_index: usize,
+    member: &CastMember,
+    scripts: Option<&CastScripts>,
+    temp_dir: &Path,
+    options: ExportOptions,
+    bitmaps: &mut Vec<DecodedBitmap>,
+) -> Result<Vec<String>> {
+    let cast = &movie.casts[cast_index];
+    let mut files = Vec::new();
+    // A script the decompiler chokes on shouldn't cost the member's assets
+    match movie.script_source(member, scripts) {
+        Ok(Some(source)) => {
+            let file_name = member_file_name(movie, cast, member, "ls");
+            fs::write(temp_dir.join(&file_name), source)?;
+            files.push(file_name);
+        }
+        Ok(None) => {}
+        Err(e) => println!(
+            "Warning: Failed to decompile script of member {} ({:?}): {:#}",
+            member.number, member.name, e
+        ),
+    }
+    match member.member_type {
+        _ if is_text(member) => match movie.styled_text(member) {
+            Ok(Some(styled)) => {
+                let mut outputs = vec![("txt", styled.text.clone())];
+                // Without decoded styles the other formats would only repeat
+                // the text
+                if !styled.plain {
+                    outputs.push(("md", styled.to_markdown()));
+                    outputs.push(("html", styled.to_html(&member.name)));
+                    outputs.push(("json", serde_json::to_string_pretty(&styled)?));
+                }
+                for (extension, data) in outputs {
+                    let file_name = member_file_name(movie, cast, member, extension);
+                    fs::write(temp_dir.join(&file_name), data)?;
+                    files.push(file_name);
+                }
+            }
+            Ok(None) => {}
+            // The text member format is only partly understood
+            Err(e) => println!(
+                "Warning: Failed to read text of member {} ({:?}): {:#}",
+                member.number, member.name, e
+            ),
+        },
+        MemberType::Bitmap => {
+            if let Some(image) = movie.bitmap(member, cast_index)? {
+                let file_name = member_file_name(movie, cast, member, "bmp");
+                files.push(file_name.clone());
+                bitmaps.push(DecodedBitmap { file_name, image });
+            }
+        }
+        MemberType::Palette => {
+            if let Some(clut) = movie.child(member.section_id, CLUT) {
+                let colours = palette::read_clut(&clut.data)?;
+                let outputs = [
+                    ("act", palette::to_act(&colours)),
+                    ("gpl", palette::to_gpl(&colours, &member.name).into_bytes()),
+                    ("json", serde_json::to_vec(&colours)?),
+                ];
+                for (extension, data) in outputs {
+                    let file_name = member_file_name(movie, cast, member, extension);
+                    fs::write(temp_dir.join(&file_name), data)?;
+                    files.push(file_name);
+                }
+            }
+        }
+        MemberType::FilmLoop => {
+            if let Some(film_loop) = movie.film_loop(member, cast_index)? {
+                files.extend(export_film_loop(
+                    movie, cast_index, member, &film_loop, temp_dir, options,
+                )?);
+            }
+        }
+        MemberType::Shape => {
+            let shape = Shape::parse(&member.specific_data)?;
+            // Shape colours index the movie's palette, which we take to be
+            // the system one like bitmaps without a palette do
+            let palette = movie.palette(&PaletteRef::Builtin(movie.system_palette()), cast_index);
+            let svg_name = member_file_name(movie, cast, member, "svg");
+            fs::write(temp_dir.join(&svg_name), shape.to_svg(&palette))?;
+            files.push(svg_name);
+            let png_name = member_file_name(movie, cast, member, "png");
+            shape
+                .render(&palette, options.image_scale)
+                .save_with_format(temp_dir.join(&png_name), ImageFormat::Png)?;
+            files.push(png_name);
+        }
+        MemberType::Xtra if is_font(member) => {
+            if let Some(file_name) = export_font(movie, cast, member, temp_dir)? {
+                files.push(file_name);
+            }
+        }
+        MemberType::Sound => files.extend(export_sound(
+            movie,
+            cast,
+            member,
+            temp_dir,
+            options.raw_mp3,
+        )?),
+        MemberType::Xtra
+            if member
+                .xtra_type()
+                .is_some_and(|t| t.eq_ignore_ascii_case("swa")) =>
+        {
+            files.extend(export_sound(
+                movie,
+                cast,
+                member,
+                temp_dir,
+                options.raw_mp3,
+            )?)
+        }
+        _ => {}
+    }
+    Ok(files)
+}
+
+fn member_file_name(movie: &Movie, cast: &CastLib, member: &CastMember, extension: &str) -> String {
+    format!(
+        "{}--{}__{}-{}.{}",
+        movie.name,
+        sanitize(&cast.name),
+        sanitize(&member.name),
+        member.number,
+        extension
+    )
+}
+
+/// Writes a film loop as an animation plus its frame list in JSON, which
+/// has the sprites of each frame for engines that draw it themselves.
+/// Returns the names of the files written.
+fn export_film_loop(
+    movie: &Movie,
+    cast_index: usize,
+    member: &CastMember,
+    film_loop: &FilmLoop,
+    temp_dir: &Path,
+    options: ExportOptions,
+) -> Result<Vec<String>> {
+    let cast = &movie.casts[cast_index];
+    let mut files = Vec::new();
+    let json_name = member_file_name(movie, cast, member, "json");
+    fs::write(
+        temp_dir.join(&json_name),
+        serde_json::to_string_pretty(film_loop)?,
+    )?;
+    files.push(json_name);
+
+    if film_loop.width() == 0 || film_loop.height() == 0 || film_loop.frames.is_empty() {
+        return Ok(files);
+    }
+    let frames = movie.render_film_loop(film_loop, cast_index)?;
+    let delay_ms = (1000 / film_loop::FRAME_RATE) as u16;
+    let (data, extension) = if options.webp {
+        (
+            anim::encode_webp(&frames, delay_ms, film_loop.looping)?,
+            "webp",
+        )
+    } else {
+        (
+            anim::encode_apng(&frames, delay_ms, film_loop.looping)?,
+            "png",
+        )
+    };
+    let file_name = member_file_name(movie, cast, member, extension);
+    fs::write(temp_dir.join(&file_name), data)?;
+    files.push(file_name);
+    Ok(files)
+}
+
+/// Fields, Director 6 text members and the Text Asset Xtra's members.
+pub(crate) fn is_text(member: &CastMember) -> bool {
+    match member.member_type {
+        MemberType::Field | MemberType::RichText => true,
+        MemberType::Xtra => member
+            .xtra_type()
+            .is_some_and(|t| t.eq_ignore_ascii_case("text")),
+        _ => false,
+    }
+}
+
+fn is_font(member: &CastMember) -> bool {
+    member.member_type == MemberType::Xtra
+        && member
+            .xtra_type()
+            .is_some_and(|t| t.eq_ignore_ascii_case("font"))
+}
+
+/// Director names embedded fonts after their family with a ` *` suffix.
+fn font_family(member: &CastMember) -> String {
+    member.name.trim_end_matches(" *").to_string()
+}
+
+/// Writes the font embedded in a font member: outline fonts as they are,
+/// Mac bitmap fonts as BDF. Returns the name of the file written.
+fn export_font(
+    movie: &Movie,
+    cast: &CastLib,
+    member: &CastMember,
+    temp_dir: &Path,
+) -> Result<Option<String>> {
+    let (data, extension) = match movie.embedded_font(member) {
+        Some(EmbeddedFont::Outline { data, extension }) => (data.to_vec(), extension),
+        Some(EmbeddedFont::Bitmap(nfnt)) => (
+            font::nfnt_to_bdf(nfnt, &font_family(member), None)?.into_bytes(),
+            "bdf",
+        ),
+        None => return Ok(None),
+    };
+    let file_name = member_file_name(movie, cast, member, extension);
+    fs::write(temp_dir.join(&file_name), data)?;
+    Ok(Some(file_name))
+}
+
+/// The fonts a text member's style runs use, for the manifest's font
+/// index.
+fn text_fonts(
+    movie: &Movie,
+    cast: &CastLib,
+    member: &CastMember,
+    styled: &StyledText,
+) -> Vec<FontUse> {
+    let mut fonts: Vec<&String> = Vec::new();
+    for run in &styled.runs {
+        if !fonts.contains(&&run.font) {
+            fonts.push(&run.font);
+        }
+    }
+    fonts
+        .into_iter()
+        .map(|font| FontUse {
+            font: font.clone(),
+            movie: movie.name.clone(),
+            cast: cast.name.clone(),
+            member: member.number,
+            name: member.name.clone(),
+        })
+        .collect()
+}
+
+/// Writes a sound as WAV. Shockwave Audio is decoded too, unless `raw_mp3`
+/// asks for its MPEG stream as is. Returns the name of the file written.
+fn export_sound(
+    movie: &Movie,
+    cast: &CastLib,
+    member: &CastMember,
+    temp_dir: &Path,
+    raw_mp3: bool,
+) -> Result<Option<String>> {
+    let (data, extension) = match movie.sound(member)? {
+        Some(Media::Wav(wav)) => (wav, "wav"),
+        Some(Media::Pcm(sound)) => (sound.to_wav(), "wav"),
+        Some(Media::Mp3(mp3)) if raw_mp3 => (mp3, "mp3"),
+        Some(Media::Mp3(mp3)) => (swa::decode_mp3(&mp3)?.to_wav(), "wav"),
+        None => return Ok(None),
+    };
+    // Unlike dir_extractor.lingo, keep the member number, so sounds of the
+    // same name don't overwrite each other
+    let file_name = member_file_name(movie, cast, member, extension);
+    fs::write(temp_dir.join(&file_name), data)?;
+    Ok(Some(file_name))
+}
+
+/// Member and cast names are free text in Director; keep them usable as
+/// file names.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn finds_cast_files_by_stem() {
+        let dir = env::temp_dir().join(format!("cgex_test_casts_{}", std::process::id()));
+        fs::create_dir_all(dir.join("Data")).unwrap();
+        for name in ["Data/DELAD.CXT", "delad.txt", "other.cst"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let found = |file_path: &str| find_cast_file(&dir, file_path).unwrap();
+        // Protected casts are found by the name of the unprotected one
+        assert_eq!(
+            found("C:\\mulle\\delad.cst"),
+            Some(dir.join("Data/DELAD.CXT"))
+        );
+        assert_eq!(found("Mulle:Casts:Other"), Some(dir.join("other.cst")));
+        assert_eq!(found("C:\\mulle\\saknas.cst"), None);
+        assert_eq!(found("C:\\mulle\\"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn describes_the_extractors_files() {
+        let dir = env::temp_dir().join(format!("cgex_test_described_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data = crate::director::tests::bitmap_movie(crate::director::Endian::Little);
+        let movie = Movie::parse("intro".to_string(), &data).unwrap();
+        assert!(describe_extracted(&dir, &movie).unwrap().is_empty());
+
+        fs::write(dir.join("INTRO--Internal__-1.bmp"), b"").unwrap();
+        let entries = describe_extracted(&dir, &movie).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].movie, "intro");
+        assert_eq!(entries[0].member, 1);
+        assert_eq!(entries[0].member_type, "bitmap");
+        assert!(entries[0].bit_depth.is_some());
+        assert_eq!(entries[0].files, ["INTRO--Internal__-1.bmp"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn names_shared_casts_apart() {
+        let mut taken = HashSet::new();
+        let names: Vec<String> = ["a/delad.cst", "b/Delad.cxt", "delad.v2.cst", "ljud.cst"]
+            .iter()
+            .map(|path| shared_cast_name(Path::new(path), &mut taken))
+            .collect();
+        assert_eq!(names, ["delad", "Delad_2", "delad_3", "ljud"]);
+    }
+}
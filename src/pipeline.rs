@@ -0,0 +1,1324 @@
+//! The extraction pipeline: copy the disc to a temp directory, export the
+//! assets of every movie, drop duplicates, process the images and move
+//! everything into one folder per movie and cast in the output directory.
+//!
+//! [`ExtractionPipeline`] runs all of it; the steps that make sense on
+//! their own, like [`detect_game`], are public too.
+
+use crate::backend::{self, ExtractorBackend};
+use crate::director::projector::{self, BundledKind};
+use crate::director::Movie;
+use crate::fingerprint::{self, Detection, Edition, MovieFile};
+use crate::game_extractor::{self, GameExtractor};
+use crate::hfs::{self, HfsVolume};
+use crate::img::{process_image, UPSCALE_FACTOR};
+use crate::iso::{self, DiscImage};
+use crate::manifest;
+use crate::native::{self, ExportOptions, MovieAssets};
+use crate::profile::{self, GameProfile};
+use crate::report::{self, IssueKind};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use data_encoding::HEXUPPER;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use rayon::prelude::*;
+use ring::digest::{digest, Context as DigestContext, SHA256};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Pipelines run so far in this process, to give each its own temp
+/// directory.
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// The two halves of a hybrid disc: the ISO 9660 file system Windows
+/// reads, and the HFS volume Macs read, which can hold different builds of
+/// the movies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Platform {
+    #[default]
+    Windows,
+    Mac,
+}
+
+/// How the assets are exported and processed.
+#[derive(Clone, Copy, Debug)]
+pub struct ExtractionOptions {
+    /// Write images as WebP instead of PNG.
+    pub compression: bool,
+    /// Upscale images with the super-resolution network.
+    pub upscale: bool,
+    /// Make the game's background colour transparent.
+    pub transparent_background: bool,
+    /// With the native backend, write Shockwave Audio as its MP3 stream
+    /// instead of decoding it to WAV.
+    pub raw_mp3: bool,
+    /// Which side of a hybrid disc image to read.
+    pub platform: Platform,
+    /// How many movies to extract at once, with a backend that can run
+    /// several workers.
+    pub jobs: usize,
+}
+
+impl Default for ExtractionOptions {
+    fn default() -> Self {
+        ExtractionOptions {
+            compression: false,
+            upscale: true,
+            transparent_background: true,
+            raw_mp3: false,
+            platform: Platform::Windows,
+            jobs: 1,
+        }
+    }
+}
+
+/// A step of the pipeline, as reported to the progress callback. Displays
+/// as the line the command line tool prints for it.
+#[derive(Clone, Copy, Debug)]
+pub enum Progress<'a> {
+    /// `confidence` is from 0 to 1, or `None` for a game that was given
+    /// rather than detected.
+    GameDetected {
+        game: &'a str,
+        edition: Option<&'a Edition>,
+        confidence: Option<f32>,
+    },
+    /// `index` counts from 1.
+    ExtractingMovie {
+        file: &'a str,
+        index: usize,
+        total: usize,
+    },
+    ExtractingCast {
+        file: &'a str,
+    },
+    RemovingDuplicates,
+    ProcessingImages {
+        upscale: bool,
+        compression: bool,
+    },
+    /// Images are processed in parallel, so these can arrive out of order.
+    ProcessingImage {
+        file: &'a str,
+        index: usize,
+        total: usize,
+    },
+    MovingFiles,
+    CleaningUp,
+    Done,
+}
+
+impl fmt::Display for Progress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Progress::GameDetected {
+                game,
+                edition,
+                confidence,
+            } => {
+                write!(f, "Found {} assets", game)?;
+                match (edition, confidence) {
+                    (Some(edition), Some(confidence)) => write!(
+                        f,
+                        " ({}, {}, {}; {:.0}% confidence)",
+                        edition.name,
+                        edition.language,
+                        edition.year,
+                        confidence * 100.0
+                    )?,
+                    (None, Some(confidence)) => {
+                        write!(f, " (by file names; {:.0}% confidence)", confidence * 100.0)?
+                    }
+                    _ => {}
+                }
+                write!(f, ". Starting extraction.")
+            }
+            Progress::ExtractingMovie { file, index, total } => {
+                write!(f, "Extracting assets from: {:?} ({}/{})", file, index, total)
+            }
+            Progress::ExtractingCast { file } => write!(f, "Extracting external cast: {:?}", file),
+            Progress::RemovingDuplicates => {
+                write!(f, "Removing duplicates. This might take a while...")
+            }
+            Progress::ProcessingImages {
+                upscale,
+                compression,
+            } => write!(
+                f,
+                "Processing images{}{}. This might take a while...",
+                if upscale { " with AI-upscaling" } else { "" },
+                if compression { " and compression" } else { "" }
+            ),
+            Progress::ProcessingImage { file, index, total } => {
+                write!(f, "Processing: {:?} ({}/{})", file, index, total)
+            }
+            Progress::MovingFiles => write!(f, "Moving files into final directory structure"),
+            Progress::CleaningUp => write!(f, "Cleaning up temporary directory"),
+            Progress::Done => write!(f, "Processing complete!"),
+        }
+    }
+}
+
+type ProgressCallback = Box<dyn Fn(Progress) + Send + Sync>;
+
+/// Extracts a game's assets from a disc or a copy of it.
+///
+/// ```no_run
+/// use cgex::backend::NativeBackend;
+/// use cgex::ExtractionPipeline;
+///
+/// ExtractionPipeline::new("disc_contents", "output")
+///     .backend(Box::new(NativeBackend))
+///     .on_progress(|progress| println!("{}", progress))
+///     .run()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct ExtractionPipeline {
+    input: PathBuf,
+    output: PathBuf,
+    options: ExtractionOptions,
+    game: Option<Box<dyn GameExtractor>>,
+    profiles: Vec<GameProfile>,
+    backend: Box<dyn ExtractorBackend>,
+    snapshot: Option<PathBuf>,
+    progress: Option<ProgressCallback>,
+}
+
+impl ExtractionPipeline {
+    /// Reads from `input`, a directory holding the disc's files or a disc
+    /// image, and writes to `output`.
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        ExtractionPipeline {
+            input: input.into(),
+            output: output.into(),
+            options: ExtractionOptions::default(),
+            game: None,
+            profiles: Vec::new(),
+            backend: backend::default_backend("extractor_tools"),
+            snapshot: None,
+            progress: None,
+        }
+    }
+
+    pub fn options(mut self, options: ExtractionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Extracts as `game` instead of detecting the game from the input.
+    pub fn game(mut self, game: Box<dyn GameExtractor>) -> Self {
+        self.game = Some(game);
+        self
+    }
+
+    /// Adds a game to detect besides the built-in ones. Added profiles win
+    /// over built-in ones matching as many files.
+    pub fn profile(mut self, profile: GameProfile) -> Self {
+        self.profiles.push(profile);
+        self
+    }
+
+    /// How the movies are extracted. By default `dir_extractor.exe` is run
+    /// from `extractor_tools` in the working directory, under Wine except
+    /// on Windows.
+    pub fn backend(mut self, backend: Box<dyn ExtractorBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Copies the temp directory to `dir` once the movies are extracted,
+    /// for a [`ReplayBackend`](backend::ReplayBackend) to start from.
+    pub fn snapshot(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.snapshot = Some(dir.into());
+        self
+    }
+
+    /// Calls `callback` as the pipeline moves through its steps. It can be
+    /// called from several threads at once.
+    pub fn on_progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    fn report(&self, progress: Progress) {
+        if let Some(callback) = &self.progress {
+            callback(progress);
+        }
+    }
+
+    /// Runs the extraction, leaving the assets in the output directory.
+    pub fn run(mut self) -> Result<()> {
+        let game = self.game.take();
+        let mut games: Vec<Box<dyn GameExtractor>> = Vec::new();
+        for profile in self.profiles.drain(..) {
+            games.push(Box::new(profile));
+        }
+        games.extend(known_games());
+        let options = self.options;
+        let input_dir = self.input.as_path();
+        let output_dir = self.output.as_path();
+
+        // Before detection, which would only say no game matched
+        if !input_dir.exists() {
+            bail!(
+                "Input directory '{}' does not exist. Please check your input path.",
+                input_dir.display()
+            );
+        }
+
+        let game = match game {
+            Some(game) => {
+                self.report(Progress::GameDetected {
+                    game: game.get_name(),
+                    edition: None,
+                    confidence: None,
+                });
+                game
+            }
+            None => {
+                let detection = detect_game(input_dir, options.platform, games)?;
+                self.report(Progress::GameDetected {
+                    game: detection.game.get_name(),
+                    edition: detection.edition.as_ref(),
+                    confidence: Some(detection.confidence),
+                });
+                detection.game
+            }
+        };
+
+        self.backend.check()?;
+
+        // Numbered too, so several pipelines can run in one process
+        let temp_dir = env::temp_dir().join(format!(
+            "cgex_{}_{}",
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&temp_dir).context("Failed to create temporary directory")?;
+
+        // Copy the entire input directory to temp, or unpack the disc image
+        // straight into it
+        if iso::is_disc_image(input_dir) {
+            let mut image = DiscImage::open(input_dir)?;
+            match options.platform {
+                Platform::Windows => image.extract(&temp_dir),
+                Platform::Mac => HfsVolume::open(&mut image)?.extract(&temp_dir),
+            }
+            .context("Failed to extract disc image to temp")?;
+        } else {
+            game_extractor::copy_directory(input_dir, &temp_dir)
+                .context("Failed to copy input directory to temp")?;
+        }
+
+        self.backend.prepare(&temp_dir)?;
+
+        // Prepare the temp directory based on the specific game requirements
+        game.prepare_temp_directory(&temp_dir)?;
+
+        // Files from the disc are left where they are; only what extraction
+        // writes is processed and moved to the output
+        let disc_files = top_level_files(&temp_dir)?;
+        let find_extracted = |extensions: &[&str]| -> Result<Vec<fs::DirEntry>> {
+            let mut files = find_files(&temp_dir, extensions)?;
+            files.retain(|file| !disc_files.contains(&*file.file_name().to_string_lossy()));
+            Ok(files)
+        };
+
+        let MovieAssets {
+            bitmaps: mut decoded_bitmaps,
+            mut manifest,
+            font_uses,
+            external_casts,
+            reports,
+        } = self
+            .extract_files(&temp_dir, game.as_ref())
+            .context("Failed to extract files")?;
+
+        if let Some(snapshot) = &self.snapshot {
+            game_extractor::copy_directory(&temp_dir, snapshot)
+                .with_context(|| format!("Failed to save snapshot to {:?}", snapshot))?;
+        }
+
+        self.report(Progress::RemovingDuplicates);
+        let mut duplicates = remove_duplicates(&temp_dir).unwrap_or_else(|e| {
+            println!("Warning: Failed to remove duplicate files: {}", e);
+            println!("Continuing with processing...");
+            HashMap::new()
+        });
+        // Natively decoded bitmaps never hit the disk; deduplicate them by
+        // pixel content in the same sorted order remove_duplicates uses.
+        decoded_bitmaps.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        let mut seen_bitmaps: HashMap<String, String> = HashMap::new();
+        decoded_bitmaps.retain(|bitmap| {
+            let mut data = bitmap.image.width().to_be_bytes().to_vec();
+            data.extend_from_slice(&bitmap.image.height().to_be_bytes());
+            data.extend_from_slice(bitmap.image.as_raw());
+            match seen_bitmaps.entry(hash_bytes(&data)) {
+                Entry::Occupied(original) => {
+                    duplicates.insert(bitmap.file_name.clone(), original.get().clone());
+                    false
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(bitmap.file_name.clone());
+                    true
+                }
+            }
+        });
+
+        let broken_images = game.get_broken_images();
+        decoded_bitmaps.retain(|bitmap| !broken_images.contains(&bitmap.file_name.as_str()));
+        for file in &broken_images {
+            let path = temp_dir.join(file);
+            if !path.exists() {
+                continue;
+            }
+            if let Err(e) = fs::remove_file(&path) {
+                println!("Warning: Failed to remove file {:?}: {}", path, e);
+            }
+        }
+
+        self.report(Progress::ProcessingImages {
+            upscale: options.upscale,
+            compression: options.compression,
+        });
+
+        let bmp_files =
+            find_extracted(&[".bmp"]).context("Failed to find BMP files for processing")?;
+        // BMP files written by dir_extractor.exe are loaded lazily, natively
+        // decoded bitmaps are already in memory
+        let mut images: Vec<(PathBuf, Option<RgbaImage>)> =
+            bmp_files.iter().map(|entry| (entry.path(), None)).collect();
+        images.extend(
+            decoded_bitmaps
+                .into_iter()
+                .map(|bitmap| (temp_dir.join(bitmap.file_name), Some(bitmap.image))),
+        );
+        let total = images.len();
+        let counter = AtomicUsize::new(1);
+
+        let processed_files: Vec<Result<(PathBuf, ImageFormat)>> = images
+            .into_par_iter()
+            .map(|(input_path, decoded)| -> Result<(PathBuf, ImageFormat)> {
+                let current = counter.fetch_add(1, Ordering::SeqCst);
+                let file_name = input_path.file_name().unwrap().to_string_lossy();
+                self.report(Progress::ProcessingImage {
+                    file: &file_name,
+                    index: current,
+                    total,
+                });
+
+                let output_path = temp_dir.join(input_path.file_name().unwrap());
+                let img = match decoded {
+                    Some(image) => DynamicImage::ImageRgba8(image),
+                    None => image::open(&input_path)
+                        .with_context(|| format!("Failed to open input image: {:?}", input_path))?,
+                };
+                process_image(
+                    img,
+                    &output_path,
+                    options.compression,
+                    options.upscale,
+                    game.get_transparent_color(),
+                    options.transparent_background,
+                )
+                .map(|format| (output_path, format))
+                .with_context(|| format!("Failed to process image: {:?}", input_path))
+            })
+            .collect();
+
+        // Handle successful and failed image processing
+        let (successful, failed): (Vec<_>, Vec<_>) =
+            processed_files.into_iter().partition(Result::is_ok);
+
+        let successful: Vec<(PathBuf, ImageFormat)> =
+            successful.into_iter().map(Result::unwrap).collect();
+
+        // Report failed images
+        for error in failed {
+            if let Err(e) = error {
+                eprintln!("Error processing image: {}", e);
+            }
+        }
+
+        game.post_extraction_setup(&temp_dir, &successful)?;
+
+        self.report(Progress::MovingFiles);
+        fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+
+        // Where each temp file ended up, for the manifest
+        let mut moved = HashMap::new();
+        let mut record_move = |src_path: &Path, dst_path: PathBuf| {
+            let file_name = src_path.file_name().unwrap().to_string_lossy().into_owned();
+            moved.insert(file_name, dst_path);
+        };
+
+        for (temp_path, format) in successful {
+            let extension = format.extensions_str()[0];
+            let dst_path = move_file_to_output(&temp_path, output_dir, Some(extension))
+                .with_context(|| format!("Failed to move processed file: {:?}", temp_path))?;
+            record_move(&temp_path, dst_path);
+        }
+
+        // Film loops and shapes are rendered by the native extractor, so they
+        // skip process_image
+        let rendered_files = find_extracted(&[".png", ".webp", ".svg"])
+            .context("Failed to find film loop and shape files for moving")?;
+        for file in rendered_files {
+            let src_path = file.path();
+            let dst_path = move_file_to_output(&src_path, output_dir, None)
+                .context(format!("Failed to move rendered file: {:?}", src_path))?;
+            record_move(&src_path, dst_path);
+        }
+
+        let font_files = find_extracted(&[".pfr", ".ttf", ".otf", ".bdf"])
+            .context("Failed to find font files for moving")?;
+        for file in font_files {
+            let src_path = file.path();
+            let dst_path = move_file_to_output(&src_path, output_dir, None)
+                .context(format!("Failed to move font file: {:?}", src_path))?;
+            record_move(&src_path, dst_path);
+        }
+
+        let wav_files =
+            find_extracted(&[".wav", ".mp3"]).context("Failed to find WAV files for moving")?;
+        for file in wav_files {
+            let src_path = file.path();
+            let dst_path = move_file_to_output(&src_path, output_dir, None)
+                .context(format!("Failed to move sound file: {:?}", src_path))?;
+            record_move(&src_path, dst_path);
+        }
+
+        let txt_files = find_extracted(&[".txt", ".md", ".html", ".ls"])
+            .context("Failed to find text and script files for moving")?;
+        for file in txt_files {
+            let src_path = file.path();
+            let dst_path = move_file_to_output(&src_path, output_dir, None)
+                .context(format!("Failed to move text file: {:?}", src_path))?;
+            record_move(&src_path, dst_path);
+        }
+
+        let data_files = find_extracted(&[".act", ".gpl", ".json"])
+            .context("Failed to find palette and JSON files for moving")?;
+        for file in data_files {
+            let src_path = file.path();
+            let dst_path = move_file_to_output(&src_path, output_dir, None)
+                .context(format!("Failed to move data file: {:?}", src_path))?;
+            record_move(&src_path, dst_path);
+        }
+
+        let image_scale = self.export_options().image_scale;
+        manifest::resolve_outputs(&mut manifest, output_dir, &moved, &duplicates);
+        manifest::write_manifest(
+            output_dir,
+            game.get_name(),
+            image_scale,
+            &manifest,
+            &font_uses,
+            &external_casts,
+        )?;
+        if !reports.is_empty() {
+            // Dialogs and timeouts were warned about as they happened, and
+            // the members not exported are summed up by `Incomplete`
+            for movie in &reports {
+                for issue in &movie.issues {
+                    if !matches!(
+                        issue.kind,
+                        IssueKind::PlayerError | IssueKind::TimedOut | IssueKind::NotExported
+                    ) {
+                        println!("Warning: {}: {}", movie.movie, issue);
+                    }
+                }
+            }
+            report::write_report(output_dir, &reports)?;
+        }
+        self.report(Progress::CleaningUp);
+        fs::remove_dir_all(&temp_dir).context("Failed to remove temporary directory")?;
+
+        self.report(Progress::Done);
+        Ok(())
+    }
+
+    fn extract_files(&self, temp_dir: &Path, game: &dyn GameExtractor) -> Result<MovieAssets> {
+        let files = find_files(temp_dir, &[".dir", ".dxr"])
+            .context("Failed to find .dir or .dxr files. Make sure the input directory is correct and contains these files.")?;
+
+        if files.is_empty() {
+            bail!("No .dir or .dxr files found in the input directory. Please check your input path.");
+        }
+
+        let movies: Vec<PathBuf> = files.iter().map(|file| file.path()).collect();
+        let workers = self.start_workers(temp_dir, movies.len())?;
+        let mut assets = if workers.is_empty() {
+            let mut assets = MovieAssets::default();
+            for index in 0..movies.len() {
+                assets.extend(self.extract_movie(
+                    self.backend.as_ref(),
+                    temp_dir,
+                    &movies,
+                    index,
+                    game,
+                )?);
+            }
+            assets
+        } else {
+            let result = self.extract_in_parallel(temp_dir, &movies, &workers, game);
+            remove_workers(workers);
+            result?
+        };
+        // The files dir_extractor.exe writes are described from the movies,
+        // when they can be read
+        if !self.backend.describes_members() {
+            for movie in &movies {
+                if let Ok(movie) = Movie::open(movie) {
+                    assets.manifest.extend(native::describe_extracted(temp_dir, &movie)?);
+                }
+            }
+        }
+        // Only backends that parse the movies find their external casts
+        self.extract_external_casts(temp_dir, &mut assets)?;
+        // Resource forks of files from a Mac volume
+        for fork in find_files(temp_dir, &[hfs::RESOURCE_FORK_EXTENSION])? {
+            native::extract_resource_fork(temp_dir, &fork.path()).with_context(|| {
+                format!("Failed to read resource fork: {:?}", fork.file_name())
+            })?;
+        }
+        Ok(assets)
+    }
+
+    /// Extracts the movie at `index` in `movies`, which are in `temp_dir` or
+    /// a worker's copy of it with the same names.
+    fn extract_movie(
+        &self,
+        backend: &dyn ExtractorBackend,
+        temp_dir: &Path,
+        movies: &[PathBuf],
+        index: usize,
+        game: &dyn GameExtractor,
+    ) -> Result<MovieAssets> {
+        let file_name = movies[index].file_name().unwrap().to_string_lossy();
+        self.report(Progress::ExtractingMovie {
+            file: &file_name,
+            index: index + 1,
+            total: movies.len(),
+        });
+        backend
+            .extract_movie(temp_dir, &temp_dir.join(&*file_name), game, self.export_options())
+            .context(format!("Failed to extract assets from: {:?}", file_name))
+    }
+
+    /// Sets up to `jobs` workers, each with its directory next to the temp
+    /// directory and a copy of the temp directory in its `staging` folder.
+    /// Returns none if the backend runs one movie at a time.
+    fn start_workers(
+        &self,
+        temp_dir: &Path,
+        movies: usize,
+    ) -> Result<Vec<(PathBuf, Box<dyn ExtractorBackend>)>> {
+        let mut workers = Vec::new();
+        let count = self.options.jobs.min(movies);
+        if count < 2 {
+            return Ok(workers);
+        }
+        let temp_name = temp_dir.file_name().unwrap().to_string_lossy();
+        let mut dirs = Vec::new();
+        let started = (|| -> Result<bool> {
+            for index in 0..count {
+                let dir = temp_dir.with_file_name(format!("{}_worker{}", temp_name, index));
+                fs::create_dir_all(&dir).context("Failed to create worker directory")?;
+                dirs.push(dir.clone());
+                let Some(worker) = self.backend.worker(&dir)? else {
+                    return Ok(false);
+                };
+                game_extractor::copy_directory(temp_dir, &dir.join("staging"))
+                    .context("Failed to copy the temp directory for a worker")?;
+                workers.push((dir, worker));
+            }
+            Ok(true)
+        })();
+        match started {
+            Ok(true) => Ok(workers),
+            started => {
+                remove_workers(workers);
+                for dir in dirs.iter().filter(|dir| dir.exists()) {
+                    let _ = fs::remove_dir_all(dir);
+                }
+                started.map(|_| Vec::new())
+            }
+        }
+    }
+
+    /// Extracts the movies on the workers, each taking the next movie when
+    /// it is done with one. The files each movie wrote are then moved into
+    /// `temp_dir` in movie order, so the result is the same as extracting
+    /// them one after another.
+    fn extract_in_parallel(
+        &self,
+        temp_dir: &Path,
+        movies: &[PathBuf],
+        workers: &[(PathBuf, Box<dyn ExtractorBackend>)],
+        game: &dyn GameExtractor,
+    ) -> Result<MovieAssets> {
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        // The movie each worker is on, to name it if the worker panics
+        let current: Vec<AtomicUsize> = workers.iter().map(|_| AtomicUsize::new(0)).collect();
+        let mut extracted: Vec<(usize, PathBuf, Vec<String>, MovieAssets)> = Vec::new();
+        let mut errors = Vec::new();
+        thread::scope(|scope| {
+            let threads: Vec<_> = workers
+                .iter()
+                .zip(&current)
+                .map(|((dir, worker), current)| {
+                    let (next, failed) = (&next, &failed);
+                    scope.spawn(move || -> Result<Vec<_>> {
+                        let staging = dir.join("staging");
+                        let mut done = Vec::new();
+                        let mut files = top_level_files(&staging)?;
+                        while !failed.load(Ordering::SeqCst) {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            if index >= movies.len() {
+                                break;
+                            }
+                            current.store(index, Ordering::SeqCst);
+                            let assets = self
+                                .extract_movie(worker.as_ref(), &staging, movies, index, game)
+                                .inspect_err(|_| failed.store(true, Ordering::SeqCst))?;
+                            let now = top_level_files(&staging)?;
+                            let written = now.difference(&files).cloned().collect();
+                            files = now;
+                            done.push((index, staging.clone(), written, assets));
+                        }
+                        Ok(done)
+                    })
+                })
+                .collect();
+            // Join every worker, so one failing doesn't lose the others' errors
+            for (thread, current) in threads.into_iter().zip(&current) {
+                match thread.join() {
+                    Ok(Ok(done)) => extracted.extend(done),
+                    Ok(Err(e)) => errors.push(e),
+                    Err(_) => {
+                        failed.store(true, Ordering::SeqCst);
+                        let movie = &movies[current.load(Ordering::SeqCst)];
+                        errors.push(anyhow!(
+                            "Worker crashed while extracting {:?}",
+                            movie.file_name().unwrap()
+                        ));
+                    }
+                }
+            }
+        });
+        let mut errors = errors.into_iter();
+        if let Some(error) = errors.next() {
+            for other in errors {
+                println!("Warning: Another worker failed too: {:#}", other);
+            }
+            return Err(error);
+        }
+
+        extracted.sort_by_key(|(index, ..)| *index);
+        let mut assets = MovieAssets::default();
+        for (_, staging, written, movie) in extracted {
+            for name in written {
+                fs::rename(staging.join(&name), temp_dir.join(&name))
+                    .with_context(|| format!("Failed to move {:?} from a worker", name))?;
+            }
+            assets.extend(movie);
+        }
+        Ok(assets)
+    }
+
+    fn export_options(&self) -> ExportOptions {
+        ExportOptions {
+            raw_mp3: self.options.raw_mp3,
+            webp: self.options.compression,
+            image_scale: if self.options.upscale {
+                UPSCALE_FACTOR
+            } else {
+                1
+            },
+        }
+    }
+
+    /// Extracts the external casts the movies link to, each once however many
+    /// movies share it, and fills in where each link's members went.
+    fn extract_external_casts(&self, temp_dir: &Path, assets: &mut MovieAssets) -> Result<()> {
+        let options = self.export_options();
+        let mut extracted: HashMap<PathBuf, String> = HashMap::new();
+        let mut names = HashSet::new();
+        let mut shared = MovieAssets::default();
+        for link in &mut assets.external_casts {
+            let Some(cast_file) = native::find_cast_file(temp_dir, &link.file_path)? else {
+                println!(
+                    "Warning: External cast {:?} of {:?} not found ({})",
+                    link.cast, link.movie, link.file_path
+                );
+                continue;
+            };
+            let folder = match extracted.entry(cast_file.clone()) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => {
+                    self.report(Progress::ExtractingCast {
+                        file: &cast_file.file_name().unwrap().to_string_lossy(),
+                    });
+                    let name = native::shared_cast_name(&cast_file, &mut names);
+                    let (folder, cast_assets) =
+                        native::extract_shared_cast(temp_dir, &cast_file, &name, options)
+                            .with_context(|| {
+                                format!("Failed to extract external cast: {:?}", cast_file)
+                            })?;
+                    shared.extend(cast_assets);
+                    entry.insert(folder).clone()
+                }
+            };
+            link.file = cast_file
+                .strip_prefix(temp_dir)
+                .ok()
+                .map(|path| path.to_string_lossy().replace('\\', "/"));
+            link.folder = Some(folder);
+        }
+        assets.extend(shared);
+        Ok(())
+    }
+}
+
+/// Writes the files bundled in a projector to `output_dir`: movies and
+/// casts at the top, Xtras in `Xtras` like a game folder has them, with
+/// their names listed in `Xtras/xtras.txt`.
+pub fn unpack_projector(projector_path: &Path, output_dir: &Path) -> Result<()> {
+    let data = fs::read(projector_path)
+        .with_context(|| format!("Failed to read projector: {:?}", projector_path))?;
+    let projector = projector::unpack(&data)
+        .with_context(|| format!("Failed to unpack projector: {:?}", projector_path))?;
+    match &projector.version {
+        Some(version) => println!("Found {} projector", version),
+        None => println!("No projector header found, scanned for movies instead"),
+    }
+
+    let mut written = HashSet::new();
+    let mut xtras = Vec::new();
+    for (index, file) in projector.files.iter().enumerate() {
+        let dir = match file.kind {
+            BundledKind::Xtra => output_dir.join("Xtras"),
+            _ => output_dir.to_path_buf(),
+        };
+        let mut name = if file.name.is_empty() {
+            format!("movie{}.{}", index + 1, file.default_extension())
+        } else {
+            file.name.clone()
+        };
+        // Projectors can bundle files of the same name from different folders
+        if !written.insert(dir.join(&name).to_string_lossy().to_lowercase()) {
+            name = format!("{}-{}", index + 1, name);
+            written.insert(dir.join(&name).to_string_lossy().to_lowercase());
+        }
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(&name), file.data)
+            .with_context(|| format!("Failed to write {:?}", name))?;
+        println!(
+            "Unpacked: {:?} ({:?}, {} bytes)",
+            name,
+            file.kind,
+            file.data.len()
+        );
+        if file.kind == BundledKind::Xtra {
+            xtras.push(name);
+        }
+    }
+    if !xtras.is_empty() {
+        let mut list = xtras.join("\n");
+        list.push('\n');
+        // Kept out of the top folder, where the pipeline would take it for
+        // extracted text
+        fs::write(output_dir.join("Xtras").join("xtras.txt"), list)
+            .context("Failed to write xtras.txt")?;
+    }
+    Ok(())
+}
+
+/// The games cgex knows how to extract, from its built-in profiles.
+pub fn known_games() -> Vec<Box<dyn GameExtractor>> {
+    profile::builtin_profiles()
+        .into_iter()
+        .map(|profile| Box::new(profile) as Box<dyn GameExtractor>)
+        .collect()
+}
+
+/// Works out which of `games` a disc holds. Its movies are first matched by
+/// content against the editions the games know, then by name: the game
+/// expecting the most of them wins, the first one on a tie.
+pub fn detect_game(
+    input_dir: &Path,
+    platform: Platform,
+    mut games: Vec<Box<dyn GameExtractor>>,
+) -> Result<Detection> {
+    // Hashing a whole disc takes a while, so only when it can pay off
+    let hash = games.iter().any(|game| !game.get_editions().is_empty());
+    let movies = find_movies(input_dir, platform, hash)?;
+
+    if hash {
+        let fingerprints: Vec<MovieFile> = movies
+            .iter()
+            .filter_map(|(name, sha256)| {
+                Some(MovieFile {
+                    name: name.clone(),
+                    sha256: sha256.clone()?,
+                })
+            })
+            .collect();
+        if let Some(found) = fingerprint::match_edition(&games, &fingerprints) {
+            let game = games.swap_remove(found.game);
+            for file in &found.modified_files {
+                println!(
+                    "Warning: {} differs from the one in {} ({}). It may be modified.",
+                    file,
+                    game.get_name(),
+                    found.edition.name
+                );
+            }
+            for file in &found.unknown_files {
+                println!(
+                    "Warning: {} is not part of {} ({}).",
+                    file,
+                    game.get_name(),
+                    found.edition.name
+                );
+            }
+            return Ok(Detection {
+                game,
+                edition: Some(found.edition),
+                confidence: found.confidence,
+                modified_files: found.modified_files,
+                unknown_files: found.unknown_files,
+            });
+        }
+    }
+
+    let found_files: HashSet<String> = movies.into_iter().map(|(name, _)| name).collect();
+    let mut best_match: Option<(usize, Box<dyn GameExtractor>)> = None;
+    // A game matching as many files as the best one, like the other Mulle
+    // Meck title on a disc holding only the names they share
+    let mut tied: Option<String> = None;
+
+    for game in games {
+        let expected_files = game.get_expected_files();
+        let match_count = expected_files.intersection(&found_files).count();
+
+        if match_count > 0 {
+            if let Some((best_count, _)) = best_match {
+                if match_count > best_count {
+                    best_match = Some((match_count, game));
+                    tied = None;
+                } else if match_count == best_count {
+                    tied = Some(game.get_name().to_string());
+                }
+            } else {
+                best_match = Some((match_count, game));
+            }
+        }
+    }
+
+    if let Some((match_count, game)) = best_match {
+        let expected_files = game.get_expected_files();
+        let expected_count = expected_files.len();
+        if match_count < expected_count {
+            println!(
+                "Warning: Only found {} out of {} expected files for {}. Proceeding anyway.",
+                match_count,
+                expected_count,
+                game.get_name()
+            );
+        }
+        if let Some(other) = &tied {
+            println!(
+                "Warning: The movies fit {} as well as {}. Taking them for {}.",
+                game.get_name(),
+                other,
+                game.get_name()
+            );
+        }
+        if !game.get_editions().is_empty() {
+            println!(
+                "Warning: The movies match no known release of {}. They may be modified.",
+                game.get_name()
+            );
+        }
+        let mut unknown_files: Vec<String> =
+            found_files.difference(&expected_files).cloned().collect();
+        unknown_files.sort();
+        // Nothing tells the tied games apart, so it's a coin toss
+        let share = if tied.is_some() { 0.5 } else { 1.0 };
+        Ok(Detection {
+            game,
+            edition: None,
+            confidence: 0.5 * share * match_count as f32 / expected_count as f32,
+            modified_files: Vec::new(),
+            unknown_files,
+        })
+    } else {
+        bail!("Unable to detect game type. No matching .dir files found.")
+    }
+}
+
+/// The SHA-256 of every movie on a disc, by file name, for adding the disc
+/// to a game profile as an edition.
+pub fn fingerprint_movies(input_dir: &Path, platform: Platform) -> Result<Vec<MovieFile>> {
+    Ok(find_movies(input_dir, platform, true)?
+        .into_iter()
+        .filter_map(|(name, sha256)| {
+            Some(MovieFile {
+                name,
+                sha256: sha256?,
+            })
+        })
+        .collect())
+}
+
+/// Lists the movies on a disc or in a folder by lower case file name, with
+/// their SHA-256 if `hash` is set.
+fn find_movies(
+    input_dir: &Path,
+    platform: Platform,
+    hash: bool,
+) -> Result<Vec<(String, Option<String>)>> {
+    let lower_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+    };
+    let mut movies = Vec::new();
+    if iso::is_disc_image(input_dir) {
+        let mut image = DiscImage::open(input_dir)?;
+        match platform {
+            Platform::Windows => {
+                for file in image.files()? {
+                    let Some(name) = lower_name(&file.path).filter(|_| is_movie(&file.path)) else {
+                        continue;
+                    };
+                    let sha256 = hash.then(|| image.read(&file)).transpose()?;
+                    movies.push((name, sha256.as_deref().map(hash_bytes)));
+                }
+            }
+            Platform::Mac => {
+                let mut volume = HfsVolume::open(&mut image)?;
+                for file in volume.files()? {
+                    let path = file.export_path();
+                    let Some(name) = lower_name(&path).filter(|_| is_movie(&path)) else {
+                        continue;
+                    };
+                    let sha256 = hash.then(|| volume.read_data_fork(&file)).transpose()?;
+                    movies.push((name, sha256.as_deref().map(hash_bytes)));
+                }
+            }
+        }
+    } else {
+        for path in find_dir_files(input_dir)? {
+            let Some(name) = lower_name(&path) else {
+                continue;
+            };
+            let sha256 = hash.then(|| hash_file(&path)).transpose()?;
+            movies.push((name, sha256));
+        }
+    }
+    movies.sort();
+    Ok(movies)
+}
+
+fn find_dir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dir_files = Vec::new();
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                dir_files.extend(find_dir_files(&path)?);
+            } else if is_movie(&path) {
+                dir_files.push(path);
+            }
+        }
+    }
+    Ok(dir_files)
+}
+
+fn is_movie(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dir") || ext.eq_ignore_ascii_case("dxr"))
+}
+
+fn hash_bytes(data: &[u8]) -> String {
+    HEXUPPER.encode(digest(&SHA256, data).as_ref())
+}
+
+/// The SHA-256 of a file, in upper case hex.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).context("Failed to open file for hashing")?;
+    let mut context = DigestContext::new(&SHA256);
+    let mut buffer = [0; 8192];
+
+    loop {
+        let count = file.read(&mut buffer).context("Failed to read file")?;
+        if count == 0 {
+            break;
+        }
+        context.update(&buffer[..count]);
+    }
+
+    let digest = context.finish();
+    Ok(HEXUPPER.encode(digest.as_ref()))
+}
+
+/// Stops each worker, then removes its directory.
+fn remove_workers(workers: Vec<(PathBuf, Box<dyn ExtractorBackend>)>) {
+    for (dir, worker) in workers {
+        drop(worker);
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            println!("Warning: Failed to remove worker directory {:?}: {}", dir, e);
+        }
+    }
+}
+
+/// The names of the files at the top of `dir`, sorted.
+fn top_level_files(dir: &Path) -> Result<BTreeSet<String>> {
+    let mut files = BTreeSet::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.insert(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    Ok(files)
+}
+
+/// Deletes files whose content matches an earlier file. Returns the names
+/// of the deleted files, mapped to the name of the file that was kept.
+pub fn remove_duplicates(path: &Path) -> Result<HashMap<String, String>> {
+    let mut kept: HashMap<String, String> = HashMap::new();
+    let mut duplicates = HashMap::new();
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .context("Failed to read output directory")?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+
+    files.sort();
+
+    for path in files {
+        let hash = hash_file(&path)?;
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        if let Some(original) = kept.get(&hash) {
+            fs::remove_file(&path).context("Failed to remove duplicate file")?;
+            duplicates.insert(file_name, original.clone());
+        } else {
+            kept.insert(hash, file_name);
+        }
+    }
+    Ok(duplicates)
+}
+
+fn find_files(dir: &Path, extensions: &[&str]) -> Result<Vec<fs::DirEntry>> {
+    let mut files: Vec<fs::DirEntry> = fs::read_dir(dir)
+        .context("Failed to read directory")?
+        .filter_map(|res| res.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    extensions.iter().any(|&valid_ext| {
+                        ext.eq_ignore_ascii_case(valid_ext.trim_start_matches('.'))
+                    })
+                })
+        })
+        .collect();
+
+    files.sort_by_key(|dir| dir.path());
+    Ok(files)
+}
+
+/// Moves a file from the temp directory into the output directory's
+/// folder structure: `movie--cast__name.ext` becomes
+/// `movie/cast/name.ext`. `extension` replaces the file's own. Returns
+/// where the file ended up.
+pub fn move_file_to_output(
+    src_path: &Path,
+    output_dir: &Path,
+    extension: Option<&str>,
+) -> Result<PathBuf> {
+    let file_name = src_path
+        .file_name()
+        .and_then(|os_str| os_str.to_str())
+        .context("Invalid file name")?;
+
+    let parts: Vec<&str> = file_name.split("--").collect();
+    let mut dst_path = output_dir.to_path_buf();
+
+    if parts.len() > 1 {
+        dst_path.extend(&parts[..parts.len() - 1]);
+
+        let file_parts: Vec<&str> = parts.last().unwrap().split("__").collect();
+        if file_parts.len() > 1 {
+            dst_path.push(file_parts[0]);
+            let mut final_name = file_parts[1..].join("__");
+            if final_name.starts_with('-') {
+                final_name = final_name[1..].to_string();
+            }
+            dst_path.push(final_name);
+        } else {
+            dst_path.push(parts.last().unwrap());
+        }
+    } else {
+        dst_path.push(file_name);
+    }
+
+    if let Some(ext) = extension {
+        dst_path.set_extension(ext);
+    }
+
+    fs::create_dir_all(dst_path.parent().unwrap())?;
+    fs::rename(src_path, &dst_path)
+        .or_else(|_| fs::copy(src_path, &dst_path).map(|_| ()))
+        .with_context(|| format!("Failed to move file: {:?}", src_path))?;
+
+    Ok(dst_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory for one test's files, named after it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cgex_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes one file per movie, and fails or panics on the movies named.
+    #[derive(Clone, Copy)]
+    struct FakeBackend {
+        fail_on: &'static str,
+        crash_on: &'static str,
+    }
+
+    impl ExtractorBackend for FakeBackend {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        fn extract_movie(
+            &self,
+            temp_dir: &Path,
+            movie: &Path,
+            _game: &dyn GameExtractor,
+            _options: ExportOptions,
+        ) -> Result<MovieAssets> {
+            let name = movie.file_name().unwrap().to_string_lossy();
+            if name == self.fail_on {
+                bail!("Can't read {}", name);
+            }
+            if name == self.crash_on {
+                panic!("Crashed on {}", name);
+            }
+            let stem = name.split('.').next().unwrap();
+            fs::write(temp_dir.join(format!("{}--1__x.txt", stem)), &*name)?;
+            Ok(MovieAssets::default())
+        }
+    }
+
+    /// Runs `movies` through two workers with the given backend, returning
+    /// the files that ended up in the temp directory.
+    fn extract(name: &str, movies: &[&str], backend: FakeBackend) -> Result<BTreeSet<String>> {
+        let dir = test_dir(name);
+        let temp_dir = dir.join("temp");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let workers: Vec<(PathBuf, Box<dyn ExtractorBackend>)> = (0..2)
+            .map(|index| {
+                let worker = dir.join(format!("worker{}", index));
+                fs::create_dir_all(worker.join("staging")).unwrap();
+                (worker, Box::new(backend) as Box<dyn ExtractorBackend>)
+            })
+            .collect();
+        let movies: Vec<PathBuf> = movies.iter().map(PathBuf::from).collect();
+        let game = &profile::builtin_profiles()[0];
+        let pipeline = ExtractionPipeline::new(&dir, &dir);
+        let result = pipeline
+            .extract_in_parallel(&temp_dir, &movies, &workers, game)
+            .and_then(|_| top_level_files(&temp_dir));
+        fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn detects_mulle_meck_titles_by_their_own_files() {
+        let detect = |name: &str, files: &[&str]| {
+            let dir = test_dir(name);
+            for file in files {
+                fs::write(dir.join(file), b"").unwrap();
+            }
+            let detection = detect_game(&dir, Platform::Windows, known_games()).unwrap();
+            fs::remove_dir_all(&dir).unwrap();
+            (detection.game.get_name().to_string(), detection.confidence)
+        };
+        let shared = ["02.dxr", "03.dxr", "lbstart.dxr"];
+        let (boats, sure) = detect("detect_boats", &[&shared[..], &["01.dxr"]].concat());
+        assert_eq!(boats, "Bygg båtar med Mulle Meck");
+        let (cars, _) = detect("detect_cars", &[&shared[..], &["unload.dxr"]].concat());
+        assert_eq!(cars, "Bygg bilar med Mulle Meck");
+        // Only the names both have can't tell them apart
+        let (_, unsure) = detect("detect_shared", &shared);
+        assert!(unsure < sure / 2.0);
+    }
+
+    #[test]
+    fn reports_a_missing_input_first() {
+        let dir = test_dir("pipeline_missing_input");
+        let error = ExtractionPipeline::new(dir.join("disc_contents"), &dir)
+            .run()
+            .unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(error.to_string().contains("does not exist"), "{}", error);
+    }
+
+    #[test]
+    fn merges_the_workers_files() {
+        let movies = ["01.dxr", "02.dxr", "03.dxr"];
+        let backend = FakeBackend {
+            fail_on: "",
+            crash_on: "",
+        };
+        let files = extract("pipeline_workers", &movies, backend).unwrap();
+        assert_eq!(
+            files.into_iter().collect::<Vec<_>>(),
+            ["01--1__x.txt", "02--1__x.txt", "03--1__x.txt"]
+        );
+    }
+
+    #[test]
+    fn reports_failed_and_crashed_workers() {
+        let movies = ["01.dxr", "02.dxr"];
+        let backend = FakeBackend {
+            fail_on: "01.dxr",
+            crash_on: "",
+        };
+        let error = extract("pipeline_failed_worker", &movies, backend).unwrap_err();
+        assert!(format!("{:#}", error).contains("Can't read 01.dxr"));
+
+        let backend = FakeBackend {
+            fail_on: "",
+            crash_on: "02.dxr",
+        };
+        let error = extract("pipeline_crashed_worker", &movies, backend).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Worker crashed while extracting \"02.dxr\""
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Game profiles: everything cgex needs to know about a game, read from
+//! TOML so a new Director title can be supported without recompiling.
+//!
+//! The games cgex has always supported are built in as profiles too, from
+//! the `profiles` folder; see those for the format. Extra profiles are
+//! loaded with `--profile`.
+
+use crate::fingerprint::Edition;
+use crate::game_extractor::{self, GameExtractor};
+use anyhow::{bail, Context, Result};
+use image::ImageFormat;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const BUILTIN_PROFILES: [(&str, &str); 4] = [
+    (
+        "jonsson_mjolner.toml",
+        include_str!("../profiles/jonsson_mjolner.toml"),
+    ),
+    (
+        "jonsson_djupet.toml",
+        include_str!("../profiles/jonsson_djupet.toml"),
+    ),
+    ("mulle_bil.toml", include_str!("../profiles/mulle_bil.toml")),
+    ("mulle_bat.toml", include_str!("../profiles/mulle_bat.toml")),
+];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameProfile {
+    pub name: String,
+    /// The background colour of the game's bitmaps.
+    pub transparent_color: [u8; 3],
+    /// Movie file names the game is detected by, in any case.
+    pub expected_files: Vec<String>,
+    /// Bitmaps `dir_extractor.exe` writes broken, dropped by temp file
+    /// name.
+    #[serde(default)]
+    pub broken_images: Vec<String>,
+    /// Steps that turn the disc's layout into a flat temp folder with the
+    /// movies at the top and the Xtras in `Xtras`, in order.
+    #[serde(default)]
+    pub prepare: Vec<PrepareStep>,
+    #[serde(default)]
+    pub copy_images: Vec<ImageCopy>,
+    /// Known releases, to tell them apart by the SHA-256 of their movies.
+    /// `cgex fingerprint` prints one for a disc.
+    #[serde(default)]
+    pub editions: Vec<Edition>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PrepareStep {
+    /// Copies a folder's contents into the top folder. The folder's name is
+    /// matched ignoring case.
+    Merge {
+        folder: String,
+        #[serde(default)]
+        optional: bool,
+    },
+    /// Moves a folder's contents to another folder, e.g. `xtras` to the
+    /// `Xtras` the extractor looks in.
+    Rename {
+        from: String,
+        to: String,
+        #[serde(default)]
+        optional: bool,
+    },
+    Delete {
+        file: String,
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+/// Copies a processed image to a second name the game's movies refer to
+/// it by.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImageCopy {
+    /// Start of the temp file name of the image, before its extension.
+    pub prefix: String,
+    /// The file name to copy it to, without extension.
+    pub copy_as: String,
+}
+
+/// The profiles of the games cgex supports out of the box.
+pub fn builtin_profiles() -> Vec<GameProfile> {
+    BUILTIN_PROFILES
+        .iter()
+        .map(|(file, profile)| {
+            let profile: GameProfile = toml::from_str(profile)
+                .unwrap_or_else(|e| panic!("Built-in profile {} is invalid: {}", file, e));
+            profile
+                .check_paths()
+                .unwrap_or_else(|e| panic!("Built-in profile {} is invalid: {}", file, e));
+            profile
+        })
+        .collect()
+}
+
+pub fn load_profile(path: &Path) -> Result<GameProfile> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read game profile: {:?}", path))?;
+    let profile: GameProfile = toml::from_str(&text)
+        .with_context(|| format!("Invalid game profile: {:?}", path))?;
+    if profile.expected_files.is_empty() {
+        bail!("Game profile {:?} lists no expected files", path);
+    }
+    profile
+        .check_paths()
+        .with_context(|| format!("Invalid game profile: {:?}", path))?;
+    Ok(profile)
+}
+
+impl GameProfile {
+    /// Makes sure the files the profile moves, copies and deletes are all
+    /// inside the temp folder.
+    fn check_paths(&self) -> Result<()> {
+        let prepared = self.prepare.iter().flat_map(PrepareStep::paths);
+        let copies = self.copy_images.iter().map(|copy| copy.copy_as.as_str());
+        for path in prepared.chain(copies) {
+            let inside = Path::new(path)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+            if path.is_empty() || !inside {
+                bail!("Path {:?} is not inside the temp folder", path);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GameExtractor for GameProfile {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn prepare_temp_directory(&self, temp_dir: &Path) -> Result<()> {
+        for step in &self.prepare {
+            step.apply(temp_dir)?;
+        }
+        Ok(())
+    }
+
+    fn get_transparent_color(&self) -> [u8; 3] {
+        self.transparent_color
+    }
+
+    fn post_extraction_setup(
+        &self,
+        temp_dir: &Path,
+        processed_files: &[(PathBuf, ImageFormat)],
+    ) -> Result<()> {
+        for (temp_path, format) in processed_files {
+            let extension = format.extensions_str()[0];
+            let file_name = temp_path.file_name().unwrap().to_string_lossy();
+            for copy in &self.copy_images {
+                if file_name.starts_with(&copy.prefix) && temp_path.exists() {
+                    let new_path = temp_dir.join(format!("{}.{}", copy.copy_as, extension));
+                    fs::copy(temp_path, &new_path)
+                        .context(format!("Failed to copy file: {:?}", temp_path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_broken_images(&self) -> Vec<&str> {
+        self.broken_images.iter().map(String::as_str).collect()
+    }
+
+    fn get_expected_files(&self) -> HashSet<String> {
+        self.expected_files
+            .iter()
+            .map(|s| s.to_lowercase())
+            .collect()
+    }
+
+    fn get_editions(&self) -> &[Edition] {
+        &self.editions
+    }
+}
+
+impl PrepareStep {
+    /// The paths in the temp folder the step works on.
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            PrepareStep::Merge { folder, .. } => vec![folder],
+            PrepareStep::Rename { from, to, .. } => vec![from, to],
+            PrepareStep::Delete { file, .. } => vec![file],
+        }
+    }
+
+    fn apply(&self, temp_dir: &Path) -> Result<()> {
+        match self {
+            PrepareStep::Merge { folder, optional } => {
+                let Some(src) = find_folder(temp_dir, folder)? else {
+                    if *optional {
+                        return Ok(());
+                    }
+                    bail!("Folder {:?} to merge not found", folder);
+                };
+                game_extractor::copy_directory(&src, temp_dir)
+                    .with_context(|| format!("Failed to copy {:?} folder contents", folder))
+            }
+            PrepareStep::Rename { from, to, optional } => {
+                let src = temp_dir.join(from);
+                if !src.is_dir() && *optional {
+                    return Ok(());
+                }
+                game_extractor::copy_directory(&src, &temp_dir.join(to))
+                    .with_context(|| format!("Failed to copy {} folder contents to {}", from, to))?;
+                fs::remove_dir_all(&src).with_context(|| format!("Failed to remove {} folder", from))
+            }
+            PrepareStep::Delete { file, optional } => {
+                let path = temp_dir.join(file);
+                if !path.exists() && *optional {
+                    return Ok(());
+                }
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {}", file))
+            }
+        }
+    }
+}
+
+/// Finds a folder in `dir` by name, preferring an exact match over one
+/// that only differs in case.
+fn find_folder(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let exact = dir.join(name);
+    if exact.is_dir() {
+        return Ok(Some(exact));
+    }
+    Ok(fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|file_name| file_name.to_string_lossy().eq_ignore_ascii_case(name))
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A directory for one test's files, named after it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cgex_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const MINIMAL: &str = concat!(
+        "name = \"Test\"\n",
+        "transparent_color = [255, 0, 255]\n",
+        "expected_files = [\"START.DXR\", \"end.dxr\"]\n",
+    );
+
+    #[test]
+    fn builtin_profiles_are_valid() {
+        let profiles = builtin_profiles();
+        assert_eq!(profiles.len(), BUILTIN_PROFILES.len());
+        for profile in &profiles {
+            assert!(!profile.name.is_empty());
+            assert!(!profile.expected_files.is_empty(), "{}", profile.name);
+        }
+    }
+
+    #[test]
+    fn loads_profiles() {
+        let dir = test_dir("profile_load");
+        let path = dir.join("game.toml");
+        let text = format!(
+            "{}{}",
+            MINIMAL,
+            concat!(
+                "broken_images = [\"02--00__Dummy-2.bmp\"]\n",
+                "[[prepare]]\naction = \"merge\"\nfolder = \"data\"\noptional = true\n",
+                "[[prepare]]\naction = \"delete\"\nfile = \"junk.txt\"\n",
+                "[[copy_images]]\nprefix = \"04--\"\ncopy_as = \"map\"\n",
+            )
+        );
+        fs::write(&path, text).unwrap();
+        let profile = load_profile(&path).unwrap();
+        assert_eq!(profile.get_name(), "Test");
+        assert_eq!(profile.get_transparent_color(), [255, 0, 255]);
+        assert_eq!(
+            profile.get_expected_files(),
+            HashSet::from(["start.dxr".to_string(), "end.dxr".to_string()])
+        );
+        assert_eq!(profile.get_broken_images(), ["02--00__Dummy-2.bmp"]);
+        assert!(matches!(
+            &profile.prepare[..],
+            [
+                PrepareStep::Merge { optional: true, .. },
+                PrepareStep::Delete { .. }
+            ]
+        ));
+        assert_eq!(profile.copy_images[0].copy_as, "map");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_temp_folder() {
+        let dir = test_dir("profile_paths");
+        let path = dir.join("game.toml");
+        for step in [
+            "[[prepare]]\naction = \"merge\"\nfolder = \"../data\"\n",
+            "[[prepare]]\naction = \"rename\"\nfrom = \"xtras\"\nto = \"/tmp/Xtras\"\n",
+            "[[prepare]]\naction = \"rename\"\nfrom = \"data/../..\"\nto = \"Xtras\"\n",
+            "[[prepare]]\naction = \"delete\"\nfile = \"/etc/passwd\"\n",
+            "[[prepare]]\naction = \"delete\"\nfile = \"\"\n",
+            "[[copy_images]]\nprefix = \"04--\"\ncopy_as = \"../map\"\n",
+        ] {
+            fs::write(&path, format!("{}{}", MINIMAL, step)).unwrap();
+            let error = load_profile(&path).unwrap_err();
+            assert!(
+                format!("{:#}", error).contains("not inside the temp folder"),
+                "{}",
+                step
+            );
+        }
+
+        let step = "[[prepare]]\naction = \"rename\"\nfrom = \"./data/xtras\"\nto = \"Xtras\"\n";
+        fs::write(&path, format!("{}{}", MINIMAL, step)).unwrap();
+        assert!(load_profile(&path).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let dir = test_dir("profile_invalid");
+        let path = dir.join("game.toml");
+        let invalid = [
+            // Misspelt fields aren't ignored
+            format!("{}transparent_colour = [0, 0, 0]\n", MINIMAL),
+            format!("{}[[prepare]]\naction = \"copy\"\n", MINIMAL),
+            "name = \"Test\"\nexpected_files = [\"start.dxr\"]\n".to_string(),
+            "name = \"Test\"\ntransparent_color = [0, 0, 0]\nexpected_files = []\n".to_string(),
+        ];
+        for text in invalid {
+            fs::write(&path, &text).unwrap();
+            assert!(load_profile(&path).is_err(), "{}", text);
+        }
+        assert!(load_profile(&dir.join("missing.toml")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prepares_the_temp_directory() {
+        let dir = test_dir("profile_prepare");
+        fs::create_dir_all(dir.join("DATA/sub")).unwrap();
+        fs::write(dir.join("DATA/sub/02.dxr"), "movie").unwrap();
+        fs::create_dir_all(dir.join("xtras")).unwrap();
+        fs::write(dir.join("xtras/sound.x32"), "xtra").unwrap();
+        fs::write(dir.join("junk.txt"), "junk").unwrap();
+        let profile: GameProfile = toml::from_str(&format!(
+            "{}{}",
+            MINIMAL,
+            concat!(
+                "[[prepare]]\naction = \"merge\"\nfolder = \"data\"\n",
+                "[[prepare]]\naction = \"merge\"\nfolder = \"movies\"\noptional = true\n",
+                "[[prepare]]\naction = \"rename\"\nfrom = \"xtras\"\nto = \"Xtras\"\n",
+                "[[prepare]]\naction = \"delete\"\nfile = \"junk.txt\"\n",
+                "[[prepare]]\naction = \"delete\"\nfile = \"gone.txt\"\noptional = true\n",
+            )
+        ))
+        .unwrap();
+        profile.prepare_temp_directory(&dir).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("sub/02.dxr")).unwrap(), "movie");
+        assert!(dir.join("Xtras/sound.x32").is_file());
+        assert!(!dir.join("junk.txt").exists());
+
+        // Steps that aren't optional fail when there's nothing to do
+        let missing: GameProfile = toml::from_str(&format!(
+            "{}[[prepare]]\naction = \"merge\"\nfolder = \"movies\"\n",
+            MINIMAL
+        ))
+        .unwrap();
+        assert!(missing.prepare_temp_directory(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copies_images_by_prefix() {
+        let dir = test_dir("profile_copy_images");
+        let image = dir.join("04--12__Map.png");
+        fs::write(&image, "png").unwrap();
+        let profile: GameProfile = toml::from_str(&format!(
+            "{}[[copy_images]]\nprefix = \"04--\"\ncopy_as = \"map\"\n",
+            MINIMAL
+        ))
+        .unwrap();
+        profile
+            .post_extraction_setup(&dir, &[(image, ImageFormat::Png)])
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("map.png")).unwrap(), "png");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,429 @@
+//! The extraction report: what happened to each movie `dir_extractor.exe`
+//! was run on. The extractor's output and the dialogs the watchdog
+//! dismissed are collected per movie, and the files a movie exported are
+//! checked against the members it holds. That way a movie that exported
+//! nothing can be told from one without bitmaps, and the members that
+//! weren't exported are listed by cast and name.
+
+use crate::director::{CastLib, CastMember, MemberType, Movie};
+use crate::native::is_text;
+use crate::watchdog::PlayerError;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+pub const REPORT_FILE: &str = "extraction_report.json";
+
+#[derive(Serialize)]
+pub struct MovieReport {
+    pub movie: String,
+    /// `None` if the extractor was killed.
+    pub exit_code: Option<i32>,
+    /// The files the movie exported, by kind.
+    pub exported: MemberCounts,
+    /// The members of those kinds the movie holds, if cgex could read it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<MemberCounts>,
+    pub issues: Vec<Issue>,
+    /// The extractor's output, for what the issues don't cover.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stdout: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+}
+
+/// Counts of the members `dir_extractor.exe` exports: bitmaps, sounds, and
+/// fields and text as `.txt`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MemberCounts {
+    pub bitmaps: usize,
+    pub sounds: usize,
+    pub texts: usize,
+}
+
+#[derive(Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// The cast library and number of the member the issue is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cast: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member_name: Option<String>,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The SharpExport Xtra didn't load, so no bitmaps were exported.
+    SharpExportFailed,
+    /// The movie threw a Director Player Error dialog.
+    PlayerError,
+    /// The extractor was killed for taking too long.
+    TimedOut,
+    /// The extractor exited with an error.
+    Crashed,
+    /// The movie has members to export but no file was written.
+    NothingExported,
+    /// Fewer files were written than the movie has members.
+    Incomplete,
+    /// No file was written for this member.
+    NotExported,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)?;
+        if let (Some(cast), Some(member)) = (&self.cast, self.member) {
+            write!(f, " (cast {:?}, member {}", cast, member)?;
+            if let Some(name) = &self.member_name {
+                write!(f, " {:?}", name)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl MovieReport {
+    /// Builds the report of one extractor run on `movie`, a movie in
+    /// `temp_dir`.
+    pub fn new(
+        temp_dir: &Path,
+        movie: &Path,
+        output: &Output,
+        player_errors: &[PlayerError],
+        timed_out: bool,
+    ) -> MovieReport {
+        let name = movie
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let parsed = Movie::open(movie).ok();
+
+        let mut issues = Vec::new();
+        for error in player_errors {
+            // Lingo's alert uses the same dialog, so the messages the
+            // extractor shows that way are told apart by their text
+            let issue = if error.text.is_empty() {
+                Issue::new(
+                    IssueKind::PlayerError,
+                    "Director Player Error, its text wasn't copied".to_string(),
+                )
+            } else {
+                alert_issue(&error.text)
+                    .unwrap_or_else(|| Issue::new(IssueKind::PlayerError, error.text.clone()))
+            };
+            issues.push(issue);
+        }
+        if timed_out {
+            issues.push(Issue::new(
+                IssueKind::TimedOut,
+                "Killed for taking too long".to_string(),
+            ));
+        } else if !output.status.success() {
+            issues.push(Issue::new(
+                IssueKind::Crashed,
+                format!("Extractor exited with {}", output.status),
+            ));
+        }
+
+        let prefix = export_prefix(movie);
+        let exported = count_exported(temp_dir, &prefix);
+        let members = parsed.as_ref().map(count_members);
+        let nothing = MemberCounts::default();
+        match members {
+            Some(members) if exported == nothing && members != nothing => issues.push(Issue::new(
+                IssueKind::NothingExported,
+                format!("Nothing exported from {}", members),
+            )),
+            None if exported == nothing => issues.push(Issue::new(
+                IssueKind::NothingExported,
+                "Nothing exported".to_string(),
+            )),
+            Some(members)
+                if exported.bitmaps < members.bitmaps
+                    || exported.sounds < members.sounds
+                    || exported.texts < members.texts =>
+            {
+                issues.push(Issue::new(
+                    IssueKind::Incomplete,
+                    format!("Exported {} of {}", exported, members),
+                ))
+            }
+            _ => {}
+        }
+        if let Some(parsed) = &parsed {
+            if exported != nothing {
+                issues.extend(missing_members(temp_dir, &prefix, &parsed.casts));
+            }
+        }
+
+        MovieReport {
+            movie: name,
+            exit_code: output.status.code(),
+            exported,
+            members,
+            issues,
+            stdout,
+            stderr,
+        }
+    }
+}
+
+impl Issue {
+    fn new(kind: IssueKind, text: String) -> Issue {
+        Issue {
+            kind,
+            cast: None,
+            member: None,
+            member_name: None,
+            text,
+        }
+    }
+}
+
+impl fmt::Display for MemberCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} bitmaps, {} sounds and {} texts",
+            self.bitmaps, self.sounds, self.texts
+        )
+    }
+}
+
+/// Reads the text of a dialog `dir_extractor.lingo` shows with `alert`,
+/// the ones it doesn't being Player Errors.
+fn alert_issue(text: &str) -> Option<Issue> {
+    text.contains("SharpExport initialization failed").then(|| {
+        Issue::new(
+            IssueKind::SharpExportFailed,
+            "SharpExport initialization failed".to_string(),
+        )
+    })
+}
+
+/// Counts the files exported from a movie, named starting with `prefix`.
+/// The start of the names of the files `dir_extractor.exe` writes for a
+/// movie: the Lingo `item 1 of movie.name`, which ends at the first dot.
+fn export_prefix(movie: &Path) -> String {
+    let name = movie.file_name().unwrap_or_default().to_string_lossy();
+    format!("{}--", name.split('.').next().unwrap_or_default())
+}
+
+fn count_exported(temp_dir: &Path, prefix: &str) -> MemberCounts {
+    let mut counts = MemberCounts::default();
+    let Ok(entries) = fs::read_dir(temp_dir) else {
+        return counts;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if !name.starts_with(&prefix.to_lowercase()) {
+            continue;
+        }
+        match Path::new(&name).extension().and_then(|e| e.to_str()) {
+            Some("bmp") => counts.bitmaps += 1,
+            Some("wav") => counts.sounds += 1,
+            Some("txt") => counts.texts += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Counts the members `dir_extractor.lingo` exports, the way it picks them:
+/// `#bitmap`, `#sound`, `#field` and `#text`.
+fn count_members(movie: &Movie) -> MemberCounts {
+    let mut counts = MemberCounts::default();
+    for member in movie.casts.iter().flat_map(|cast| &cast.members) {
+        match member.member_type {
+            MemberType::Bitmap => counts.bitmaps += 1,
+            MemberType::Sound => counts.sounds += 1,
+            _ if is_text(member) => counts.texts += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// The file `dir_extractor.lingo` writes for a member, after `prefix`, or
+/// `None` for members it doesn't export. Sounds aren't numbered, and
+/// unnamed ones are named after their number instead.
+pub(crate) fn expected_file(prefix: &str, cast: &CastLib, member: &CastMember) -> Option<String> {
+    let numbered = |extension| {
+        format!(
+            "{}{}__{}-{}.{}",
+            prefix, cast.name, member.name, member.number, extension
+        )
+    };
+    match member.member_type {
+        MemberType::Bitmap => Some(numbered("bmp")),
+        MemberType::Sound if member.name.is_empty() => {
+            Some(format!("{}{}__{}.wav", prefix, cast.name, member.number))
+        }
+        MemberType::Sound => Some(format!("{}{}__{}.wav", prefix, cast.name, member.name)),
+        _ if is_text(member) => Some(numbered("txt")),
+        _ => None,
+    }
+}
+
+/// Lists the members of `casts` whose file wasn't written, by cast and
+/// name. Why isn't known: the shipped extractor doesn't report the Xtras'
+/// error codes.
+fn missing_members(temp_dir: &Path, prefix: &str, casts: &[CastLib]) -> Vec<Issue> {
+    let written: HashSet<String> = fs::read_dir(temp_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+        .collect();
+    let mut issues = Vec::new();
+    for cast in casts {
+        for member in &cast.members {
+            let Some(file) = expected_file(prefix, cast, member) else {
+                continue;
+            };
+            if written.contains(&file.to_lowercase()) {
+                continue;
+            }
+            issues.push(Issue {
+                kind: IssueKind::NotExported,
+                cast: Some(cast.name.clone()),
+                member: Some(member.number),
+                member_name: (!member.name.is_empty()).then(|| member.name.clone()),
+                text: format!("No {} file written", member.member_type.name()),
+            });
+        }
+    }
+    issues
+}
+
+/// Writes the reports to `extraction_report.json` in the output directory.
+pub fn write_report(output_dir: &Path, reports: &[MovieReport]) -> Result<()> {
+    #[derive(Serialize)]
+    struct Report<'a> {
+        movies: &'a [MovieReport],
+    }
+
+    fs::write(
+        output_dir.join(REPORT_FILE),
+        serde_json::to_string_pretty(&Report { movies: reports })?,
+    )
+    .context("Failed to write extraction report")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_extractors_alerts() {
+        let issue = alert_issue("SharpExport initialization failed").unwrap();
+        assert_eq!(issue.kind, IssueKind::SharpExportFailed);
+        assert_eq!(issue.to_string(), "SharpExport initialization failed");
+        assert!(alert_issue("Script error: Handler not defined #foo").is_none());
+        assert!(alert_issue("").is_none());
+    }
+
+    #[test]
+    fn counts_a_movies_exported_files() {
+        let dir = std::env::temp_dir().join(format!("cgex_test_report_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "intro--Internal__bil-1.bmp",
+            "intro--Internal__bil-2.bmp",
+            "Intro--Internal__motor.wav",
+            "intro--Internal__skylt.txt",
+            "intro2--Internal__bil-1.bmp",
+            "intro.dxr",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let counts = count_exported(&dir, "intro--");
+        assert_eq!(
+            counts,
+            MemberCounts {
+                bitmaps: 2,
+                sounds: 1,
+                texts: 1
+            }
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prefixes_files_with_the_name_up_to_the_first_dot() {
+        assert_eq!(export_prefix(Path::new("data/intro.dxr")), "intro--");
+        assert_eq!(export_prefix(Path::new("data/intro.v2.dxr")), "intro--");
+        assert_eq!(export_prefix(Path::new("data/intro")), "intro--");
+    }
+
+    fn member(number: u32, member_type: MemberType, name: &str) -> CastMember {
+        CastMember {
+            number,
+            section_id: 0,
+            member_type,
+            name: name.to_string(),
+            script_id: 0,
+            script_text: String::new(),
+            specific_data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn lists_the_members_not_exported() {
+        let dir = std::env::temp_dir().join(format!("cgex_test_missing_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "intro--Internal__bil-1.bmp",
+            "INTRO--Internal__Motor.wav",
+            "intro--Internal__3.wav",
+            "intro--Internal__skylt-5.txt",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let mut rich_text = member(6, MemberType::Xtra, "rubrik");
+        rich_text.specific_data = [4u32.to_be_bytes().as_slice(), b"TEXT"].concat();
+        let casts = [CastLib {
+            id: 1024,
+            name: "Internal".to_string(),
+            file_path: String::new(),
+            members: vec![
+                member(1, MemberType::Bitmap, "bil"),
+                member(2, MemberType::Sound, "motor"),
+                member(3, MemberType::Sound, ""),
+                member(4, MemberType::Bitmap, "hjul"),
+                member(5, MemberType::RichText, "skylt"),
+                rich_text,
+                member(7, MemberType::Script, "start"),
+            ],
+        }];
+        let issues = missing_members(&dir, "intro--", &casts);
+        let missing: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.kind, issue.member, issue.member_name.as_deref()))
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                (IssueKind::NotExported, Some(4), Some("hjul")),
+                (IssueKind::NotExported, Some(6), Some("rubrik")),
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "No bitmap file written (cast \"Internal\", member 4 \"hjul\")"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//! Mac resource forks, as written next to the data fork when reading the
+//! Mac volume of a disc.
+//!
+//! A fork starts with the offsets of its data and its map. The map lists
+//! each resource type with the references of its resources: their id,
+//! name and where their data is. Every resource's data is prefixed with
+//! its length.
+
+use crate::director::{decode_string, Endian, Reader};
+use anyhow::Result;
+
+/// Marks a resource without a name.
+const NO_NAME: u16 = 0xffff;
+
+pub struct Resource<'a> {
+    pub resource_type: [u8; 4],
+    pub id: i16,
+    pub name: Option<String>,
+    pub data: &'a [u8],
+}
+
+/// Lists the resources in a resource fork, in map order.
+pub fn read_resources(fork: &[u8]) -> Result<Vec<Resource<'_>>> {
+    if fork.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut reader = Reader::new(fork, Endian::Big);
+    let data_start = reader.read_u32()? as usize;
+    let map_start = reader.read_u32()? as usize;
+    reader.seek(map_start + 24)?;
+    let type_list = map_start + reader.read_u16()? as usize;
+    let name_list = map_start + reader.read_u16()? as usize;
+
+    reader.seek(type_list)?;
+    // Counts are stored minus one, so an empty list has all bits set
+    let type_count = reader.read_u16()?.wrapping_add(1);
+    let mut resources = Vec::new();
+    for index in 0..type_count as usize {
+        reader.seek(type_list + 2 + index * 8)?;
+        let resource_type: [u8; 4] = reader.read_bytes(4)?.try_into()?;
+        let count = reader.read_u16()? as usize + 1;
+        let references = type_list + reader.read_u16()? as usize;
+        for reference in 0..count {
+            reader.seek(references + reference * 12)?;
+            let id = reader.read_u16()? as i16;
+            let name_offset = reader.read_u16()?;
+            reader.skip(1)?; // attributes
+            let data_offset = (reader.read_u8()? as usize) << 16 | reader.read_u16()? as usize;
+
+            let mut data = Reader::new(fork, Endian::Big);
+            data.seek(data_start + data_offset)?;
+            let len = data.read_u32()? as usize;
+            let data = data.read_bytes(len)?;
+            let name = if name_offset == NO_NAME {
+                None
+            } else {
+                let mut names = Reader::new(fork, Endian::Big);
+                names.seek(name_list + name_offset as usize)?;
+                let len = names.read_u8()? as usize;
+                Some(decode_string(names.read_bytes(len)?, Endian::Big))
+            };
+            resources.push(Resource {
+                resource_type,
+                id,
+                name,
+                data,
+            });
+        }
+    }
+    Ok(resources)
+}
+
+/// The resource type as text, e.g. `snd `.
+pub fn type_name(resource: &Resource) -> String {
+    decode_string(&resource.resource_type, Endian::Big)
+}
+
+/// Maps bitmap font resource ids to their family name and point size,
+/// from the font family (`FOND`) resources. Old `FONT` resources don't
+/// need it: their id is the family's times 128 plus the size, and the
+/// family's name is on the size 0 resource.
+pub fn font_families(resources: &[Resource]) -> Vec<(i16, String, u16)> {
+    let mut fonts = Vec::new();
+    for family in resources.iter().filter(|r| &r.resource_type == b"FOND") {
+        let name = family.name.clone().unwrap_or_default();
+        let mut reader = Reader::new(family.data, Endian::Big);
+        // The association table follows the 52-byte family record
+        let Ok(count) = reader.seek(52).and_then(|_| reader.read_u16()) else {
+            continue;
+        };
+        for _ in 0..=count as usize {
+            let Ok(entry) = reader.read_bytes(6) else {
+                break;
+            };
+            // Size, style, then the font's resource id
+            let size = u16::from_be_bytes([entry[0], entry[1]]);
+            let id = i16::from_be_bytes([entry[4], entry[5]]);
+            fonts.push((id, name.clone(), size));
+        }
+    }
+    fonts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A resource's type, id, name and data.
+    type Stored<'a> = (&'a [u8; 4], i16, Option<&'a str>, Vec<u8>);
+
+    /// Builds a resource fork holding `resources`, each of a type of its
+    /// own.
+    fn fork(resources: &[Stored]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut names = Vec::new();
+        let mut types = ((resources.len() as u16).wrapping_sub(1))
+            .to_be_bytes()
+            .to_vec();
+        let mut references = Vec::new();
+        let references_start = 2 + resources.len() * 8;
+        for (resource_type, id, name, bytes) in resources {
+            types.extend(*resource_type);
+            types.extend(0u16.to_be_bytes());
+            types.extend(((references_start + references.len()) as u16).to_be_bytes());
+            references.extend(id.to_be_bytes());
+            match name {
+                Some(name) => {
+                    references.extend((names.len() as u16).to_be_bytes());
+                    names.push(name.len() as u8);
+                    names.extend(name.as_bytes());
+                }
+                None => references.extend(NO_NAME.to_be_bytes()),
+            }
+            references.push(0);
+            references.extend(&(data.len() as u32).to_be_bytes()[1..]);
+            references.extend([0; 4]);
+            data.extend((bytes.len() as u32).to_be_bytes());
+            data.extend(bytes);
+        }
+        let data_start = 256;
+        let map_start = data_start + data.len();
+        let type_list = 28;
+        let name_list = type_list + types.len() + references.len();
+        let mut fork = vec![0; data_start];
+        fork[..4].copy_from_slice(&(data_start as u32).to_be_bytes());
+        fork[4..8].copy_from_slice(&(map_start as u32).to_be_bytes());
+        fork.extend(data);
+        fork.extend([0; 24]);
+        fork.extend((type_list as u16).to_be_bytes());
+        fork.extend((name_list as u16).to_be_bytes());
+        fork.extend(types);
+        fork.extend(references);
+        fork.extend(names);
+        fork
+    }
+
+    #[test]
+    fn reads_resources_with_and_without_names() {
+        let fork = fork(&[
+            (b"snd ", 128, Some("Klick"), b"abc".to_vec()),
+            (b"PICT", -4000, None, vec![1, 2]),
+        ]);
+        let resources = read_resources(&fork).unwrap();
+        assert_eq!(resources.len(), 2);
+        assert_eq!(type_name(&resources[0]), "snd ");
+        assert_eq!(resources[0].id, 128);
+        assert_eq!(resources[0].name.as_deref(), Some("Klick"));
+        assert_eq!(resources[0].data, b"abc");
+        assert_eq!(resources[1].id, -4000);
+        assert_eq!(resources[1].name, None);
+        assert_eq!(resources[1].data, [1, 2]);
+    }
+
+    #[test]
+    fn reads_empty_forks() {
+        assert!(read_resources(&[]).unwrap().is_empty());
+        assert!(read_resources(&fork(&[])).unwrap().is_empty());
+        // A reference past the end of the data
+        let mut fork = fork(&[(b"snd ", 128, None, b"abc".to_vec())]);
+        fork[256..260].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(read_resources(&fork).is_err());
+    }
+
+    #[test]
+    fn lists_font_family_members() {
+        let mut family = vec![0; 52];
+        family.extend(0u16.to_be_bytes());
+        family.extend([0, 12, 0, 0, 0x04, 0xd2]);
+        let fork = fork(&[(b"FOND", 5, Some("Chicago"), family)]);
+        let resources = read_resources(&fork).unwrap();
+        assert_eq!(
+            font_families(&resources),
+            [(1234, "Chicago".to_string(), 12)]
+        );
+    }
+}
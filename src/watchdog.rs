@@ -0,0 +1,324 @@
+//! Runs `dir_extractor.exe` under Wine and watches over it. Movies that
+//! throw Director Player Error dialogs stop until the dialog is closed, so
+//! the watchdog looks for those windows with xdotool, and closes the ones
+//! belonging to the extractor's own processes, never another program's.
+//! On a private display their text is copied out first, through the
+//! clipboard, to be reported with the movie; on the user's own display
+//! the clipboard is left alone. A movie that runs past its timeout has the
+//! whole Wine process tree started for it killed.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The title of the dialogs Director shows for script errors.
+const DIALOG_TITLE: &str = "Director Player Error";
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Separates the caption, text and buttons of a message box copied with
+/// Ctrl+C.
+const COPY_SEPARATOR: &str = "---------------------------";
+
+/// A Director Player Error dialog a movie threw.
+#[derive(Clone, Debug, Serialize)]
+pub struct PlayerError {
+    pub movie: String,
+    /// The dialog's message on one line, e.g. `Script error: Handler not
+    /// defined #foo`. Empty if it couldn't be copied, or wasn't because the
+    /// dialog was on the user's display.
+    pub text: String,
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.text.is_empty() {
+            write!(f, "{} threw a {} dialog", self.movie, DIALOG_TITLE)
+        } else {
+            write!(
+                f,
+                "{} threw a {} dialog: {}",
+                self.movie, DIALOG_TITLE, self.text
+            )
+        }
+    }
+}
+
+/// How a watched extractor run went.
+pub struct Watched {
+    pub output: Output,
+    /// The dialogs that were dismissed, in the order they appeared.
+    pub player_errors: Vec<PlayerError>,
+    /// Whether the run was killed for taking longer than the timeout.
+    pub timed_out: bool,
+}
+
+/// Runs `command`, the extractor for `movie`, dismissing its Player Error
+/// dialogs and killing it after `timeout`. `display` is a private display
+/// the extractor runs on; with `None` the dialogs are looked for on the
+/// inherited `DISPLAY` and dismissed without copying their text.
+pub fn run(
+    mut command: Command,
+    movie: &str,
+    display: Option<&str>,
+    timeout: Duration,
+) -> Result<Watched> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run extractor with Wine")?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let mut handled = HashSet::new();
+    let mut player_errors = Vec::new();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            timed_out = true;
+            kill_tree(&mut child);
+            break child.wait()?;
+        }
+        let tree = process_tree(child.id());
+        for window in find_dialogs(display) {
+            if !window_pid(display, &window).is_some_and(|pid| tree.contains(&pid)) {
+                continue;
+            }
+            // A dialog still open on the next poll is dismissed again, but
+            // only reported once
+            if handled.insert(window.clone()) {
+                player_errors.push(PlayerError {
+                    movie: movie.to_string(),
+                    text: display
+                        .and_then(|display| copy_text(display, &window))
+                        .unwrap_or_default(),
+                });
+            }
+            // Sent to the dialog itself, so it can't land in whatever
+            // window has the focus on the user's display
+            xdotool(display, &["key", "--window", &window, "Return"]);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Watched {
+        output: Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        },
+        player_errors,
+        timed_out,
+    })
+}
+
+/// Reads a pipe to the end on its own thread, so the extractor never
+/// blocks on a full one.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut data);
+        }
+        data
+    })
+}
+
+/// Whether `tool` is in one of the `PATH` folders.
+pub fn is_installed(tool: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(tool).is_file()))
+}
+
+/// Warns about the tools the watchdog needs that aren't installed, since
+/// without them it fails quietly on every poll.
+pub fn check_tools() {
+    if !is_installed("xdotool") {
+        println!(
+            "Warning: xdotool is not installed, so {} dialogs won't be closed and movies that throw them will run until --movie-timeout",
+            DIALOG_TITLE
+        );
+    } else if !is_installed("xclip") || !is_installed("Xvfb") {
+        println!(
+            "Warning: xclip or Xvfb is not installed, so the messages of {} dialogs won't be reported",
+            DIALOG_TITLE
+        );
+    }
+}
+
+/// Runs xdotool, returning its output if it succeeded.
+fn xdotool(display: Option<&str>, args: &[&str]) -> Option<String> {
+    let mut command = Command::new("xdotool");
+    command.args(args);
+    if let Some(display) = display {
+        command.env("DISPLAY", display);
+    }
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Window ids of the open Player Error dialogs, anyone's.
+fn find_dialogs(display: Option<&str>) -> Vec<String> {
+    let title = format!("^{}$", DIALOG_TITLE);
+    xdotool(display, &["search", "--name", &title])
+        .map(|ids| ids.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The process that owns a window, from its `_NET_WM_PID`. Wine sets it
+/// to the Unix process of the program that opened the window.
+fn window_pid(display: Option<&str>, window: &str) -> Option<u32> {
+    xdotool(display, &["getwindowpid", window])?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Copies a dialog's message with Ctrl+C, which message boxes answer by
+/// putting their caption, text and buttons on the clipboard, and reads it
+/// back with xclip. Only for private displays, whose clipboard nobody
+/// else uses.
+fn copy_text(display: &str, window: &str) -> Option<String> {
+    xdotool(
+        Some(display),
+        &["windowfocus", "--sync", window, "key", "ctrl+c"],
+    )?;
+    let output = Command::new("xclip")
+        .args(["-selection", "clipboard", "-o"])
+        .env("DISPLAY", display)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    dialog_text(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads the message out of a message box copied with Ctrl+C, on one line
+/// for the warning. `None` if the clipboard holds something else.
+fn dialog_text(copied: &str) -> Option<String> {
+    let copied = copied.replace('\r', "");
+    let sections: Vec<&str> = copied
+        .split(COPY_SEPARATOR)
+        .map(str::trim)
+        .filter(|section| !section.is_empty())
+        .collect();
+    match sections[..] {
+        [DIALOG_TITLE, text, ..] => Some(text.split_whitespace().collect::<Vec<_>>().join(" ")),
+        _ => None,
+    }
+}
+
+/// `root` and every process descended from it. Only Linux has `/proc` to
+/// find them in; elsewhere it is just `root`.
+fn process_tree(root: u32) -> HashSet<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+                continue;
+            };
+            let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            if let Some(parent) = stat_parent(&stat) {
+                children.entry(parent).or_default().push(pid);
+            }
+        }
+    }
+    let mut tree = HashSet::new();
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        if tree.insert(pid) {
+            pending.extend(children.get(&pid).into_iter().flatten());
+        }
+    }
+    tree
+}
+
+/// The parent pid in a `/proc/<pid>/stat` line. It follows the state,
+/// after the parenthesised name, which can itself contain spaces and
+/// parentheses.
+fn stat_parent(stat: &str) -> Option<u32> {
+    stat.rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().nth(1))
+        .and_then(|parent| parent.parse().ok())
+}
+
+/// Kills the extractor and every process it started.
+fn kill_tree(child: &mut Child) {
+    let pids: Vec<String> = process_tree(child.id())
+        .into_iter()
+        .filter(|&pid| pid != child.id())
+        .map(|pid| pid.to_string())
+        .collect();
+    let _ = child.kill();
+    if !pids.is_empty() {
+        let _ = Command::new("kill").arg("-KILL").args(&pids).status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_tools_on_the_path() {
+        assert!(is_installed("sh"));
+        assert!(!is_installed("cgex-no-such-tool"));
+    }
+
+    #[test]
+    fn reads_copied_dialogs() {
+        let copied = "---------------------------\r\nDirector Player Error\r\n---------------------------\r\nScript error: Handler not\r\ndefined\r\n\r\n#foo\r\n---------------------------\r\nOK   \r\n---------------------------\r\n";
+        assert_eq!(
+            dialog_text(copied).as_deref(),
+            Some("Script error: Handler not defined #foo")
+        );
+        // Without the buttons, as some Wine versions copy it
+        let copied = "---------------------------\nDirector Player Error\n---------------------------\nOut of memory\n";
+        assert_eq!(dialog_text(copied).as_deref(), Some("Out of memory"));
+        // Whatever else is on the clipboard
+        assert_eq!(dialog_text("Script error: Handler not defined #foo"), None);
+        assert_eq!(
+            dialog_text(
+                "---------------------------\nOther dialog\n---------------------------\ntext\n"
+            ),
+            None
+        );
+        assert_eq!(dialog_text(""), None);
+    }
+
+    #[test]
+    fn reads_the_parent_from_proc_stat() {
+        assert_eq!(
+            stat_parent("4242 (wine) S 4200 4242 4200 0 -1 4194560 1234 0"),
+            Some(4200)
+        );
+        assert_eq!(
+            stat_parent("4243 (dir extractor.exe) R 4242 4242 4200 0 -1"),
+            Some(4242)
+        );
+        // A name with parentheses of its own, and a closing one
+        assert_eq!(
+            stat_parent("4244 (a) S 1 (b)) S 4243 4242 4200"),
+            Some(4243)
+        );
+        assert_eq!(stat_parent("4245 (wine"), None);
+        assert_eq!(stat_parent("4245 (wine) S"), None);
+        assert_eq!(stat_parent(""), None);
+    }
+}